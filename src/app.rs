@@ -1,23 +1,53 @@
+use crate::commands::Completion;
 use crate::config::Config;
+use crate::keymap::{Keymap, Resolution, key_matches};
+use crate::line_edit::{EditResult, LineEditor};
+use crate::bulk_rename::BulkRename;
+use crate::checksum::Checksums;
+use crate::dir_sizes::DirSizer;
+use crate::du::DiskUsage;
+use crate::dupes::DupeView;
+use crate::cleanup::CleanupView;
+use crate::archive::ArchiveJob;
+use crate::archive_view::ArchiveMount;
+use crate::extract::ExtractJob;
+use crate::sftp::{self, SftpFs};
+use crate::share::{ShareJob, SharePrompt};
+use crate::pattern_rename::PatternRename;
+#[cfg(unix)]
+use crate::properties::Properties;
+#[cfg(unix)]
+use crate::xattr::XattrPanel;
+use crate::rename::NamePart;
+use crate::undo::UndoEntry;
+use crate::vfs::{self, FileSystem, LocalFs};
+use crate::tags::{TagDb, split_query};
+use crate::macros::{MacroAwait, load_macros};
+use crate::palette::{Palette, load_palette_recent};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::widgets::ListState;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use sysinfo::Disks;
 
+const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+const MAX_COUNT: usize = 9999;
+
 #[derive(PartialEq)]
 pub enum InputMode {
     Normal,
     Editing,
     Search,
     Renaming,
+    Command,
 }
 
 #[derive(PartialEq, Clone)]
@@ -25,12 +55,15 @@ pub enum Focus {
     FileList,
     DriveList,
     Favorites,
+    Tags,
 }
 
 #[derive(PartialEq, Clone)]
 pub enum ClipboardOp {
     Copy,
     Cut,
+    /// Перенос распакованного архива; в буфер обмена не попадает
+    Extract,
 }
 
 #[derive(PartialEq, Clone, Copy, Default)]
@@ -49,17 +82,24 @@ pub enum ConflictAction {
     Cancel,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct CachedMeta {
     pub is_dir: bool,
+    /// У папок — сумма по поддереву, когда она посчитана
     pub size: u64,
+    pub size_known: bool,
     pub modified: Option<SystemTime>,
+    /// Цель символьной ссылки как записана (для `name -> target`)
+    pub link_target: Option<PathBuf>,
+    /// Ссылка указывает в никуда
+    pub broken: bool,
 }
 
 pub struct App {
     pub current_dir: PathBuf,
     pub items: Vec<PathBuf>,
     pub meta_cache: HashMap<PathBuf, CachedMeta>,
+    pub dir_sizer: DirSizer,
     pub filtered_items: Vec<PathBuf>,
     pub state: ListState,
 
@@ -68,8 +108,14 @@ pub struct App {
     pub focus: Focus,
 
     pub input_mode: InputMode,
-    pub input_buffer: String,
-    pub search_query: String,
+    pub create_input: LineEditor,
+    /// Шаблоны из `<config dir>/templates` и выбранный для строки создания
+    pub templates: Vec<PathBuf>,
+    pub create_template: Option<usize>,
+    pub rename_input: LineEditor,
+    pub command_input: LineEditor,
+    pub completion: Option<Completion>,
+    pub search_query: LineEditor,
     pub message: String,
     pub preview_content: String,
     pub file_to_edit: Option<PathBuf>,
@@ -79,23 +125,71 @@ pub struct App {
 
     pub favorites: Vec<PathBuf>,
     pub favorites_state: ListState,
+    pub tags: TagDb,
+    pub tags_state: ListState,
+    pub collapsed_tags: HashSet<String>,
 
     pub show_statusbar: bool,
     pub show_help: bool,
     pub help_scroll: u16,
+    pub help_query: String,
+    pub palette: Option<Palette>,
+    pub palette_recent: Vec<String>,
 
     pub confirm_delete: bool,
     pub pending_delete: Option<PathBuf>,
+    /// Пути, которые подтверждение удаления переносит в корзину (отчёт очистки)
+    pub pending_trash: Vec<PathBuf>,
 
     pub sort_mode: SortMode,
+    pub sort_ascending: bool,
+    pub show_hidden: bool,
     pub left_panel_pct: u16,
     pub center_panel_pct: u16,
 
+    pub bulk_rename: Option<BulkRename>,
+    pub pattern_rename: Option<PatternRename>,
+    #[cfg(unix)]
+    pub properties: Option<Properties>,
+    #[cfg(unix)]
+    pub xattrs: Option<XattrPanel>,
+    pub checksums: Option<Checksums>,
+    /// Архив, который сейчас пишется в фоне
+    pub archive: Option<ArchiveJob>,
+    /// Архив, который сейчас распаковывается в фоне
+    pub extract: Option<ExtractJob>,
+    /// Открытые архивы, вложенные — после внешних
+    pub archives: Vec<ArchiveMount>,
+    /// Открытые sftp-серверы, по одному соединению на хост
+    pub remotes: Vec<SftpFs>,
+    /// HTTP-раздача, пока её не остановят
+    pub share: Option<ShareJob>,
+    /// Окно с адресом и QR-кодом раздачи
+    pub share_popup: bool,
+    pub share_prompt: Option<SharePrompt>,
+    /// Диск, с которым работает список; архивы и серверы перекрывают его своими путями
+    pub fs: Box<dyn FileSystem>,
+    pub dupes: Option<DupeView>,
+    pub cleanup: Option<CleanupView>,
+    pub disk_usage: Option<DiskUsage>,
+    pub undo_stack: Vec<UndoEntry>,
+
     pub conflict_src: Option<PathBuf>,
     pub conflict_dest: Option<PathBuf>,
     pub conflict_paste_queue: Option<(Vec<PathBuf>, ClipboardOp, usize)>,
 
     pub config: Config,
+    pub keymap: Keymap,
+    pub pending_keys: Vec<KeyEvent>,
+    pub pending_since: Option<Instant>,
+    pub pending_count: Option<usize>,
+    pub should_quit: bool,
+
+    pub macros: BTreeMap<char, Vec<KeyEvent>>,
+    pub macro_recording: Option<(char, Vec<KeyEvent>)>,
+    pub macro_await: Option<MacroAwait>,
+    pub macro_depth: usize,
+    pub last_macro: Option<char>,
     pub update_available: Option<String>,
     pub update_checker: Arc<Mutex<Option<String>>>,
 }
@@ -109,52 +203,107 @@ impl App {
 
         let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
-        let favorites = load_favorites();
+        let mut app = App::with_config(config, current_dir);
+        app.message = msg;
+        app.favorites = load_favorites();
+        app.tags = TagDb::load();
+        app.palette_recent = load_palette_recent();
+        app.macros = load_macros();
+
+        app.refresh_items();
+        app.refresh_drives();
+        app.spawn_update_checker();
+        app
+    }
+
+    /// Приложение без сохранённого состояния: не читает файлы, не обходит диск и сеть
+    pub fn with_config(config: Config, current_dir: PathBuf) -> App {
+        let keymap = Keymap::from_config(&config.keys);
 
-        let mut app = App {
+        App {
             current_dir,
             items: Vec::new(),
             meta_cache: HashMap::new(),
+            dir_sizer: DirSizer::default(),
             filtered_items: Vec::new(),
             state: ListState::default(),
             drives: Vec::new(),
             drive_state: ListState::default(),
             focus: Focus::FileList,
             input_mode: InputMode::Normal,
-            input_buffer: String::new(),
-            search_query: String::new(),
-            message: msg,
+            create_input: LineEditor::default(),
+            templates: Vec::new(),
+            create_template: None,
+            rename_input: LineEditor::default(),
+            command_input: LineEditor::default(),
+            completion: None,
+            search_query: LineEditor::default(),
+            message: String::new(),
             preview_content: String::new(),
             file_to_edit: None,
             clipboard: None,
             selected_indices: HashSet::new(),
-            favorites,
+            favorites: Vec::new(),
             favorites_state: ListState::default(),
+            tags: TagDb::default(),
+            tags_state: ListState::default(),
+            collapsed_tags: HashSet::new(),
             show_statusbar: true,
             show_help: false,
             help_scroll: 0,
+            help_query: String::new(),
+            palette: None,
+            palette_recent: Vec::new(),
             confirm_delete: false,
             pending_delete: None,
+            pending_trash: Vec::new(),
             sort_mode: SortMode::Name,
+            sort_ascending: true,
+            show_hidden: true,
             left_panel_pct: 20,
             center_panel_pct: 40,
+            bulk_rename: None,
+            pattern_rename: None,
+            #[cfg(unix)]
+            properties: None,
+            #[cfg(unix)]
+            xattrs: None,
+            checksums: None,
+            archive: None,
+            extract: None,
+            archives: Vec::new(),
+            remotes: Vec::new(),
+            share: None,
+            share_popup: false,
+            share_prompt: None,
+            fs: Box::new(LocalFs),
+            dupes: None,
+            cleanup: None,
+            disk_usage: None,
+            undo_stack: Vec::new(),
             conflict_src: None,
             conflict_dest: None,
             conflict_paste_queue: None,
             config,
+            keymap,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            pending_count: None,
+            should_quit: false,
+            macros: BTreeMap::new(),
+            macro_recording: None,
+            macro_await: None,
+            macro_depth: 0,
+            last_macro: None,
             update_available: None,
             update_checker: Arc::new(Mutex::new(None)),
-        };
-
-        app.refresh_items();
-        app.refresh_drives();
-        app.spawn_update_checker();
-        app
+        }
     }
 
     pub fn reload_config(&mut self) {
         match Config::load() {
             Ok(new_config) => {
+                self.keymap = Keymap::from_config(&new_config.keys);
                 self.config = new_config;
                 self.message = "Config reloaded successfully!".to_string();
             }
@@ -167,76 +316,105 @@ impl App {
     pub fn refresh_items(&mut self) {
         self.items.clear();
         self.meta_cache.clear();
-        if let Ok(entries) = fs::read_dir(&self.current_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                let is_dir = path.is_dir();
-                if let Ok(meta) = fs::metadata(&path) {
-                    self.meta_cache.insert(path.clone(), CachedMeta {
-                        is_dir,
-                        size: meta.len(),
-                        modified: meta.modified().ok(),
-                    });
-                }
-                self.items.push(path);
+        self.dir_sizer.entered(&self.current_dir);
+        self.prune_archives();
+        let fs = self.fs_in(&self.current_dir);
+        let entries = match fs.entries(&self.current_dir) {
+            Ok(entries) => entries,
+            // Ошибки локального диска молча дают пустой список; сервер или архив — объясняем
+            Err(e) if !fs.is_local() => {
+                self.message = format!("Cannot list {}: {}", self.current_dir.display(), e);
+                Vec::new()
             }
-        }
-
-        match self.sort_mode {
-            SortMode::Name => {
-                self.items.sort_by(|a, b| {
-                    let a_is_dir = self.meta_cache.get(a).is_some_and(|m| m.is_dir);
-                    let b_is_dir = self.meta_cache.get(b).is_some_and(|m| m.is_dir);
-                    if a_is_dir == b_is_dir {
-                        a.file_name().cmp(&b.file_name())
-                    } else if a_is_dir {
-                        std::cmp::Ordering::Less
-                    } else {
-                        std::cmp::Ordering::Greater
-                    }
-                });
-            }
-            SortMode::Size => {
-                self.items.sort_by(|a, b| {
-                    let a_is_dir = self.meta_cache.get(a).is_some_and(|m| m.is_dir);
-                    let b_is_dir = self.meta_cache.get(b).is_some_and(|m| m.is_dir);
-                    if a_is_dir != b_is_dir {
-                        return if a_is_dir { std::cmp::Ordering::Less } else { std::cmp::Ordering::Greater };
-                    }
-                    let a_size = self.meta_cache.get(a).map(|m| m.size).unwrap_or(0);
-                    let b_size = self.meta_cache.get(b).map(|m| m.size).unwrap_or(0);
-                    b_size.cmp(&a_size)
-                });
+            Err(_) => Vec::new(),
+        };
+        let mut listed = Vec::new();
+        for (path, meta) in entries {
+            if !self.show_hidden && path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')) {
+                continue;
             }
-            SortMode::Date => {
-                self.items.sort_by(|a, b| {
-                    let a_is_dir = self.meta_cache.get(a).is_some_and(|m| m.is_dir);
-                    let b_is_dir = self.meta_cache.get(b).is_some_and(|m| m.is_dir);
-                    if a_is_dir != b_is_dir {
-                        return if a_is_dir { std::cmp::Ordering::Less } else { std::cmp::Ordering::Greater };
-                    }
-                    let a_date = self.meta_cache.get(a).and_then(|m| m.modified);
-                    let b_date = self.meta_cache.get(b).and_then(|m| m.modified);
-                    b_date.cmp(&a_date)
-                });
+            let meta = meta.map(|meta| {
+                // Размер папки — если его знает провайдер или уже посчитали
+                let known = if meta.is_dir { meta.size.or_else(|| self.dir_sizer.known.get(&path).copied()) } else { meta.size };
+                CachedMeta {
+                    is_dir: meta.is_dir,
+                    size: known.unwrap_or(0),
+                    size_known: known.is_some(),
+                    modified: meta.modified,
+                    link_target: meta.link_target,
+                    broken: meta.broken,
+                }
+            });
+            listed.push((path, meta));
+        }
+        for (path, meta) in listed {
+            if let Some(meta) = meta {
+                self.meta_cache.insert(path.clone(), meta);
             }
+            self.items.push(path);
         }
 
+        self.sort_items();
         self.search_query.clear();
         self.selected_indices.clear();
         self.update_search();
+        self.auto_dir_sizes();
+    }
+
+    fn sort_items(&mut self) {
+        let mode = self.sort_mode;
+        let ascending = self.sort_ascending;
+        self.items.sort_by(|a, b| {
+            let a_meta = self.meta_cache.get(a);
+            let b_meta = self.meta_cache.get(b);
+            let a_is_dir = a_meta.is_some_and(|m| m.is_dir);
+            let b_is_dir = b_meta.is_some_and(|m| m.is_dir);
+            if a_is_dir != b_is_dir {
+                return if a_is_dir { std::cmp::Ordering::Less } else { std::cmp::Ordering::Greater };
+            }
+            let ord = match mode {
+                SortMode::Name => a.file_name().cmp(&b.file_name()),
+                SortMode::Size => {
+                    let a_size = a_meta.map(|m| m.size).unwrap_or(0);
+                    let b_size = b_meta.map(|m| m.size).unwrap_or(0);
+                    a_size.cmp(&b_size)
+                }
+                SortMode::Date => {
+                    let a_date = a_meta.and_then(|m| m.modified);
+                    let b_date = b_meta.and_then(|m| m.modified);
+                    a_date.cmp(&b_date)
+                }
+            };
+            if ascending { ord } else { ord.reverse() }
+        });
+    }
+
+    /// Пересортировка без перечитывания: курсор и выделение остаются на тех же файлах
+    pub fn resort_items(&mut self) {
+        let highlighted = self.state.selected().and_then(|i| self.filtered_items.get(i).cloned());
+        let marked: HashSet<PathBuf> = self.selected_indices.iter().filter_map(|&i| self.filtered_items.get(i).cloned()).collect();
+        self.sort_items();
+        self.update_search();
+        self.selected_indices = self.filtered_items.iter().enumerate().filter(|(_, p)| marked.contains(*p)).map(|(i, _)| i).collect();
+        if let Some(i) = highlighted.and_then(|h| self.filtered_items.iter().position(|p| *p == h)) {
+            self.state.select(Some(i));
+            self.update_preview();
+        }
     }
 
     pub fn update_search(&mut self) {
         if self.search_query.is_empty() {
             self.filtered_items = self.items.clone();
         } else {
+            // `tag:имя` отбирает по тегам, остальное — нечёткий поиск по имени
+            let (tags, text) = split_query(self.search_query.text());
             self.filtered_items = self
                 .items
                 .iter()
                 .filter(|path| {
                     if let Some(name) = path.file_name() {
-                        fuzzy_match(&name.to_string_lossy(), &self.search_query)
+                        tags.iter().all(|tag| self.tags.has(path, tag))
+                            && (text.is_empty() || fuzzy_match(&name.to_string_lossy(), &text))
                     } else {
                         false
                     }
@@ -267,32 +445,41 @@ impl App {
         if let Some(selected) = self.state.selected()
             && let Some(path) = self.filtered_items.get(selected) {
                 let is_dir = self.meta_cache.get(path).is_some_and(|m| m.is_dir);
-                if is_dir {
-                    self.preview_content = format!("Directory: {}\n\nContains:", path.display());
-                    if let Ok(entries) = fs::read_dir(path) {
-                        for (i, entry) in entries.flatten().enumerate() {
+                let fs = self.fs_for(path);
+                let text = if is_dir {
+                    let mut text = format!("Directory: {}\n\nContains:", path.display());
+                    if let Ok(entries) = fs.list(path) {
+                        for (i, entry) in entries.iter().enumerate() {
                             if i > 20 {
-                                self.preview_content.push_str("\n...and more...");
+                                text.push_str("\n...and more...");
                                 break;
                             }
-                            let name = entry.file_name().to_string_lossy().to_string();
-                            self.preview_content.push_str(&format!("\n- {}", name));
+                            let name = entry.file_name().unwrap_or_default().to_string_lossy().to_string();
+                            text.push_str(&format!("\n- {}", name));
                         }
                     }
+                    text
                 } else {
-                    match fs::File::open(path) {
-                        Ok(mut file) => {
-                            let mut buffer = [0; 4096];
-                            if let Ok(n) = file.read(&mut buffer) {
-                                let text = String::from_utf8_lossy(&buffer[..n]);
-                                let content = text.to_string();
-                                self.preview_content = content.lines().take(60).collect::<Vec<_>>().join("\n");
+                    match fs.read(path) {
+                        Ok(file) => {
+                            let mut buffer = Vec::new();
+                            if file.take(4096).read_to_end(&mut buffer).is_ok() {
+                                let content = String::from_utf8_lossy(&buffer);
+                                content.lines().take(60).collect::<Vec<_>>().join("\n")
                             } else {
-                                self.preview_content = "Binary/Unreadable".to_string();
+                                "Binary/Unreadable".to_string()
                             }
                         }
-                        Err(e) => self.preview_content = format!("Error: {}", e),
+                        Err(e) => format!("Error: {}", e),
                     }
+                };
+                self.preview_content = text;
+                // Отметка о расширенных атрибутах над содержимым
+                #[cfg(unix)]
+                if self.fs_for(path).is_local()
+                    && let Some(summary) = crate::xattr::summary(path)
+                {
+                    self.preview_content = format!("⚑ {}\n\n{}", summary, self.preview_content);
                 }
             } else {
                 self.preview_content = "No file selected\n——————————————\nNavigate with j/k   Open with l/Enter\nSearch with /       Copy with y, Paste with p\nFavorites with f    Delete with D\n\nPress ? for help".to_string();
@@ -315,6 +502,8 @@ impl App {
             SortMode::Size => SortMode::Date,
             SortMode::Date => SortMode::Name,
         };
+        // Имя — по алфавиту, размер и дата — от больших/новых
+        self.sort_ascending = self.sort_mode == SortMode::Name;
         let label = match self.sort_mode {
             SortMode::Name => "Name",
             SortMode::Size => "Size",
@@ -324,6 +513,19 @@ impl App {
         self.refresh_items();
     }
 
+    pub fn set_sort(&mut self, mode: SortMode, ascending: bool) {
+        self.sort_mode = mode;
+        self.sort_ascending = ascending;
+        let label = match self.sort_mode {
+            SortMode::Name => "Name",
+            SortMode::Size => "Size",
+            SortMode::Date => "Date",
+        };
+        let dir = if ascending { "asc" } else { "desc" };
+        self.message = format!("Sort by: {} ({})", label, dir);
+        self.refresh_items();
+    }
+
     // --- UPDATE CHECKER ---
     pub fn spawn_update_checker(&self) {
         let checker = self.update_checker.clone();
@@ -402,8 +604,11 @@ impl App {
         self.paste_next(paths, op, 0);
     }
 
-    fn paste_next(&mut self, paths: Vec<PathBuf>, op: ClipboardOp, idx: usize) {
+    pub fn paste_next(&mut self, paths: Vec<PathBuf>, op: ClipboardOp, idx: usize) {
         if idx >= paths.len() {
+            if op == ClipboardOp::Extract {
+                return self.finish_extract(Some(paths.len()));
+            }
             self.message = format!("Pasted {} item(s).", paths.len());
             if op == ClipboardOp::Cut {
                 self.clipboard = None;
@@ -418,13 +623,16 @@ impl App {
             Some(n) => n.to_os_string(),
             None => return self.paste_next(paths, op, idx + 1),
         };
-        let dest = self.current_dir.join(&file_name);
+        let dest = match (&op, &self.extract) {
+            (ClipboardOp::Extract, Some(job)) => job.target.join(&file_name),
+            _ => self.current_dir.join(&file_name),
+        };
 
         if dest == *src {
             return self.paste_next(paths, op, idx + 1);
         }
 
-        if dest.exists() {
+        if self.fs_for(&dest).exists(&dest) {
             self.conflict_src = Some(src.clone());
             self.conflict_dest = Some(dest);
             self.conflict_paste_queue = Some((paths, op, idx));
@@ -435,21 +643,25 @@ impl App {
             return;
         }
 
-        let result = match op {
-            ClipboardOp::Copy => copy_recursive(src, &dest),
-            ClipboardOp::Cut => fs::rename(src, &dest).map_err(|e| e.to_string()),
-        };
-        if let Err(e) = result {
-            self.message = format!("Paste error: {}", e);
-        }
+        let src = src.clone();
+        self.do_paste_one(&src, &dest, &op);
         self.paste_next(paths, op, idx + 1);
     }
 
-    fn do_paste_one(&mut self, src: &PathBuf, dest: &PathBuf, op: &ClipboardOp) {
+    fn do_paste_one(&mut self, src: &Path, dest: &Path, op: &ClipboardOp) {
+        let (src_fs, dest_fs) = (self.fs_for(src), self.fs_for(dest));
         let result = match op {
-            ClipboardOp::Copy => copy_recursive(src, dest),
-            ClipboardOp::Cut => fs::rename(src, dest).map_err(|e| e.to_string()),
+            ClipboardOp::Copy => vfs::copy_recursive(src_fs, src, dest_fs, dest, self.config.copy_xattrs),
+            // Между провайдерами переименовать нельзя — копия и удаление
+            ClipboardOp::Cut if src_fs.id() != dest_fs.id() => {
+                vfs::copy_recursive(src_fs, src, dest_fs, dest, self.config.copy_xattrs)
+                    .and_then(|_| src_fs.remove(src).map_err(|e| e.to_string()))
+            }
+            ClipboardOp::Cut | ClipboardOp::Extract => src_fs.rename(src, dest).map_err(|e| e.to_string()),
         };
+        if result.is_ok() && *op == ClipboardOp::Cut {
+            self.tags_moved(&[(src.to_path_buf(), dest.to_path_buf())]);
+        }
         if let Err(e) = result {
             self.message = format!("Paste error: {}", e);
         }
@@ -467,7 +679,7 @@ impl App {
 
         match action {
             ConflictAction::Overwrite => {
-                let _ = if dest.is_dir() { fs::remove_dir_all(&dest) } else { fs::remove_file(&dest) };
+                let _ = self.fs_for(&dest).remove(&dest);
                 self.do_paste_one(&src, &dest, &op);
                 self.paste_next(paths, op, idx + 1);
             }
@@ -476,11 +688,16 @@ impl App {
                 self.paste_next(paths, op, idx + 1);
             }
             ConflictAction::RenameAuto => {
-                let new_dest = find_available_name(&dest);
+                let fs = self.fs_for(&dest);
+                let new_dest = find_available_name_by(&dest, |p| fs.exists(p));
                 self.do_paste_one(&src, &new_dest, &op);
                 self.paste_next(paths, op, idx + 1);
             }
             ConflictAction::Cancel => {
+                if op == ClipboardOp::Extract {
+                    self.message = "Extraction cancelled.".to_string();
+                    return self.finish_extract(None);
+                }
                 self.message = "Paste cancelled.".to_string();
                 if op == ClipboardOp::Cut {
                     self.clipboard = None;
@@ -531,7 +748,9 @@ impl App {
     pub fn open_favorite(&mut self) {
         if let Some(selected) = self.favorites_state.selected()
             && let Some(path) = self.favorites.get(selected).cloned() {
-                if path.is_dir() {
+                if sftp::is_remote(&path) {
+                    self.open_remote(&path.to_string_lossy());
+                } else if path.is_dir() {
                     self.current_dir = path;
                     self.refresh_items();
                     self.focus = Focus::FileList;
@@ -554,126 +773,32 @@ impl App {
                 .draw(|f| crate::ui::render(f, self))
                 .map_err(|e| io::Error::other(e.to_string()))?;
 
-            if let Event::Key(key) = event::read()? {
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
+            self.tick();
 
-                // --- GLOBAL: HOT RELOAD ---
-                if key.code == KeyCode::F(5) || key_matches(&key, &self.config.keys.reload) {
-                    self.reload_config();
-                    continue;
-                }
-
-                // --- GLOBAL: TOGGLE STATUSBAR (Ctrl+B) ---
-                if key.code == KeyCode::Char('b') && key.modifiers.contains(KeyModifiers::CONTROL) {
-                    self.show_statusbar = !self.show_statusbar;
-                    continue;
-                }
-
-                // --- GLOBAL: TOGGLE HELP POPUP (?) ---
-                if key.code == KeyCode::Char('?') {
-                    self.show_help = !self.show_help;
-                    if self.show_help {
-                        self.help_scroll = 0;
-                    }
-                    continue;
-                }
-
-                // Esc закрывает help попап
-                if key.code == KeyCode::Esc && self.show_help {
-                    self.show_help = false;
-                    self.help_scroll = 0;
-                    continue;
-                }
-
-                // --- HELP SCROLL ---
-                if self.show_help {
-                    match key.code {
-                        KeyCode::Up => self.help_scroll = self.help_scroll.saturating_sub(1),
-                        KeyCode::Down => self.help_scroll = self.help_scroll.saturating_add(1),
-                        _ => {}
-                    }
-                    continue;
-                }
-
-                // --- CONFLICT RESOLUTION ---
-                if self.conflict_src.is_some() {
-                    match key.code {
-                        KeyCode::Char('o') | KeyCode::Char('O') => {
-                            self.resolve_conflict(ConflictAction::Overwrite);
-                        }
-                        KeyCode::Char('s') | KeyCode::Char('S') => {
-                            self.resolve_conflict(ConflictAction::Skip);
-                        }
-                        KeyCode::Char('r') | KeyCode::Char('R') => {
-                            self.resolve_conflict(ConflictAction::RenameAuto);
-                        }
-                        KeyCode::Esc => {
-                            self.resolve_conflict(ConflictAction::Cancel);
-                        }
-                        _ => {}
-                    }
-                    continue;
-                }
+            // Опрос с таймаутом, чтобы which-key и фоновые результаты появлялись без нажатий
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
 
-                // --- DELETE CONFIRMATION ---
-                if self.confirm_delete {
-                    match key.code {
-                        KeyCode::Char('y') | KeyCode::Char('Y') => {
-                            self.execute_delete();
-                        }
-                        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                            self.confirm_delete = false;
-                            self.pending_delete = None;
-                            self.message = "Delete cancelled.".to_string();
-                        }
-                        _ => {}
-                    }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
                     continue;
                 }
 
-                // --- GLOBAL: FOCUS SWITCH ---
-                if key.code == KeyCode::Tab {
-                    self.focus = match self.focus {
-                        Focus::FileList => Focus::DriveList,
-                        Focus::DriveList => Focus::Favorites,
-                        Focus::Favorites => Focus::FileList,
-                    };
-                    continue;
-                }
-                if key_matches(&key, &self.config.keys.focus_drives) {
-                    self.focus = Focus::DriveList;
-                    continue;
+                // Запись макроса: клавиша, остановившая запись, в неё не попадает; пароль тоже
+                let was_recording = self.macro_recording.is_some() && self.share_prompt.is_none();
+                self.handle_key(key);
+                if was_recording {
+                    self.record_key(&key);
                 }
-                if key_matches(&key, &self.config.keys.focus_files) {
-                    self.focus = Focus::FileList;
-                    continue;
-                }
-
-                match self.input_mode {
-                    InputMode::Normal => {
-                        if key_matches(&key, &self.config.keys.quit) {
-                            write_cwd(&self.current_dir);
-                            return Ok(());
-                        }
 
-                        if key_matches(&key, &self.config.keys.search) {
-                            self.input_mode = InputMode::Search;
-                            self.search_query.clear();
-                            self.update_search();
-                            continue;
-                        }
-
-                        match self.focus {
-                            Focus::FileList => self.handle_file_list_input(key),
-                            Focus::DriveList => self.handle_drive_list_input(key),
-                            Focus::Favorites => self.handle_favorites_input(key),
-                        }
+                if self.should_quit {
+                    // С сервера оболочка остаётся, где была
+                    let dir = self.real_dir();
+                    if !sftp::is_remote(&dir) {
+                        write_cwd(&dir);
                     }
-                    InputMode::Editing => self.handle_editing_input(key),
-                    InputMode::Search => self.handle_search_input(key),
-                    InputMode::Renaming => self.handle_renaming_input(key),
+                    return Ok(());
                 }
             }
 
@@ -705,197 +830,379 @@ impl App {
                 );
                 let _ = terminal.hide_cursor();
                 let _ = terminal.clear();
+                if self.bulk_rename.as_ref().is_some_and(|b| b.file == path) {
+                    self.finish_bulk_edit();
+                }
                 self.update_preview();
             }
         }
     }
 
-    fn handle_file_list_input(&mut self, key: KeyEvent) {
-        if key_matches(&key, &self.config.keys.down) || key.code == KeyCode::Down {
-            self.next_item();
-        } else if key_matches(&key, &self.config.keys.up) || key.code == KeyCode::Up {
-            self.previous_item();
-        } else if key.code == KeyCode::Char(' ') {
-            self.toggle_selection();
-        } else if key.code == KeyCode::Left && key.modifiers.contains(KeyModifiers::SHIFT) {
-            self.resize_center(-5);
-        } else if key.code == KeyCode::Right && key.modifiers.contains(KeyModifiers::SHIFT) {
-            self.resize_center(5);
-        } else if key_matches(&key, &self.config.keys.submit) || key.code == KeyCode::Enter || key.code == KeyCode::Right {
-            if let Some(selected) = self.state.selected()
-                && let Some(path) = self.filtered_items.get(selected) {
-                    let path = path.clone();
-                    if path.is_dir() {
-                        self.current_dir = path;
-                        self.refresh_items();
-                    }
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        // --- MACRO REGISTER ---
+        if let Some(action) = self.macro_await.take() {
+            self.macro_register_key(action, key);
+            return;
+        }
+
+        // --- HELP POPUP ---
+        if self.show_help {
+            match key.code {
+                // Esc сначала очищает поиск, потом закрывает
+                KeyCode::Esc if !self.help_query.is_empty() => {
+                    self.help_query.clear();
+                    self.help_scroll = 0;
                 }
-        } else if key_matches(&key, &self.config.keys.back_dir) || key.code == KeyCode::Backspace || key.code == KeyCode::Left {
-            if let Some(parent) = self.current_dir.parent() {
-                self.current_dir = parent.to_path_buf();
-                self.refresh_items();
+                KeyCode::Esc => {
+                    self.show_help = false;
+                    self.help_scroll = 0;
+                }
+                KeyCode::Up => self.help_scroll = self.help_scroll.saturating_sub(1),
+                KeyCode::Down => self.help_scroll = self.help_scroll.saturating_add(1),
+                KeyCode::PageUp => self.help_scroll = self.help_scroll.saturating_sub(10),
+                KeyCode::PageDown => self.help_scroll = self.help_scroll.saturating_add(10),
+                _ if self.help_query.is_empty() && self.keymap.lookup(&key) == Some("help") => {
+                    self.show_help = false;
+                }
+                KeyCode::Backspace => {
+                    self.help_query.pop();
+                    self.help_scroll = 0;
+                }
+                KeyCode::Char(c) => {
+                    self.help_query.push(c);
+                    self.help_scroll = 0;
+                }
+                _ => {}
             }
-        } else if key_matches(&key, &self.config.keys.create) {
-            self.input_mode = InputMode::Editing;
-        } else if key_matches(&key, &self.config.keys.delete) {
-            self.delete_prompt();
-        } else if key_matches(&key, &self.config.keys.rename) {
-            self.start_rename();
-        } else if key_matches(&key, &self.config.keys.edit) {
-            if !self.selected_indices.is_empty() {
-                let paths = self.selected_paths();
-                if let Some(path) = paths.into_iter().find(|p| !p.is_dir()) {
-                    self.file_to_edit = Some(path);
+            return;
+        }
+
+        // --- COMMAND PALETTE ---
+        if self.palette.is_some() {
+            self.handle_palette_input(key);
+            return;
+        }
+
+        // --- CONFLICT RESOLUTION ---
+        if self.conflict_src.is_some() {
+            match key.code {
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    self.resolve_conflict(ConflictAction::Overwrite);
                 }
-            } else if let Some(selected) = self.state.selected()
-                && let Some(path) = self.filtered_items.get(selected)
-                    && !path.is_dir() {
-                        self.file_to_edit = Some(path.clone());
-                    }
-        } else if key_matches(&key, &self.config.keys.sort) {
-            self.cycle_sort_mode();
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    self.resolve_conflict(ConflictAction::Skip);
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    self.resolve_conflict(ConflictAction::RenameAuto);
+                }
+                KeyCode::Esc => {
+                    self.resolve_conflict(ConflictAction::Cancel);
+                }
+                _ => {}
+            }
+            return;
         }
-        // --- CLIPBOARD ---
-        else if key.code == KeyCode::Char('y') {
-            self.copy_item();
-        } else if key.code == KeyCode::Char('x') {
-            self.cut_item();
-        } else if key.code == KeyCode::Char('p') {
-            self.paste_item();
+
+        // --- DISK USAGE ---
+        if self.disk_usage.is_some() {
+            self.handle_disk_usage_input(key);
+            return;
         }
-        // --- FAVORITES ---
-        else if key.code == KeyCode::Char('f') {
-            self.add_favorite();
+
+        // --- DUPLICATES ---
+        if self.dupes.is_some() {
+            self.handle_dupes_input(key);
+            return;
         }
-    }
 
-    fn handle_drive_list_input(&mut self, key: KeyEvent) {
-        if key_matches(&key, &self.config.keys.down) || key.code == KeyCode::Down {
-            let i = match self.drive_state.selected() {
-                Some(i) => {
-                    if i >= self.drives.len().saturating_sub(1) {
-                        0
-                    } else {
-                        i + 1
-                    }
-                }
-                None => 0,
-            };
-            self.drive_state.select(Some(i));
-        } else if key_matches(&key, &self.config.keys.up) || key.code == KeyCode::Up {
-            let i = match self.drive_state.selected() {
-                Some(i) => {
-                    if i == 0 {
-                        self.drives.len().saturating_sub(1)
-                    } else {
-                        i - 1
-                    }
+        // --- SHARE ---
+        if self.share_prompt.is_some() {
+            self.handle_share_prompt_input(key);
+            return;
+        }
+        if self.share_popup {
+            self.handle_share_input(key);
+            return;
+        }
+
+        // --- CHECKSUMS ---
+        if self.checksums.is_some() {
+            self.handle_checksums_input(key);
+            return;
+        }
+
+        // --- CLEANUP (удаление подтверждается ниже, как обычно) ---
+        if self.cleanup.is_some() && !self.confirm_delete {
+            self.handle_cleanup_input(key);
+            return;
+        }
+
+        // --- XATTRS ---
+        #[cfg(unix)]
+        if self.xattrs.is_some() {
+            self.handle_xattr_input(key);
+            return;
+        }
+
+        // --- PROPERTIES ---
+        #[cfg(unix)]
+        if self.properties.is_some() {
+            self.handle_properties_input(key);
+            return;
+        }
+
+        // --- PATTERN RENAME ---
+        if self.pattern_rename.is_some() {
+            self.handle_pattern_rename_input(key);
+            return;
+        }
+
+        // --- BULK RENAME CONFIRM ---
+        if self.bulk_rename.as_ref().is_some_and(|b| b.awaiting_confirm()) {
+            self.handle_bulk_rename_input(key);
+            return;
+        }
+
+        // --- DELETE CONFIRMATION ---
+        if self.confirm_delete {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.execute_delete();
                 }
-                None => 0,
-            };
-            self.drive_state.select(Some(i));
-        } else if (key_matches(&key, &self.config.keys.submit) || key.code == KeyCode::Enter || key.code == KeyCode::Right)
-            && let Some(selected) = self.drive_state.selected()
-                && let Some((mount, _)) = self.drives.get(selected) {
-                    self.current_dir = PathBuf::from(mount);
-                    self.refresh_items();
-                    self.focus = Focus::FileList;
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.confirm_delete = false;
+                    self.pending_delete = None;
+                    self.pending_trash.clear();
+                    self.message = "Delete cancelled.".to_string();
                 }
+                _ => {}
+            }
+            return;
+        }
+
+        match self.input_mode {
+            InputMode::Normal => self.handle_normal_key(key),
+            InputMode::Editing => self.handle_editing_input(key),
+            InputMode::Search => self.handle_search_input(key),
+            InputMode::Renaming => self.handle_renaming_input(key),
+            InputMode::Command => self.handle_command_input(key),
+        }
     }
 
-    fn handle_favorites_input(&mut self, key: KeyEvent) {
-        if key_matches(&key, &self.config.keys.down) || key.code == KeyCode::Down {
-            let len = self.favorites.len();
-            if len == 0 {
+    /// Клавиша в Normal режиме: копим последовательность, пока она — префикс привязки
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        if (!self.pending_keys.is_empty() || self.pending_count.is_some()) && key.code == KeyCode::Esc {
+            self.cancel_pending_keys();
+            return;
+        }
+        // Числовой префикс: "3@a", "5j"
+        if self.pending_keys.is_empty()
+            && key.modifiers.is_empty()
+            && let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = self.pending_count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+            self.pending_count = Some(count.min(MAX_COUNT));
+            return;
+        }
+        self.pending_keys.push(key);
+        let command = match self.keymap.resolve(&self.pending_keys) {
+            Resolution::Command(command) => Some(command.to_string()),
+            Resolution::Pending(..) => {
+                self.pending_since.get_or_insert_with(Instant::now);
                 return;
             }
-            let i = match self.favorites_state.selected() {
-                Some(i) => {
-                    if i >= len - 1 { 0 } else { i + 1 }
+            Resolution::NoMatch => {
+                if self.pending_keys.len() > 1 {
+                    self.message = "No binding for that key sequence".to_string();
                 }
-                None => 0,
-            };
-            self.favorites_state.select(Some(i));
-        } else if key_matches(&key, &self.config.keys.up) || key.code == KeyCode::Up {
-            let len = self.favorites.len();
-            if len == 0 {
-                return;
+                None
             }
-            let i = match self.favorites_state.selected() {
-                Some(i) => {
-                    if i == 0 { len - 1 } else { i - 1 }
-                }
-                None => 0,
-            };
-            self.favorites_state.select(Some(i));
-        } else if key_matches(&key, &self.config.keys.submit)
-            || key.code == KeyCode::Enter
-            || key.code == KeyCode::Right
-        {
-            self.open_favorite();
-        } else if key_matches(&key, &self.config.keys.delete) || key.code == KeyCode::Char('F') {
-            self.remove_favorite();
+        };
+        let count = self.pending_count.unwrap_or(1);
+        self.cancel_pending_keys();
+        if let Some(command) = command {
+            self.execute_counted(&command, count);
         }
     }
 
-    fn handle_editing_input(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Enter => {
-                self.create_item();
-                self.input_mode = InputMode::Normal;
-                self.input_buffer.clear();
+    /// Команда с числовым префиксом: для макросов это число повторов воспроизведения
+    fn execute_counted(&mut self, command: &str, count: usize) {
+        if command == "macro play" {
+            self.execute_command(command);
+            if let Some(MacroAwait::Play(_)) = self.macro_await {
+                self.macro_await = Some(MacroAwait::Play(count));
             }
-            KeyCode::Esc => {
-                self.input_mode = InputMode::Normal;
-                self.input_buffer.clear();
+            return;
+        }
+        for _ in 0..count {
+            self.execute_command(command);
+            if self.should_quit || self.input_mode != InputMode::Normal {
+                break;
             }
-            KeyCode::Char(c) => self.input_buffer.push(c),
-            KeyCode::Backspace => {
-                self.input_buffer.pop();
+        }
+    }
+
+    pub fn cancel_pending_keys(&mut self) {
+        self.pending_keys.clear();
+        self.pending_since = None;
+        self.pending_count = None;
+    }
+
+    /// Периодическая работа между событиями
+    pub fn tick(&mut self) {
+        self.poll_dupes();
+        self.poll_cleanup();
+        self.poll_disk_usage();
+        self.poll_dir_sizes();
+        self.poll_archive();
+        self.poll_extract();
+        self.poll_share();
+        // Неоднозначный префикс ("g" при наличии "g" и "g g") срабатывает по таймауту
+        if let Some(since) = self.pending_since
+            && since.elapsed() >= SEQUENCE_TIMEOUT
+            && let Resolution::Pending(Some(command), _) = self.keymap.resolve(&self.pending_keys)
+        {
+            let command = command.to_string();
+            let count = self.pending_count.unwrap_or(1);
+            self.cancel_pending_keys();
+            self.execute_counted(&command, count);
+        }
+    }
+
+    /// Пора ли показывать which-key подсказку
+    pub fn which_key_visible(&self) -> bool {
+        self.pending_since
+            .is_some_and(|t| t.elapsed() >= Duration::from_millis(self.config.which_key_delay_ms))
+    }
+
+    pub fn open_selected(&mut self) {
+        if let Some(selected) = self.state.selected()
+            && let Some(path) = self.filtered_items.get(selected) {
+                let path = path.clone();
+                if self.meta_cache.get(&path).is_some_and(|m| m.is_dir) {
+                    self.current_dir = path;
+                    self.refresh_items();
+                } else {
+                    self.open_archive_path(&path);
+                }
             }
-            _ => {}
+    }
+
+    /// Открывает каталог, где лежит `path`, и ставит на него курсор
+    pub fn reveal_path(&mut self, path: &std::path::Path) {
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        self.current_dir = parent.to_path_buf();
+        self.focus = Focus::FileList;
+        self.refresh_items();
+        if let Some(i) = self.filtered_items.iter().position(|p| p == path) {
+            self.state.select(Some(i));
         }
+        self.update_preview();
     }
 
-    fn handle_renaming_input(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Enter => {
-                self.rename_item();
+    pub fn edit_selected(&mut self) {
+        if !self.selected_indices.is_empty() {
+            let paths = self.selected_paths();
+            if let Some(path) = paths.into_iter().find(|p| !p.is_dir()) {
+                self.file_to_edit = Some(path);
             }
-            KeyCode::Esc => {
-                self.input_mode = InputMode::Normal;
-                self.input_buffer.clear();
-                self.message = "Rename cancelled.".to_string();
+        } else if let Some(selected) = self.state.selected()
+            && let Some(path) = self.filtered_items.get(selected)
+                && !path.is_dir() {
+                    self.file_to_edit = Some(path.clone());
+                }
+    }
+
+    pub fn next_drive(&mut self) {
+        let i = match self.drive_state.selected() {
+            Some(i) => {
+                if i >= self.drives.len().saturating_sub(1) {
+                    0
+                } else {
+                    i + 1
+                }
             }
-            KeyCode::Char(c) => self.input_buffer.push(c),
-            KeyCode::Backspace => {
-                self.input_buffer.pop();
+            None => 0,
+        };
+        self.drive_state.select(Some(i));
+    }
+
+    pub fn previous_drive(&mut self) {
+        let i = match self.drive_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.drives.len().saturating_sub(1)
+                } else {
+                    i - 1
+                }
             }
-            _ => {}
+            None => 0,
+        };
+        self.drive_state.select(Some(i));
+    }
+
+    pub fn open_drive(&mut self) {
+        if let Some(selected) = self.drive_state.selected()
+            && let Some((mount, _)) = self.drives.get(selected) {
+                self.current_dir = PathBuf::from(mount);
+                self.refresh_items();
+                self.focus = Focus::FileList;
+            }
+    }
+
+    pub fn next_favorite(&mut self) {
+        let len = self.favorites.len();
+        if len == 0 {
+            return;
         }
+        let i = match self.favorites_state.selected() {
+            Some(i) => {
+                if i >= len - 1 { 0 } else { i + 1 }
+            }
+            None => 0,
+        };
+        self.favorites_state.select(Some(i));
+    }
+
+    pub fn previous_favorite(&mut self) {
+        let len = self.favorites.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.favorites_state.selected() {
+            Some(i) => {
+                if i == 0 { len - 1 } else { i - 1 }
+            }
+            None => 0,
+        };
+        self.favorites_state.select(Some(i));
     }
 
     fn handle_search_input(&mut self, key: KeyEvent) {
-        if key_matches(&key, &self.config.keys.submit) {
+        // Клавиша подтверждения из конфига работает, только если это не печатаемый символ
+        let printable = matches!(key.code, KeyCode::Char(_))
+            && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if key.code == KeyCode::Enter || (!printable && key_matches(&key, &self.config.keys.submit)) {
+            self.search_query.push_history();
             self.input_mode = InputMode::Normal;
-        } else if key_matches(&key, &self.config.keys.cancel) {
+        } else if key.code == KeyCode::Esc || (!printable && key_matches(&key, &self.config.keys.cancel)) {
             self.input_mode = InputMode::Normal;
             self.search_query.clear();
             self.update_search();
-        } else if key.code == KeyCode::Backspace {
-            self.search_query.pop();
-            self.update_search();
-        } else if let KeyCode::Char(c) = key.code {
-            self.search_query.push(c);
-            self.update_search();
-        } else if key_matches(&key, &self.config.keys.down) || key.code == KeyCode::Down {
+        } else if key.code == KeyCode::Down {
             self.next_item();
-        } else if key_matches(&key, &self.config.keys.up) || key.code == KeyCode::Up {
+        } else if key.code == KeyCode::Up {
             self.previous_item();
+        } else if self.search_query.handle_key(key) == EditResult::Changed {
+            self.update_search();
         }
     }
 
-    fn next_item(&mut self) {
+    pub fn next_item(&mut self) {
         if self.filtered_items.is_empty() {
             return;
         }
@@ -913,7 +1220,7 @@ impl App {
         self.update_preview();
     }
 
-    fn previous_item(&mut self) {
+    pub fn previous_item(&mut self) {
         if self.filtered_items.is_empty() {
             return;
         }
@@ -931,22 +1238,7 @@ impl App {
         self.update_preview();
     }
 
-    fn create_item(&mut self) {
-        let new_path = self.current_dir.join(&self.input_buffer);
-        let res = if self.input_buffer.ends_with('/') {
-            fs::create_dir(&new_path)
-        } else {
-            fs::File::create(&new_path).map(|_| ())
-        };
-        match res {
-            Ok(_) => self.message = format!("Created: {}", self.input_buffer),
-            Err(e) => self.message = format!("Error: {}", e),
-        }
-        self.refresh_items();
-        self.update_preview();
-    }
-
-    fn delete_prompt(&mut self) {
+    pub fn delete_prompt(&mut self) {
         let count = self.selected_indices.len();
         if count > 0 {
             self.pending_delete = None;
@@ -967,18 +1259,19 @@ impl App {
         self.confirm_delete = false;
         let count = self.selected_indices.len();
 
-        if count > 0 {
+        if !self.pending_trash.is_empty() {
+            self.trash_pending();
+        } else if count > 0 {
             let paths = std::mem::take(&mut self.selected_indices)
                 .into_iter()
                 .filter_map(|i| self.filtered_items.get(i).cloned())
                 .collect::<Vec<_>>();
             for path in &paths {
-                let _ = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+                let _ = self.fs_for(path).remove(path);
             }
             self.message = format!("Deleted {} item(s).", paths.len());
         } else if let Some(path) = self.pending_delete.take() {
-            let res = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
-            match res {
+            match self.fs_for(&path).remove(&path) {
                 Ok(_) => self.message = "Deleted.".to_string(),
                 Err(e) => self.message = format!("Error: {}", e),
             }
@@ -987,25 +1280,37 @@ impl App {
         self.update_preview();
     }
 
-    fn start_rename(&mut self) {
+    pub fn start_rename(&mut self) {
         if let Some(selected) = self.state.selected()
             && let Some(path) = self.filtered_items.get(selected)
                 && let Some(name) = path.file_name() {
-                    self.input_buffer = name.to_string_lossy().to_string();
+                    self.rename_input.set(name.to_string_lossy());
                     self.input_mode = InputMode::Renaming;
+                    self.select_rename_part(NamePart::Stem);
                     self.message = "Edit name and press Enter to rename.".to_string();
                 }
     }
 
-    fn rename_item(&mut self) {
-        if let Some(selected) = self.state.selected()
+    pub fn rename_item(&mut self) {
+        let new_name = self.rename_input.text().to_string();
+        if let Some(err) = self.rename_error() {
+            self.message = format!("Rename error: {}", err);
+        } else if let Some(selected) = self.state.selected()
             && let Some(path) = self.filtered_items.get(selected) {
-                let new_path = path.parent().unwrap_or(&self.current_dir).join(&self.input_buffer);
+                let new_path = path.parent().unwrap_or(&self.current_dir).join(&new_name);
                 if new_path == *path {
                     self.message = "Same name, nothing changed.".to_string();
                 } else {
-                    match fs::rename(path, &new_path) {
-                        Ok(_) => self.message = format!("Renamed to: {}", self.input_buffer),
+                    match self.fs_for(path).rename(path, &new_path) {
+                        Ok(_) => {
+                            let pairs = vec![(path.clone(), new_path)];
+                            self.tags_moved(&pairs);
+                            // Отмена переименовывает на локальном диске
+                            if self.fs_for(&pairs[0].1).is_local() {
+                                self.push_undo(UndoEntry::Rename(pairs));
+                            }
+                            self.message = format!("Renamed to: {}", new_name);
+                        }
                         Err(e) => self.message = format!("Rename error: {}", e),
                     }
                     self.refresh_items();
@@ -1013,33 +1318,23 @@ impl App {
                 }
             }
         self.input_mode = InputMode::Normal;
-        self.input_buffer.clear();
+        self.rename_input.clear();
     }
 }
 
-fn copy_recursive(src: &PathBuf, dest: &PathBuf) -> Result<(), String> {
-    if src.is_dir() {
-        fs::create_dir_all(dest).map_err(|e| e.to_string())?;
-        for entry in fs::read_dir(src).map_err(|e| e.to_string())? {
-            let entry = entry.map_err(|e| e.to_string())?;
-            let child_src = entry.path();
-            let child_dest = dest.join(entry.file_name());
-            copy_recursive(&child_src, &child_dest)?;
-        }
-        Ok(())
-    } else {
-        fs::copy(src, dest).map(|_| ()).map_err(|e| e.to_string())
-    }
+pub fn find_available_name(dest: &std::path::Path) -> PathBuf {
+    find_available_name_by(dest, |p| p.exists())
 }
 
-fn find_available_name(dest: &std::path::Path) -> PathBuf {
+/// То же, но существование проверяет вызывающий (например, через провайдер)
+pub fn find_available_name_by(dest: &std::path::Path, exists: impl Fn(&std::path::Path) -> bool) -> PathBuf {
     let parent = dest.parent().map(|p| p.to_path_buf()).unwrap_or_else(|| PathBuf::from("."));
     let stem = dest.file_stem().and_then(|s| s.to_str()).unwrap_or("file").to_string();
     let ext = dest.extension().and_then(|e| e.to_str()).map(|e| format!(".{}", e)).unwrap_or_default();
     for i in 1..9999 {
         let name = format!("{} ({}){}", stem, i, ext);
         let candidate = parent.join(&name);
-        if !candidate.exists() {
+        if !exists(&candidate) {
             return candidate;
         }
     }
@@ -1110,7 +1405,7 @@ fn check_github_version(current: &str) -> Option<String> {
     if version != current { Some(version.to_string()) } else { None }
 }
 
-fn fuzzy_match(text: &str, query: &str) -> bool {
+pub fn fuzzy_match(text: &str, query: &str) -> bool {
     let text_lower = text.to_lowercase();
     let query_lower = query.to_lowercase();
     let mut qi = query_lower.chars();
@@ -1128,37 +1423,3 @@ fn fuzzy_match(text: &str, query: &str) -> bool {
     }
     false
 }
-
-fn key_matches(key: &KeyEvent, binding: &str) -> bool {
-    let binding = binding.to_lowercase();
-    let code_str = match key.code {
-        KeyCode::Char(c) => c.to_string().to_lowercase(),
-        KeyCode::Enter => "enter".to_string(),
-        KeyCode::Esc => "esc".to_string(),
-        KeyCode::Backspace => "backspace".to_string(),
-        KeyCode::Tab => "tab".to_string(),
-        KeyCode::Delete => "delete".to_string(),
-        KeyCode::Left => "left".to_string(),
-        KeyCode::Right => "right".to_string(),
-        KeyCode::Up => "up".to_string(),
-        KeyCode::Down => "down".to_string(),
-        KeyCode::F(n) => format!("f{}", n),
-        _ => String::new(),
-    };
-
-    let mut parts = Vec::new();
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
-        parts.push("ctrl");
-    }
-    if key.modifiers.contains(KeyModifiers::ALT) {
-        parts.push("alt");
-    }
-
-    if !parts.is_empty() || code_str.len() > 1 {
-        parts.push(&code_str);
-        let formed = parts.join("-");
-        formed == binding
-    } else {
-        code_str == binding
-    }
-}
@@ -0,0 +1,443 @@
+use crate::app::App;
+use crate::commands::glob_match;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Уровень сжатия, если в запросе нет `-0`…`-9`
+const DEFAULT_LEVEL: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl Format {
+    /// Формат по расширению имени архива
+    pub fn from_name(name: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        let formats = [
+            (".zip", Format::Zip),
+            (".tar", Format::Tar),
+            (".tar.gz", Format::TarGz),
+            (".tgz", Format::TarGz),
+            (".tar.xz", Format::TarXz),
+            (".txz", Format::TarXz),
+            (".tar.zst", Format::TarZst),
+            (".tzst", Format::TarZst),
+        ];
+        formats.iter().find(|(ext, _)| lower.ends_with(ext)).map(|(_, f)| *f)
+    }
+}
+
+/// Что попадёт в архив; ссылки уже разыменованы
+struct Entry {
+    path: PathBuf,
+    /// Путь внутри архива через `/`, у папок с `/` на конце
+    name: String,
+    is_dir: bool,
+    size: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    mtime: u64,
+}
+
+#[derive(Default)]
+pub struct ArchiveProgress {
+    pub total_bytes: u64,
+    pub done_bytes: u64,
+    /// Итог: сводка или ошибка
+    pub result: Option<Result<String, String>>,
+}
+
+/// Архив, который пишется в фоне
+pub struct ArchiveJob {
+    pub output: PathBuf,
+    pub progress: Arc<Mutex<ArchiveProgress>>,
+    cancel: Arc<AtomicBool>,
+}
+
+fn set_progress(progress: &Mutex<ArchiveProgress>, f: impl FnOnce(&mut ArchiveProgress)) {
+    if let Ok(mut p) = progress.lock() {
+        f(&mut p);
+    }
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "cancelled")
+}
+
+#[cfg(unix)]
+fn owner_and_mode(meta: &fs::Metadata) -> (u32, u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (meta.mode() & 0o7777, meta.uid(), meta.gid())
+}
+
+#[cfg(not(unix))]
+fn owner_and_mode(meta: &fs::Metadata) -> (u32, u32, u32) {
+    (if meta.is_dir() { 0o755 } else { 0o644 }, 0, 0)
+}
+
+/// Обход выбранного. Ссылки разыменовываются, как при копировании (`copy_recursive`);
+/// битые ссылки и особые файлы пропускаются с ошибкой, петли из ссылок на папки обрываются.
+struct Collector<'a> {
+    excludes: &'a [String],
+    output: Option<PathBuf>,
+    entries: Vec<Entry>,
+    errors: Vec<String>,
+    cancel: &'a AtomicBool,
+}
+
+impl Collector<'_> {
+    fn excluded(&self, name: &str, rel: &str) -> bool {
+        self.excludes.iter().any(|pat| glob_match(pat, name) || glob_match(pat, rel))
+    }
+
+    fn add(&mut self, path: &Path, rel: String, ancestors: &mut Vec<PathBuf>) {
+        if self.cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if self.excluded(&name, &rel) {
+            return;
+        }
+        let meta = match fs::metadata(path) {
+            Ok(m) => m,
+            Err(e) => return self.errors.push(format!("{}: {}", rel, e)),
+        };
+        let real = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        // Сам архив может оказаться внутри выбранной папки
+        if self.output.as_ref() == Some(&real) {
+            return;
+        }
+        let (mode, uid, gid) = owner_and_mode(&meta);
+        let mtime = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+        if meta.is_dir() {
+            if ancestors.contains(&real) {
+                return self.errors.push(format!("{}: symlink loop", rel));
+            }
+            self.entries.push(Entry { path: path.to_path_buf(), name: format!("{}/", rel), is_dir: true, size: 0, mode, uid, gid, mtime });
+            let mut children: Vec<PathBuf> = match fs::read_dir(path) {
+                Ok(read) => read.flatten().map(|e| e.path()).collect(),
+                Err(e) => return self.errors.push(format!("{}: {}", rel, e)),
+            };
+            children.sort();
+            ancestors.push(real);
+            for child in children {
+                let child_name = child.file_name().unwrap_or_default().to_string_lossy().to_string();
+                self.add(&child, format!("{}/{}", rel, child_name), ancestors);
+            }
+            ancestors.pop();
+        } else if meta.is_file() {
+            self.entries.push(Entry { path: path.to_path_buf(), name: rel, is_dir: false, size: meta.len(), mode, uid, gid, mtime });
+        } else {
+            self.errors.push(format!("{}: not a regular file, skipped", rel));
+        }
+    }
+}
+
+/// Читает файл, отмечая прогресс и проверяя отмену
+struct ProgressReader<'a, R: Read> {
+    inner: R,
+    progress: &'a Mutex<ArchiveProgress>,
+    cancel: &'a AtomicBool,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let n = self.inner.read(buf)?;
+        set_progress(self.progress, |p| p.done_bytes += n as u64);
+        Ok(n)
+    }
+}
+
+// --- TAR (крейт `tar`, длинные имена через GNU `././@LongLink`) ---
+
+fn write_tar<W: Write>(entries: &[Entry], out: W, progress: &Mutex<ArchiveProgress>, cancel: &AtomicBool) -> io::Result<W> {
+    let mut builder = tar::Builder::new(out);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(entry.mode);
+        header.set_uid(entry.uid as u64);
+        header.set_gid(entry.gid as u64);
+        header.set_mtime(entry.mtime);
+        if entry.is_dir {
+            header.set_entry_type(tar::EntryType::Directory);
+            header.set_size(0);
+            builder.append_data(&mut header, &entry.name, io::empty())?;
+            continue;
+        }
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(entry.size);
+        // Файл мог измениться после обхода: в заголовке уже записан размер, держимся его
+        let file = ProgressReader { inner: fs::File::open(&entry.path)?.take(entry.size), progress, cancel };
+        builder.append_data(&mut header, &entry.name, file.chain(io::repeat(0)).take(entry.size))?;
+    }
+    builder.into_inner()
+}
+
+/// tar через внешний `xz` / `zstd`: архив пишется им на вход
+fn write_tar_through(program: &str, args: &[String], entries: &[Entry], file: fs::File, progress: &Mutex<ArchiveProgress>, cancel: &AtomicBool) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(file)
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("{} not found ({})", program, e)))?;
+    let stdin = child.stdin.take().ok_or_else(|| io::Error::other("no stdin"))?;
+    let res = write_tar(entries, BufWriter::new(stdin), progress, cancel).and_then(|mut w| w.flush());
+    if res.is_err() {
+        let _ = child.kill();
+    }
+    let status = child.wait()?;
+    res?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed ({})", program, status)));
+    }
+    Ok(())
+}
+
+// --- ZIP (крейт `zip`, zip64 включается сам для больших файлов) ---
+
+/// Время изменения для zip; до 1980 года формат не дотягивается
+fn zip_time(mtime: u64) -> zip::DateTime {
+    let (y, m, d, h, mi, s) = crate::ui::date_parts(UNIX_EPOCH + std::time::Duration::from_secs(mtime));
+    zip::DateTime::from_date_and_time(y as u16, m as u8, d as u8, h as u8, mi as u8, s as u8).unwrap_or_default()
+}
+
+fn write_zip(entries: &[Entry], file: fs::File, level: u32, progress: &Mutex<ArchiveProgress>, cancel: &AtomicBool) -> io::Result<()> {
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file)).set_auto_large_file();
+    let method = if level > 0 { zip::CompressionMethod::Deflated } else { zip::CompressionMethod::Stored };
+    for entry in entries {
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(method)
+            .compression_level((level > 0).then_some(level as i64))
+            .last_modified_time(zip_time(entry.mtime))
+            .unix_permissions(entry.mode);
+        if entry.is_dir {
+            zip.add_directory(entry.name.as_str(), options)?;
+            continue;
+        }
+        zip.start_file(entry.name.as_str(), options)?;
+        io::copy(&mut ProgressReader { inner: fs::File::open(&entry.path)?, progress, cancel }, &mut zip)?;
+    }
+    zip.finish()?.flush()
+}
+
+fn write_archive(format: Format, entries: &[Entry], output: &Path, level: u32, progress: &Mutex<ArchiveProgress>, cancel: &AtomicBool) -> io::Result<()> {
+    let file = fs::OpenOptions::new().write(true).create_new(true).open(output)?;
+    match format {
+        Format::Zip => write_zip(entries, file, level, progress, cancel),
+        Format::Tar => write_tar(entries, BufWriter::new(file), progress, cancel)?.flush(),
+        Format::TarGz => write_tar(entries, GzEncoder::new(BufWriter::new(file), Compression::new(level)), progress, cancel)?.finish()?.flush(),
+        Format::TarXz => write_tar_through("xz", &[format!("-{}", level), "-T0".into(), "-c".into()], entries, file, progress, cancel),
+        Format::TarZst => write_tar_through("zstd", &[format!("-{}", level.max(1)), "-q".into(), "-c".into()], entries, file, progress, cancel),
+    }
+}
+
+/// Разбор `compress <имя> [-0…-9] [-x шаблон]...`
+fn parse_compress_args(args: &[String]) -> Result<(String, u32, Vec<String>), String> {
+    let mut name = None;
+    let mut level = DEFAULT_LEVEL;
+    let mut excludes = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-x" | "--exclude" => excludes.push(iter.next().ok_or("-x needs a pattern")?.clone()),
+            a if a.len() == 2 && a.starts_with('-') && a.as_bytes()[1].is_ascii_digit() => level = (a.as_bytes()[1] - b'0') as u32,
+            a if a.starts_with('-') => return Err(format!("unknown option {}", a)),
+            a if name.is_none() => name = Some(a.to_string()),
+            a => return Err(format!("unexpected argument {}", a)),
+        }
+    }
+    Ok((name.ok_or("archive name missing")?, level, excludes))
+}
+
+impl App {
+    /// `compress` без аргументов: строка команды с предложенным именем
+    pub fn prompt_compress(&mut self) {
+        let targets = self.bulk_targets_or_highlighted();
+        let base = match targets.as_slice() {
+            [single] if single.is_dir() => single.file_name(),
+            [single] => single.file_stem(),
+            _ => self.current_dir.file_name(),
+        };
+        let base = base.map_or("archive".to_string(), |n| n.to_string_lossy().to_string());
+        let name = format!("{}.zip", base);
+        let quoted = if name.contains(' ') { format!("\"{}\"", name) } else { name };
+        self.start_command();
+        self.command_input.set(format!("compress {}", quoted));
+    }
+
+    /// `compress <имя> [-0…-9] [-x шаблон]...` — архив выбранного (или текущего) в фоне
+    pub fn compress_command(&mut self, args: &[String]) {
+        if args.first().map(String::as_str) == Some("cancel") {
+            return match self.archive.as_ref() {
+                Some(job) => {
+                    job.cancel.store(true, Ordering::Relaxed);
+                    self.message = "Cancelling archive…".to_string();
+                }
+                None => self.message = "No archive in progress".to_string(),
+            };
+        }
+        if self.archive.is_some() {
+            self.message = "An archive is already being written (`:compress cancel` stops it)".to_string();
+            return;
+        }
+        let (name, level, excludes) = match parse_compress_args(args) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.message = format!("Usage: compress <name.zip|.tar|.tar.gz|.tar.xz|.tar.zst> [-0..-9] [-x pattern]... ({})", e);
+                return;
+            }
+        };
+        let Some(format) = Format::from_name(&name) else {
+            self.message = format!("Unknown archive type '{}': use .zip, .tar, .tar.gz, .tar.xz or .tar.zst", name);
+            return;
+        };
+        let output = crate::commands::expand_path(&self.current_dir, &name);
+        if output.exists() {
+            self.message = format!("'{}' already exists", name);
+            return;
+        }
+        let sources = self.bulk_targets_or_highlighted();
+        if sources.is_empty() {
+            self.message = "Nothing to compress".to_string();
+            return;
+        }
+
+        let progress = Arc::new(Mutex::new(ArchiveProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (shared, stop, out) = (progress.clone(), cancel.clone(), output.clone());
+        thread::spawn(move || {
+            let out_real = out.parent().and_then(|p| fs::canonicalize(p).ok()).map(|p| p.join(out.file_name().unwrap_or_default()));
+            let mut collector = Collector { excludes: &excludes, output: out_real, entries: Vec::new(), errors: Vec::new(), cancel: &stop };
+            for src in &sources {
+                let rel = src.file_name().unwrap_or_default().to_string_lossy().to_string();
+                collector.add(src, rel, &mut Vec::new());
+            }
+            let total: u64 = collector.entries.iter().map(|e| e.size).sum();
+            set_progress(&shared, |p| p.total_bytes = total);
+            let started = SystemTime::now();
+            let res = if stop.load(Ordering::Relaxed) { Err(cancelled()) } else { write_archive(format, &collector.entries, &out, level, &shared, &stop) };
+            let result = match res {
+                Ok(()) => {
+                    let files = collector.entries.iter().filter(|e| !e.is_dir).count();
+                    let written = fs::metadata(&out).map_or(0, |m| m.len());
+                    let secs = started.elapsed().map_or(0.0, |d| d.as_secs_f64());
+                    let mut summary = format!(
+                        "Created {}: {} file(s), {} → {} in {:.1}s",
+                        out.file_name().unwrap_or_default().to_string_lossy(),
+                        files,
+                        crate::ui::format_size(total),
+                        crate::ui::format_size(written),
+                        secs
+                    );
+                    if let Some(first) = collector.errors.first() {
+                        summary.push_str(&format!(", {} skipped ({})", collector.errors.len(), first));
+                    }
+                    Ok(summary)
+                }
+                Err(e) => {
+                    // Недописанный архив не оставляем; чужой файл create_new не тронул бы
+                    if e.kind() != io::ErrorKind::AlreadyExists {
+                        let _ = fs::remove_file(&out);
+                    }
+                    Err(if e.kind() == io::ErrorKind::Interrupted { "Archive cancelled".to_string() } else { format!("Archive error: {}", e) })
+                }
+            };
+            set_progress(&shared, |p| p.result = Some(result));
+        });
+        self.message = format!("Compressing {}…", name);
+        self.archive = Some(ArchiveJob { output, progress, cancel });
+    }
+
+    /// Прогресс в строке сообщений и итог, когда архив готов (вызывается из `tick`)
+    pub fn poll_archive(&mut self) {
+        let Some(job) = self.archive.as_ref() else {
+            return;
+        };
+        let Ok(mut progress) = job.progress.lock() else {
+            return;
+        };
+        if let Some(result) = progress.result.take() {
+            drop(progress);
+            self.message = result.unwrap_or_else(|e| e);
+            self.archive = None;
+            self.refresh_items();
+            self.update_preview();
+            return;
+        }
+        // Чужие сообщения не затираем
+        if self.message.starts_with("Compressing") && progress.total_bytes > 0 {
+            let name = job.output.file_name().unwrap_or_default().to_string_lossy().to_string();
+            self.message = format!(
+                "Compressing {}… {}% ({} / {})",
+                name,
+                progress.done_bytes * 100 / progress.total_bytes,
+                crate::ui::format_size(progress.done_bytes),
+                crate::ui::format_size(progress.total_bytes)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_args() {
+        assert_eq!(Format::from_name("a.TAR.GZ"), Some(Format::TarGz));
+        assert_eq!(Format::from_name("a.tzst"), Some(Format::TarZst));
+        assert_eq!(Format::from_name("a.gz"), None);
+        let args: Vec<String> = ["out.zip", "-9", "-x", "*.log"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(parse_compress_args(&args), Ok(("out.zip".to_string(), 9, vec!["*.log".to_string()])));
+    }
+
+    #[test]
+    fn written_archives_extract_back() {
+        use crate::extract::{ExtractProgress, Kind, extract_into};
+        let dir = std::env::temp_dir().join(format!("ffm_archive_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let src = dir.join("proj");
+        fs::create_dir_all(src.join("src/empty")).unwrap();
+        fs::write(src.join("README.md"), "# proj\n".repeat(500)).unwrap();
+        let bin: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        fs::write(src.join("src/data.bin"), &bin).unwrap();
+        fs::write(src.join("src/skip.log"), "excluded").unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let excludes = vec!["*.log".to_string()];
+        let mut collector = Collector { excludes: &excludes, output: None, entries: Vec::new(), errors: Vec::new(), cancel: &cancel };
+        collector.add(&src, "proj".to_string(), &mut Vec::new());
+        for (format, name) in [(Format::Zip, "out.zip"), (Format::TarGz, "out.tar.gz"), (Format::Tar, "out.tar")] {
+            let archive = dir.join(name);
+            write_archive(format, &collector.entries, &archive, 6, &Mutex::new(ArchiveProgress::default()), &cancel).unwrap();
+            let target = dir.join(format!("x-{}", name));
+            let refused = extract_into(Kind::Native(format), &archive, &target, &Mutex::new(ExtractProgress::default()), &cancel).unwrap();
+            assert!(refused.is_empty(), "{:?}", refused);
+            assert_eq!(fs::read_to_string(target.join("proj/README.md")).unwrap(), "# proj\n".repeat(500));
+            assert!(fs::read(target.join("proj/src/data.bin")).unwrap() == bin, "{}", name);
+            assert!(target.join("proj/src/empty").is_dir());
+            assert!(!target.join("proj/src/skip.log").exists());
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
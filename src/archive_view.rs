@@ -0,0 +1,489 @@
+use crate::app::{App, Focus};
+use crate::archive::Format;
+use crate::extract::{self, Kind, Sink, TarReader, ZipEntry};
+use crate::vfs::{FileSystem, Metadata, WriteMode};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Команды, которые внутри архива не работают: он открыт только для чтения
+pub const READ_ONLY_COMMANDS: &[&str] = &[
+    "mkdir", "touch", "rename", "bulkrename", "patternrename", "link", "properties", "xattr", "tag", "untag", "delete",
+    "create", "edit", "cut", "paste", "compress", "extract", "checksum", "cleanup", "dupes", "du", "dirsize",
+];
+
+/// Запись в индексе архива
+struct Node {
+    is_dir: bool,
+    /// У папок — сумма файлов внутри
+    size: u64,
+    mtime: Option<u64>,
+    mode: Option<u32>,
+    /// Цель символьной ссылки как записана
+    link: Option<String>,
+    /// Жёсткая ссылка tar: данные берутся у этой записи
+    hard: Option<PathBuf>,
+    /// Номер записи zip или смещение данных в потоке tar
+    at: u64,
+}
+
+impl Node {
+    fn dir() -> Self {
+        Node { is_dir: true, size: 0, mtime: None, mode: None, link: None, hard: None, at: 0 }
+    }
+}
+
+/// Открытый архив: индекс записей и файл, из которого они читаются
+pub struct ArchiveMount {
+    /// Путь архива в списке файлов; всё под ним — записи архива
+    pub root: PathBuf,
+    /// Файл на диске: сам архив или временная копия вложенного
+    file: PathBuf,
+    kind: Kind,
+    nodes: BTreeMap<PathBuf, Node>,
+    zip: Vec<ZipEntry>,
+    temp: bool,
+}
+
+impl Drop for ArchiveMount {
+    fn drop(&mut self) {
+        if self.temp {
+            let _ = fs::remove_file(&self.file);
+        }
+    }
+}
+
+fn insert(nodes: &mut BTreeMap<PathBuf, Node>, name: &str, node: Node) {
+    // Записи с `..` и абсолютными путями в дерево не попадают
+    if let Some(rel) = extract::safe_relative(name).filter(|r| !r.as_os_str().is_empty()) {
+        nodes.insert(rel, node);
+    }
+}
+
+fn to_time(secs: Option<u64>) -> Option<std::time::SystemTime> {
+    secs.map(|s| UNIX_EPOCH + Duration::from_secs(s))
+}
+
+impl ArchiveMount {
+    fn mount(root: PathBuf, file: PathBuf, kind: Kind, temp: bool) -> io::Result<Self> {
+        // Временный файл уберёт Drop, даже если индекс не прочитался
+        let mut mount = ArchiveMount { root, file, kind, nodes: BTreeMap::new(), zip: Vec::new(), temp };
+        mount.index()?;
+        Ok(mount)
+    }
+
+    fn index(&mut self) -> io::Result<()> {
+        if self.kind == Kind::Native(Format::Zip) {
+            let mut file = fs::File::open(&self.file)?;
+            self.zip = extract::zip_entries(&mut file)?;
+            for (i, e) in self.zip.iter().enumerate() {
+                let mut link = None;
+                if e.is_symlink() && e.unsupported().is_none() {
+                    let mut target = String::new();
+                    extract::open_zip_entry(&mut file, e)?.take(4096).read_to_string(&mut target)?;
+                    link = Some(target);
+                }
+                let node = Node { is_dir: e.is_dir(), size: e.size, mtime: Some(e.mtime), mode: e.mode, link, hard: None, at: i as u64 };
+                insert(&mut self.nodes, &e.name, node);
+            }
+        } else {
+            let mut tar = TarReader::new(extract::tar_stream(self.kind, &self.file)?);
+            while let Some(e) = tar.next_entry()? {
+                if !e.is_file() && !matches!(e.kind, b'1' | b'2' | b'5') {
+                    continue;
+                }
+                let node = Node {
+                    is_dir: e.kind == b'5',
+                    size: e.size,
+                    mtime: Some(e.mtime),
+                    mode: Some(e.mode),
+                    link: (e.kind == b'2').then(|| e.link.clone()),
+                    hard: if e.kind == b'1' { extract::safe_relative(&e.link) } else { None },
+                    at: e.offset,
+                };
+                insert(&mut self.nodes, &e.name, node);
+            }
+        }
+        // Папки без своей записи, размеры жёстких ссылок и папок
+        let paths: Vec<PathBuf> = self.nodes.keys().cloned().collect();
+        for path in &paths {
+            for dir in path.ancestors().skip(1).filter(|d| !d.as_os_str().is_empty()) {
+                self.nodes.entry(dir.to_path_buf()).or_insert_with(Node::dir);
+            }
+        }
+        let mut sizes = Vec::new();
+        for (path, node) in &self.nodes {
+            let size = match &node.hard {
+                Some(target) => self.nodes.get(target).map_or(0, |t| t.size),
+                None if node.is_dir || node.link.is_some() => continue,
+                None => node.size,
+            };
+            sizes.push((path.clone(), size));
+        }
+        for (path, size) in sizes {
+            self.nodes.entry(path.clone()).and_modify(|n| n.size = size);
+            for dir in path.ancestors().skip(1).filter(|d| !d.as_os_str().is_empty()) {
+                self.nodes.entry(dir.to_path_buf()).and_modify(|n| n.size += size);
+            }
+        }
+        Ok(())
+    }
+
+    /// Путь записи после всех ссылок внутри архива; `None`, если ссылка ведёт наружу или в никуда
+    fn resolve(&self, rel: &Path) -> Option<PathBuf> {
+        self.resolve_hops(rel, &mut 0)
+    }
+
+    fn resolve_hops(&self, rel: &Path, hops: &mut usize) -> Option<PathBuf> {
+        let mut cur = PathBuf::new();
+        for comp in rel.components() {
+            match comp {
+                Component::CurDir => continue,
+                Component::ParentDir => {
+                    if !cur.pop() {
+                        return None;
+                    }
+                    continue;
+                }
+                Component::Normal(name) => cur.push(name),
+                Component::RootDir | Component::Prefix(_) => return None,
+            }
+            let node = self.nodes.get(&cur)?;
+            if let Some(link) = &node.link {
+                *hops += 1;
+                if *hops > 32 {
+                    return None;
+                }
+                let base = cur.parent().unwrap_or(Path::new("")).join(link);
+                cur = self.resolve_hops(&base, hops)?;
+            } else if let Some(target) = &node.hard {
+                cur = target.clone();
+            }
+        }
+        Some(cur)
+    }
+
+    /// Путь записи по пути в списке файлов
+    fn rel(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.root).ok().map(Path::to_path_buf)
+    }
+
+    /// Корень архива — тоже папка, хоть и без записи
+    fn is_dir(&self, rel: &Path) -> bool {
+        rel.as_os_str().is_empty() || self.nodes.get(rel).is_some_and(|n| n.is_dir)
+    }
+
+    fn children<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Node)> + 'a {
+        self.nodes.iter().filter(move |(p, _)| p.parent() == Some(dir))
+    }
+
+    /// Данные файла из архива
+    fn open(&self, rel: &Path) -> io::Result<Box<dyn Read>> {
+        let target = self.resolve(rel).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "link target is not in the archive"))?;
+        let node = match self.nodes.get(&target) {
+            Some(node) if !node.is_dir => node,
+            _ => return Err(io::Error::other("is a directory")),
+        };
+        match self.kind {
+            Kind::Native(Format::Zip) => {
+                let entry = &self.zip[node.at as usize];
+                if let Some(reason) = entry.unsupported() {
+                    return Err(io::Error::other(reason));
+                }
+                extract::open_zip_entry(fs::File::open(&self.file)?, entry)
+            }
+            Kind::Native(Format::Tar) => {
+                let mut file = fs::File::open(&self.file)?;
+                file.seek(SeekFrom::Start(node.at))?;
+                Ok(Box::new(file.take(node.size)))
+            }
+            // Сжатый поток читается с начала до нужной записи
+            _ => {
+                let mut tar = TarReader::new(extract::tar_stream(self.kind, &self.file)?);
+                while let Some(e) = tar.next_entry()? {
+                    if e.offset == node.at {
+                        return Ok(Box::new(tar));
+                    }
+                }
+                Err(io::Error::new(io::ErrorKind::NotFound, "entry not found"))
+            }
+        }
+    }
+
+    /// Копирует запись (папку — целиком) в `dest`; ссылки проверяются как при распаковке.
+    /// Возвращает пропущенные записи.
+    fn copy_out(&self, rel: &Path, dest: &Path) -> io::Result<Vec<String>> {
+        let (Some(root), Some(leaf)) = (dest.parent(), dest.file_name()) else {
+            return Err(io::Error::other("bad destination"));
+        };
+        let src = self.resolve(rel).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "link target is not in the archive"))?;
+        let cancel = AtomicBool::new(false);
+        let mut sink = Sink::new(root, &cancel);
+        let leaf = PathBuf::from(leaf);
+        let name = |path: &Path| path.strip_prefix(&src).ok().map(|rest| leaf.join(rest).to_string_lossy().to_string());
+        if !self.is_dir(&src) {
+            let node = self.nodes.get(&src);
+            sink.file(&leaf.to_string_lossy(), &mut self.open(&src)?, node.and_then(|n| n.mode), node.and_then(|n| n.mtime))?;
+            return Ok(sink.finish());
+        }
+        let top = self.nodes.get(&src);
+        sink.dir(&leaf.to_string_lossy(), top.and_then(|n| n.mode), top.and_then(|n| n.mtime))?;
+        if self.kind == Kind::Native(Format::Zip) {
+            for (path, node) in self.nodes.range(src.clone()..).take_while(|(p, _)| p.starts_with(&src)).skip(1) {
+                let Some(out) = name(path) else { continue };
+                match &node.link {
+                    Some(link) => sink.symlink(&out, link),
+                    None if node.is_dir => sink.dir(&out, node.mode, node.mtime)?,
+                    None => match self.open(path) {
+                        Ok(mut data) => {
+                            sink.file(&out, &mut data, node.mode, node.mtime)?;
+                        }
+                        Err(e) => sink.refuse(format!("{}: {}", path.display(), e)),
+                    },
+                }
+            }
+            return Ok(sink.finish());
+        }
+        // tar — за один проход по потоку
+        let mut tar = TarReader::new(extract::tar_stream(self.kind, &self.file)?);
+        while let Some(e) = tar.next_entry()? {
+            let Some(path) = extract::safe_relative(&e.name) else { continue };
+            let Some(out) = name(&path).filter(|_| path != src) else { continue };
+            let (mode, mtime) = (Some(e.mode), Some(e.mtime));
+            match e.kind {
+                _ if e.is_file() => {
+                    sink.file(&out, &mut tar, mode, mtime)?;
+                }
+                b'5' => sink.dir(&out, mode, mtime)?,
+                b'2' => sink.symlink(&out, &e.link),
+                // Жёсткая ссылка наружу копируется как обычный файл
+                b'1' => match extract::safe_relative(&e.link).and_then(|t| name(&t)) {
+                    Some(target) => sink.hard_link(&out, &target)?,
+                    None => match self.open(&path) {
+                        Ok(mut data) => {
+                            sink.file(&out, &mut data, mode, mtime)?;
+                        }
+                        Err(err) => sink.refuse(format!("{}: {}", e.name, err)),
+                    },
+                },
+                _ => {}
+            }
+        }
+        Ok(sink.finish())
+    }
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "archives are opened read-only")
+}
+
+/// Пути — как в списке файлов, от `root`
+impl FileSystem for ArchiveMount {
+    /// Каждый открытый архив — отдельное пространство
+    fn id(&self) -> String {
+        format!("archive:{}", self.root.display())
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let target = self.rel(dir).and_then(|rel| self.resolve(&rel)).filter(|t| self.is_dir(t));
+        let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not a folder in the archive"))?;
+        Ok(self.children(&target).filter_map(|(p, _)| p.file_name()).map(|name| dir.join(name)).collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let rel = self.rel(path).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        if rel.as_os_str().is_empty() {
+            return Ok(Metadata { is_dir: true, ..Default::default() });
+        }
+        let node = self.nodes.get(&rel).or_else(|| self.resolve(&rel).and_then(|t| self.nodes.get(&t)));
+        let node = node.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let target = self.resolve(&rel);
+        Ok(Metadata {
+            is_dir: target.as_deref().is_some_and(|t| self.is_dir(t)),
+            size: Some(node.size),
+            modified: to_time(node.mtime),
+            link_target: node.link.as_ref().map(PathBuf::from),
+            broken: target.is_none(),
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+        self.open(&self.rel(path).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?)
+    }
+
+    fn write(&self, _: &Path, _: WriteMode) -> io::Result<Box<dyn io::Write>> {
+        Err(read_only())
+    }
+
+    fn rename(&self, _: &Path, _: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn remove(&self, _: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn mkdir(&self, _: &Path, _: bool) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    /// Папка копируется за один проход по архиву, ссылки проверяются как при распаковке
+    fn export(&self, path: &Path, dest: &Path) -> Option<Result<(), String>> {
+        let rel = self.rel(path)?;
+        Some(match self.copy_out(&rel, dest) {
+            Ok(refused) if refused.is_empty() => Ok(()),
+            Ok(refused) => Err(format!("{} entr(ies) skipped: {}", refused.len(), refused[0])),
+            Err(e) => Err(e.to_string()),
+        })
+    }
+}
+
+impl App {
+    /// Открытый архив, внутри которого лежит `path`, и путь записи в нём; вложенный — раньше внешнего
+    pub fn archive_at(&self, path: &Path) -> Option<(&ArchiveMount, PathBuf)> {
+        self.archives.iter().rev().find_map(|m| path.strip_prefix(&m.root).ok().map(|rel| (m, rel.to_path_buf())))
+    }
+
+    pub fn in_archive(&self) -> bool {
+        self.archive_at(&self.current_dir).is_some()
+    }
+
+    /// Отказ для команд, которые меняют файлы, пока открыт архив
+    pub fn archive_read_only(&mut self, command: &str) -> bool {
+        let blocked = self.focus == Focus::FileList && READ_ONLY_COMMANDS.contains(&command) && self.in_archive();
+        if blocked {
+            self.message = "Archives are opened read-only — copy files out with y and p".to_string();
+        }
+        blocked
+    }
+
+    /// Настоящая папка, где лежит открытый архив (для выхода в оболочку)
+    pub fn real_dir(&self) -> PathBuf {
+        self.archives
+            .iter()
+            .find(|m| self.current_dir.starts_with(&m.root))
+            .and_then(|m| m.root.parent())
+            .map_or_else(|| self.current_dir.clone(), Path::to_path_buf)
+    }
+
+    /// Заголовок списка внутри архива: `archive.zip/inner/dir`
+    pub fn archive_title(&self) -> Option<String> {
+        let outer = self.archives.iter().find(|m| self.current_dir.starts_with(&m.root))?;
+        let base = outer.root.parent()?;
+        Some(self.current_dir.strip_prefix(base).ok()?.to_string_lossy().to_string())
+    }
+
+    /// Папка внутри архива или сам архив, в том числе вложенный. `false` — это обычный файл.
+    pub fn open_archive_path(&mut self, path: &Path) -> bool {
+        if let Some((mount, rel)) = self.archive_at(path)
+            && mount.resolve(&rel).is_some_and(|t| mount.is_dir(&t))
+        {
+            self.current_dir = path.to_path_buf();
+            self.refresh_items();
+            return true;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let Some(kind) = extract::kind_of(&name) else {
+            return false;
+        };
+        if !self.archives.iter().any(|m| m.root == path) {
+            let provider = self.fs_for(path);
+            let mount = if provider.is_local() {
+                ArchiveMount::mount(path.to_path_buf(), path.to_path_buf(), kind, false)
+            } else {
+                // Вложенный или удалённый архив читается из временной копии
+                crate::vfs::temp_file("ffm-archive-", &format!("-{}", name)).and_then(|(temp, mut file)| {
+                    let spooled = provider.read(path).and_then(|mut data| io::copy(&mut data, &mut file));
+                    spooled.and_then(|_| ArchiveMount::mount(path.to_path_buf(), temp.clone(), kind, true)).inspect_err(|_| {
+                        let _ = fs::remove_file(&temp);
+                    })
+                })
+            };
+            match mount {
+                Ok(mount) => {
+                    self.message = format!("{}: {} entries (read-only)", name, mount.nodes.len());
+                    self.archives.push(mount);
+                }
+                Err(e) => {
+                    self.message = format!("Cannot open {}: {}", name, e);
+                    return true;
+                }
+            }
+        }
+        self.current_dir = path.to_path_buf();
+        self.refresh_items();
+        true
+    }
+
+    /// Ушли из архива — закрываем его, если на него не ссылается буфер обмена (вызывается из `refresh_items`)
+    pub fn prune_archives(&mut self) {
+        let keep: Vec<bool> = self
+            .archives
+            .iter()
+            .map(|m| {
+                self.current_dir.starts_with(&m.root)
+                    || self.clipboard.as_ref().is_some_and(|(paths, _)| paths.iter().any(|p| p.starts_with(&m.root)))
+            })
+            .collect();
+        let mut keep = keep.into_iter();
+        self.archives.retain(|_| keep.next().unwrap_or(false));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Заголовок ustar и данные записи; `kind` — `b'0'` файл, `b'2'` ссылка, `b'1'` жёсткая ссылка
+    pub fn tar_entry(out: &mut Vec<u8>, name: &str, kind: u8, link: &str, data: &[u8]) {
+        let mut h = [0u8; 512];
+        h[..name.len()].copy_from_slice(name.as_bytes());
+        h[100..107].copy_from_slice(b"0000644");
+        h[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        h[136..147].copy_from_slice(b"14000000000");
+        h[156] = kind;
+        h[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        h[148..156].fill(b' ');
+        let sum: u32 = h.iter().map(|&b| b as u32).sum();
+        h[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        out.extend_from_slice(&h);
+        out.extend_from_slice(data);
+        out.resize(out.len().div_ceil(512) * 512, 0);
+    }
+
+    #[test]
+    fn browses_and_copies_out_of_tar() {
+        let dir = std::env::temp_dir().join(format!("ffm_archive_view_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "top/docs/a.txt", b'0', "", b"hello");
+        tar_entry(&mut tar, "top/b.txt", b'0', "", b"world!");
+        tar_entry(&mut tar, "top/link", b'2', "docs/a.txt", b"");
+        tar_entry(&mut tar, "top/away", b'2', "../../etc/passwd", b"");
+        tar_entry(&mut tar, "../evil", b'0', "", b"x");
+        tar.resize(tar.len() + 1024, 0);
+        let file = dir.join("t.tar");
+        fs::write(&file, &tar).unwrap();
+
+        let mount = ArchiveMount::mount(file.clone(), file, Kind::Native(Format::Tar), false).unwrap();
+        let top: Vec<_> = mount.children(Path::new("")).map(|(p, _)| p.clone()).collect();
+        assert_eq!(top, vec![PathBuf::from("top")]);
+        assert_eq!(mount.nodes[Path::new("top")].size, 11);
+        assert_eq!(mount.resolve(Path::new("top/link")), Some(PathBuf::from("top/docs/a.txt")));
+        assert_eq!(mount.resolve(Path::new("top/away")), None);
+        let mut text = String::new();
+        mount.open(Path::new("top/link")).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello");
+
+        let refused = mount.copy_out(Path::new("top"), &dir.join("out")).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("out/docs/a.txt")).unwrap(), "hello");
+        assert_eq!(fs::read_to_string(dir.join("out/link")).unwrap(), "hello");
+        assert!(fs::symlink_metadata(dir.join("out/away")).is_err());
+        assert_eq!(refused.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::app::App;
+use crate::rename::{apply_renames, chained, check_renames, name_error};
+use crate::undo::UndoEntry;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Массовое переименование через $EDITOR: имена пишутся во временный файл
+/// по одному на строку, после редактора строки сопоставляются по порядку.
+pub struct BulkRename {
+    pub sources: Vec<PathBuf>,
+    pub file: PathBuf,
+    /// Все пары `(старый, новый)`, включая неизменённые; пусто — ждём редактор
+    pub plan: Vec<(PathBuf, PathBuf)>,
+    /// Индексы пар, которые участвуют в обмене/цепочке
+    pub chained: HashSet<usize>,
+    pub scroll: u16,
+}
+
+impl BulkRename {
+    /// Только изменённые пары (для окна подтверждения)
+    pub fn changes(&self) -> impl Iterator<Item = (usize, &(PathBuf, PathBuf))> {
+        self.plan.iter().enumerate().filter(|(_, (src, dst))| src != dst)
+    }
+
+    pub fn awaiting_confirm(&self) -> bool {
+        !self.plan.is_empty()
+    }
+}
+
+/// Сопоставляет строки отредактированного файла с исходными путями
+pub fn parse_names(sources: &[PathBuf], content: &str) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let mut lines: Vec<&str> = content.lines().collect();
+    // Редакторы добавляют перевод строки в конце — пустой хвост не считается
+    while lines.last().is_some_and(|l| l.is_empty()) && lines.len() > sources.len() {
+        lines.pop();
+    }
+    if lines.len() != sources.len() {
+        return Err(format!(
+            "Line count changed: expected {}, got {} (lines can't be added or removed)",
+            sources.len(),
+            lines.len()
+        ));
+    }
+    sources
+        .iter()
+        .zip(lines)
+        .enumerate()
+        .map(|(i, (src, name))| {
+            let name = name.trim_end_matches('\r');
+            match name_error(name) {
+                Some(err) => Err(format!("line {}: {}", i + 1, err)),
+                None => Ok((src.clone(), src.with_file_name(name))),
+            }
+        })
+        .collect()
+}
+
+impl App {
+    /// Выбранные элементы, а без выбора — весь текущий список
+    pub fn bulk_targets(&self) -> Vec<PathBuf> {
+        if self.selected_indices.is_empty() {
+            return self.filtered_items.clone();
+        }
+        let mut indices: Vec<usize> = self.selected_indices.iter().copied().collect();
+        indices.sort_unstable();
+        indices.iter().filter_map(|&i| self.filtered_items.get(i).cloned()).collect()
+    }
+
+    pub fn start_bulk_rename(&mut self) {
+        let sources = self.bulk_targets();
+        if sources.is_empty() {
+            self.message = "Nothing to rename".to_string();
+            return;
+        }
+        let names: Vec<String> = sources
+            .iter()
+            .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+            .collect();
+        if names.iter().any(|n| n.contains('\n')) {
+            self.message = "Bulk rename: a name contains a newline".to_string();
+            return;
+        }
+        // Свой файл 0600 под случайным именем: план не подменит другой пользователь
+        let written = crate::vfs::temp_file("ffm-bulk-rename-", ".txt").and_then(|(file, mut out)| {
+            out.write_all((names.join("\n") + "\n").as_bytes()).map(|_| file.clone()).inspect_err(|_| {
+                let _ = fs::remove_file(&file);
+            })
+        });
+        let file = match written {
+            Ok(file) => file,
+            Err(e) => {
+                self.message = format!("Bulk rename: {}", e);
+                return;
+            }
+        };
+        self.file_to_edit = Some(file.clone());
+        self.bulk_rename = Some(BulkRename {
+            sources,
+            file,
+            plan: Vec::new(),
+            chained: HashSet::new(),
+            scroll: 0,
+        });
+    }
+
+    /// Вызывается после закрытия редактора: читает файл и строит план
+    pub fn finish_bulk_edit(&mut self) {
+        let Some(bulk) = self.bulk_rename.as_ref() else {
+            return;
+        };
+        let plan = fs::read_to_string(&bulk.file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_names(&bulk.sources, &content))
+            .and_then(|plan| check_renames(&plan).map(|_| plan));
+        match plan {
+            Ok(plan) if plan.iter().all(|(src, dst)| src == dst) => {
+                self.message = "Bulk rename: no names changed".to_string();
+                self.cancel_bulk_rename();
+            }
+            Ok(plan) => {
+                if let Some(bulk) = self.bulk_rename.as_mut() {
+                    bulk.chained = chained(&plan);
+                    bulk.plan = plan;
+                    bulk.scroll = 0;
+                }
+            }
+            Err(e) => {
+                self.message = format!("Bulk rename: {}", e);
+                self.cancel_bulk_rename();
+            }
+        }
+    }
+
+    fn cancel_bulk_rename(&mut self) {
+        if let Some(bulk) = self.bulk_rename.take() {
+            let _ = fs::remove_file(&bulk.file);
+        }
+    }
+
+    fn apply_bulk_rename(&mut self) {
+        let Some(bulk) = self.bulk_rename.take() else {
+            return;
+        };
+        let _ = fs::remove_file(&bulk.file);
+        // Диск мог измениться, пока было открыто окно подтверждения
+        let res = check_renames(&bulk.plan).and_then(|_| apply_renames(&bulk.plan));
+        match res {
+            Ok(n) => {
+                let changed = bulk.plan.into_iter().filter(|(src, dst)| src != dst).collect::<Vec<_>>();
+                self.tags_moved(&changed);
+                self.push_undo(UndoEntry::Rename(changed));
+                self.selected_indices.clear();
+                self.message = format!("Renamed {} item(s)", n);
+            }
+            Err(e) => self.message = format!("Bulk rename failed, nothing changed: {}", e),
+        }
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    pub fn handle_bulk_rename_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => self.apply_bulk_rename(),
+            // Вернуться в редактор с уже исправленными именами
+            KeyCode::Char('e') => {
+                if let Some(bulk) = self.bulk_rename.as_mut() {
+                    bulk.plan.clear();
+                    self.file_to_edit = Some(bulk.file.clone());
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
+                self.cancel_bulk_rename();
+                self.message = "Bulk rename cancelled".to_string();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(bulk) = self.bulk_rename.as_mut() {
+                    bulk.scroll = bulk.scroll.saturating_add(1);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(bulk) = self.bulk_rename.as_mut() {
+                    bulk.scroll = bulk.scroll.saturating_sub(1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_count_must_match() {
+        let sources = vec![PathBuf::from("/d/a.jpg"), PathBuf::from("/d/b.jpg")];
+        let plan = parse_names(&sources, "x.jpg\nb.jpg\n").unwrap();
+        assert_eq!(plan[0].1, PathBuf::from("/d/x.jpg"));
+        assert_eq!(plan[1].0, plan[1].1);
+        assert!(parse_names(&sources, "x.jpg\n").is_err());
+        assert!(parse_names(&sources, "a\nb\nc\n").is_err());
+        assert!(parse_names(&sources, "\nb.jpg\n").is_err());
+    }
+}
@@ -0,0 +1,397 @@
+use crate::app::App;
+use crate::hashes::{ALGORITHMS, Algorithm, MultiHasher};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const READ_BUF: usize = 256 * 1024;
+
+/// Расширения одиночных файлов сумм (`foo.iso.sha256`)
+const SUMS_EXTENSIONS: &[&str] = &["sha256", "sha1", "md5", "b3"];
+
+pub enum Outcome {
+    Digests(Vec<(Algorithm, String)>),
+    Ok,
+    Failed,
+    Missing,
+    Error(String),
+}
+
+impl Outcome {
+    pub fn label(&self) -> &str {
+        match self {
+            Outcome::Digests(_) => "",
+            Outcome::Ok => "OK",
+            Outcome::Failed => "FAILED",
+            Outcome::Missing => "MISSING",
+            Outcome::Error(e) => e,
+        }
+    }
+}
+
+pub struct FileResult {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// Общее состояние с фоновым потоком
+#[derive(Default)]
+pub struct ChecksumProgress {
+    pub total_bytes: u64,
+    pub done_bytes: u64,
+    pub current: String,
+    pub results: Vec<FileResult>,
+    pub finished: bool,
+}
+
+/// Задание: файл и, при проверке, ожидаемая сумма
+struct Task {
+    name: String,
+    path: PathBuf,
+    expected: Option<(Algorithm, String)>,
+}
+
+pub struct Checksums {
+    /// Файл сумм в режиме проверки
+    pub sums_file: Option<String>,
+    pub files: usize,
+    pub skipped_dirs: usize,
+    pub progress: Arc<Mutex<ChecksumProgress>>,
+    cancel: Arc<AtomicBool>,
+    pub selected: usize,
+}
+
+impl Checksums {
+    fn start(tasks: Vec<Task>, sums_file: Option<String>, skipped_dirs: usize) -> Self {
+        let total_bytes = tasks.iter().filter_map(|t| fs::metadata(&t.path).ok()).map(|m| m.len()).sum();
+        let progress = Arc::new(Mutex::new(ChecksumProgress { total_bytes, ..Default::default() }));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let files = tasks.len();
+        let (shared, stop) = (progress.clone(), cancel.clone());
+        thread::spawn(move || run_tasks(tasks, &shared, &stop));
+        Checksums { sums_file, files, skipped_dirs, progress, cancel, selected: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.progress.lock().map(|p| p.finished).unwrap_or(true)
+    }
+
+    /// Строки, по которым ходит курсор: (файл, номер алгоритма)
+    pub fn rows(results: &[FileResult]) -> Vec<(usize, Option<usize>)> {
+        let mut rows = Vec::new();
+        for (i, r) in results.iter().enumerate() {
+            match &r.outcome {
+                Outcome::Digests(d) => rows.extend((0..d.len()).map(|j| (i, Some(j)))),
+                _ => rows.push((i, None)),
+            }
+        }
+        rows
+    }
+
+    /// Итог проверки: (OK, FAILED, MISSING, ошибки)
+    pub fn counts(results: &[FileResult]) -> (usize, usize, usize, usize) {
+        let mut counts = (0, 0, 0, 0);
+        for r in results {
+            match r.outcome {
+                Outcome::Ok => counts.0 += 1,
+                Outcome::Failed => counts.1 += 1,
+                Outcome::Missing => counts.2 += 1,
+                Outcome::Error(_) => counts.3 += 1,
+                Outcome::Digests(_) => {}
+            }
+        }
+        counts
+    }
+
+    /// Текст выбранной строки для буфера обмена
+    fn selected_text(&self) -> Option<String> {
+        let progress = self.progress.lock().ok()?;
+        let (file, alg) = *Self::rows(&progress.results).get(self.selected)?;
+        let result = progress.results.get(file)?;
+        match (&result.outcome, alg) {
+            (Outcome::Digests(d), Some(j)) => d.get(j).map(|(_, hex)| hex.clone()),
+            (outcome, _) => Some(format!("{}: {}", result.name, outcome.label())),
+        }
+    }
+
+    /// Весь отчёт: суммы в формате `sha256sum`, по алгоритмам, или строки проверки
+    fn report(&self) -> String {
+        let Ok(progress) = self.progress.lock() else {
+            return String::new();
+        };
+        let mut out = String::new();
+        for alg in ALGORITHMS {
+            let lines: Vec<String> = progress
+                .results
+                .iter()
+                .filter_map(|r| match &r.outcome {
+                    Outcome::Digests(d) => d.iter().find(|(a, _)| a == alg).map(|(_, hex)| format!("{}  {}", hex, r.name)),
+                    _ => None,
+                })
+                .collect();
+            if !lines.is_empty() {
+                out.push_str(&format!("# {}\n{}\n", alg.label(), lines.join("\n")));
+            }
+        }
+        for r in &progress.results {
+            if !matches!(r.outcome, Outcome::Digests(_)) {
+                out.push_str(&format!("{}: {}\n", r.name, r.outcome.label()));
+            }
+        }
+        out
+    }
+}
+
+impl Drop for Checksums {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn run_tasks(tasks: Vec<Task>, progress: &Mutex<ChecksumProgress>, cancel: &AtomicBool) {
+    for task in tasks {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Ok(mut p) = progress.lock() {
+            p.current = task.name.clone();
+        }
+        let outcome = match &task.expected {
+            None => match hash_file(&task.path, ALGORITHMS, progress, cancel) {
+                Ok(digests) => Outcome::Digests(digests),
+                Err(e) => Outcome::Error(e.to_string()),
+            },
+            Some(_) if !task.path.is_file() => Outcome::Missing,
+            Some((alg, expected)) => match hash_file(&task.path, &[*alg], progress, cancel) {
+                Ok(digests) if digests.first().is_some_and(|(_, d)| d.eq_ignore_ascii_case(expected)) => Outcome::Ok,
+                Ok(_) => Outcome::Failed,
+                Err(e) => Outcome::Error(e.to_string()),
+            },
+        };
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Ok(mut p) = progress.lock() {
+            p.results.push(FileResult { name: task.name, outcome });
+        }
+    }
+    if let Ok(mut p) = progress.lock() {
+        p.current.clear();
+        p.finished = true;
+    }
+}
+
+fn hash_file(
+    path: &Path,
+    algorithms: &[Algorithm],
+    progress: &Mutex<ChecksumProgress>,
+    cancel: &AtomicBool,
+) -> io::Result<Vec<(Algorithm, String)>> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = MultiHasher::new(algorithms);
+    let mut buf = vec![0u8; READ_BUF];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        if let Ok(mut p) = progress.lock() {
+            p.done_bytes += n as u64;
+        }
+    }
+    Ok(hasher.finish())
+}
+
+pub fn is_sums_file(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    let ext = Path::new(name).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+    (upper.contains("SUMS") && Algorithm::from_sums_name(name).is_some())
+        || ext.is_some_and(|e| SUMS_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Разбирает файл сумм: `hex  name`, `hex *name`, BSD `SHA256 (name) = hex`
+/// и одиночный `hex` (тогда имя — сам файл сумм без расширения)
+pub fn parse_sums(text: &str, sums_name: &str) -> Vec<(Algorithm, String, String)> {
+    let default_alg = Algorithm::from_sums_name(sums_name);
+    let bare_name = Path::new(sums_name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // BSD-формат
+        if let Some((tag, rest)) = line.split_once(" (")
+            && let Some((name, hex)) = rest.rsplit_once(") = ")
+            && let Some(alg) = Algorithm::from_sums_name(tag)
+        {
+            entries.push((alg, hex.trim().to_lowercase(), name.to_string()));
+            continue;
+        }
+        // GNU экранирует имена с `\` и переводом строки, помечая строку ведущим `\`
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (hex, name) = match line.split_once(' ') {
+            Some((hex, rest)) => {
+                let rest = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*')).unwrap_or(rest);
+                (hex, rest.to_string())
+            }
+            None => (line.trim(), bare_name.clone()),
+        };
+        if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        let by_len = ALGORITHMS.iter().copied().find(|a| a.hex_len() == hex.len());
+        let Some(alg) = default_alg.filter(|a| a.hex_len() == hex.len()).or(by_len) else {
+            continue;
+        };
+        let name = if escaped { name.replace("\\n", "\n").replace("\\\\", "\\") } else { name };
+        entries.push((alg, hex.to_lowercase(), name));
+    }
+    entries
+}
+
+impl App {
+    /// Суммы выделенных файлов или файла под курсором; каталоги пропускаются
+    pub fn start_checksums(&mut self) {
+        let targets = self.bulk_targets_or_highlighted();
+        let (files, dirs): (Vec<PathBuf>, Vec<PathBuf>) = targets.into_iter().partition(|p| !p.is_dir());
+        if files.is_empty() {
+            self.message = if dirs.is_empty() { "Checksum: nothing selected" } else { "Checksum: directories are skipped" }.to_string();
+            return;
+        }
+        let tasks = files
+            .into_iter()
+            .map(|path| Task { name: path.file_name().unwrap_or_default().to_string_lossy().to_string(), path, expected: None })
+            .collect();
+        self.checksums = Some(Checksums::start(tasks, None, dirs.len()));
+    }
+
+    /// Проверка по файлу сумм: явно указанному, под курсором или найденному в каталоге
+    pub fn verify_checksums(&mut self, file: Option<&str>) {
+        let sums = match file {
+            Some(f) => Some(self.current_dir.join(f)),
+            None => self.find_sums_file(),
+        };
+        let Some(sums) = sums else {
+            self.message = "Verify: no SHA256SUMS or *.sha256 file here".to_string();
+            return;
+        };
+        let text = match fs::read_to_string(&sums) {
+            Ok(t) => t,
+            Err(e) => {
+                self.message = format!("Verify {}: {}", sums.display(), e);
+                return;
+            }
+        };
+        let sums_name = sums.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let entries = parse_sums(&text, &sums_name);
+        if entries.is_empty() {
+            self.message = format!("Verify: no checksums found in {}", sums_name);
+            return;
+        }
+        let base = sums.parent().map(Path::to_path_buf).unwrap_or_else(|| self.current_dir.clone());
+        let tasks = entries
+            .into_iter()
+            .map(|(alg, hex, name)| Task { path: base.join(&name), name, expected: Some((alg, hex)) })
+            .collect();
+        self.checksums = Some(Checksums::start(tasks, Some(sums_name), 0));
+    }
+
+    fn find_sums_file(&self) -> Option<PathBuf> {
+        let highlighted = self.state.selected().and_then(|i| self.filtered_items.get(i));
+        if let Some(path) = highlighted {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_file() && is_sums_file(&name) {
+                return Some(path.clone());
+            }
+            // foo.iso → foo.iso.sha256
+            for ext in SUMS_EXTENSIONS {
+                let candidate = path.with_file_name(format!("{}.{}", name, ext));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        let mut found: Vec<PathBuf> = fs::read_dir(&self.current_dir)
+            .ok()?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && is_sums_file(&p.file_name().unwrap_or_default().to_string_lossy()))
+            .collect();
+        // SHA256SUMS и прочие *SUMS раньше одиночных файлов
+        found.sort_by_key(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy().to_uppercase();
+            (!name.contains("SUMS"), !name.contains("SHA256"), name)
+        });
+        found.into_iter().next()
+    }
+
+    pub fn handle_checksums_input(&mut self, key: KeyEvent) {
+        let Some(popup) = self.checksums.as_mut() else {
+            return;
+        };
+        let rows = popup.progress.lock().map(|p| Checksums::rows(&p.results).len()).unwrap_or(0);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if !popup.finished() {
+                    self.message = "Checksum cancelled".to_string();
+                }
+                self.checksums = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') if rows > 0 => popup.selected = (popup.selected + 1) % rows,
+            KeyCode::Up | KeyCode::Char('k') if rows > 0 => popup.selected = (popup.selected + rows - 1) % rows,
+            KeyCode::Char('y') => {
+                if let Some(text) = popup.selected_text() {
+                    self.message = match crate::system_clipboard::copy_text(&text) {
+                        Ok(via) => format!("Copied {} ({})", text, via),
+                        Err(e) => format!("Copy failed: {}", e),
+                    };
+                }
+            }
+            KeyCode::Char('Y') => {
+                let report = popup.report();
+                self.message = match crate::system_clipboard::copy_text(&report) {
+                    Ok(via) => format!("Copied report, {} line(s) ({})", report.lines().count(), via),
+                    Err(e) => format!("Copy failed: {}", e),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sums_formats() {
+        let sha = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let text = format!(
+            "# comment\n{sha}  a.txt\n{sha} *b.bin\nSHA256 (c d.txt) = {sha}\n\\{sha}  back\\\\slash\nnot a hash\n"
+        );
+        let entries = parse_sums(&text, "SHA256SUMS");
+        let names: Vec<&str> = entries.iter().map(|(_, _, n)| n.as_str()).collect();
+        assert_eq!(names, ["a.txt", "b.bin", "c d.txt", "back\\slash"]);
+        assert!(entries.iter().all(|(a, h, _)| *a == Algorithm::Sha256 && h == sha));
+
+        // Одиночный файл с одной суммой
+        let md5 = parse_sums("D41D8CD98F00B204E9800998ECF8427E\n", "x.iso.md5");
+        assert_eq!(md5[0].0, Algorithm::Md5);
+        assert_eq!(md5[0].2, "x.iso");
+        assert!(is_sums_file("SHA256SUMS") && is_sums_file("x.iso.b3") && !is_sums_file("sums.txt"));
+    }
+}
@@ -0,0 +1,520 @@
+use crate::app::App;
+use crate::commands::glob_match;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Каталоги систем контроля версий не трогаем: пустые папки внутри `.git` нужны
+const SKIP_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    Junk,
+    EmptyFiles,
+    EmptyDirs,
+    BrokenLinks,
+}
+
+impl Category {
+    pub const ALL: [Category; 4] = [Category::Junk, Category::EmptyFiles, Category::EmptyDirs, Category::BrokenLinks];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Junk => "Junk",
+            Category::EmptyFiles => "Empty files",
+            Category::EmptyDirs => "Empty folders",
+            Category::BrokenLinks => "Broken symlinks",
+        }
+    }
+}
+
+/// Шаблон мусора из `cleanup_patterns`: `*.tmp`, `__pycache__/` (только папки), `target/ 30d` (не новее 30 дней)
+#[derive(Clone, PartialEq, Debug)]
+pub struct JunkPattern {
+    pub glob: String,
+    pub dir_only: bool,
+    pub min_age: Option<Duration>,
+}
+
+impl JunkPattern {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut words = spec.split_whitespace();
+        let glob = words.next()?;
+        let min_age = match words.next() {
+            Some(age) => Some(Duration::from_secs(age.strip_suffix('d')?.parse::<u64>().ok()? * 24 * 60 * 60)),
+            None => None,
+        };
+        let (glob, dir_only) = match glob.strip_suffix('/') {
+            Some(g) => (g, true),
+            None => (glob, false),
+        };
+        Some(JunkPattern { glob: glob.to_string(), dir_only, min_age })
+    }
+
+    fn matches(&self, name: &str, is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && glob_match(&self.glob, name)
+    }
+}
+
+#[derive(Clone)]
+pub struct CleanupItem {
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+pub struct CleanupGroup {
+    pub category: Category,
+    pub items: Vec<CleanupItem>,
+}
+
+impl CleanupGroup {
+    pub fn size(&self) -> u64 {
+        self.items.iter().map(|i| i.size).sum()
+    }
+}
+
+#[derive(Default)]
+pub struct CleanupProgress {
+    pub dirs: usize,
+    pub found: usize,
+    pub current: String,
+    pub errors: usize,
+    pub groups: Vec<CleanupGroup>,
+    pub finished: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CleanupRow {
+    Group(usize),
+    Item(usize, usize),
+}
+
+pub struct CleanupView {
+    pub root: PathBuf,
+    pub progress: Arc<Mutex<CleanupProgress>>,
+    cancel: Arc<AtomicBool>,
+    pub groups: Vec<CleanupGroup>,
+    pub loaded: bool,
+    pub state: ListState,
+    pub marked: HashSet<PathBuf>,
+}
+
+impl CleanupView {
+    pub fn rows(&self) -> Vec<CleanupRow> {
+        let mut rows = Vec::new();
+        for (g, group) in self.groups.iter().enumerate() {
+            rows.push(CleanupRow::Group(g));
+            rows.extend((0..group.items.len()).map(|i| CleanupRow::Item(g, i)));
+        }
+        rows
+    }
+
+    /// Отмеченные пути в порядке показа
+    pub fn marked_paths(&self) -> Vec<PathBuf> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.items.iter())
+            .filter(|i| self.marked.contains(&i.path))
+            .map(|i| i.path.clone())
+            .collect()
+    }
+
+    /// (число, объём) отмеченных
+    pub fn marked_total(&self) -> (usize, u64) {
+        self.groups
+            .iter()
+            .flat_map(|g| g.items.iter())
+            .filter(|i| self.marked.contains(&i.path))
+            .fold((0, 0), |(n, bytes), i| (n + 1, bytes + i.size))
+    }
+
+    fn selected_row(&self) -> Option<CleanupRow> {
+        self.rows().get(self.state.selected()?).copied()
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let len = self.rows().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state.select(Some((current + delta).rem_euclid(len as isize) as usize));
+    }
+
+    /// Пробел на заголовке переключает всю категорию
+    fn toggle_selected(&mut self) {
+        let paths: Vec<PathBuf> = match self.selected_row() {
+            Some(CleanupRow::Group(g)) => self.groups[g].items.iter().map(|i| i.path.clone()).collect(),
+            Some(CleanupRow::Item(g, i)) => vec![self.groups[g].items[i].path.clone()],
+            None => return,
+        };
+        if paths.iter().all(|p| self.marked.contains(p)) {
+            for p in &paths {
+                self.marked.remove(p);
+            }
+        } else {
+            self.marked.extend(paths);
+        }
+    }
+
+    fn mark_all(&mut self) {
+        self.marked = self.groups.iter().flat_map(|g| g.items.iter().map(|i| i.path.clone())).collect();
+    }
+
+    /// Убирает обработанные пути и опустевшие категории
+    pub fn forget(&mut self, done: &HashSet<PathBuf>) {
+        for group in &mut self.groups {
+            group.items.retain(|i| !done.contains(&i.path));
+        }
+        self.groups.retain(|g| !g.items.is_empty());
+        self.marked.retain(|p| !done.contains(p));
+        let len = self.rows().len();
+        self.state.select(if len == 0 { None } else { Some(self.state.selected().unwrap_or(0).min(len - 1)) });
+    }
+}
+
+impl Drop for CleanupView {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn set_progress(progress: &Mutex<CleanupProgress>, f: impl FnOnce(&mut CleanupProgress)) {
+    if let Ok(mut p) = progress.lock() {
+        f(&mut p);
+    }
+}
+
+#[cfg(unix)]
+fn device(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Размер поддерева и время последнего изменения в нём (без перехода по ссылкам)
+fn tree_stats(path: &Path) -> (u64, Option<SystemTime>) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return (0, None);
+    };
+    let mut newest = meta.modified().ok();
+    if !meta.is_dir() {
+        return (meta.len(), newest);
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+        let (s, m) = tree_stats(&entry.path());
+        size += s;
+        newest = newest.max(m);
+    }
+    (size, newest)
+}
+
+struct Walker<'a> {
+    patterns: &'a [JunkPattern],
+    dev: Option<u64>,
+    now: SystemTime,
+    progress: &'a Mutex<CleanupProgress>,
+    cancel: &'a AtomicBool,
+    found: [Vec<CleanupItem>; 4],
+}
+
+impl Walker<'_> {
+    fn push(&mut self, category: Category, item: CleanupItem) {
+        self.found[category as usize].push(item);
+    }
+
+    /// Мусор: имя подходит под шаблон, а содержимое не новее заданного возраста
+    fn junk(&self, path: &Path, name: &str, is_dir: bool) -> Option<CleanupItem> {
+        let pattern = self.patterns.iter().find(|p| p.matches(name, is_dir))?;
+        let (size, newest) = tree_stats(path);
+        if let Some(age) = pattern.min_age {
+            let old_enough = newest.is_some_and(|m| self.now.duration_since(m).unwrap_or_default() >= age);
+            if !old_enough {
+                return None;
+            }
+        }
+        Some(CleanupItem { path: path.to_path_buf(), size, is_dir })
+    }
+
+    /// Возвращает true, если в поддереве нет ничего, кроме пустых папок.
+    /// Такие папки показываются самой верхней, без вложенных.
+    fn walk(&mut self, dir: &Path, is_root: bool) -> bool {
+        if self.cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        let Ok(read) = fs::read_dir(dir) else {
+            set_progress(self.progress, |p| p.errors += 1);
+            return false;
+        };
+        let mut empty = true;
+        let mut empty_children = Vec::new();
+        for entry in read.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(meta) = entry.metadata() else {
+                empty = false;
+                continue;
+            };
+            if meta.file_type().is_symlink() {
+                empty = false;
+                if fs::metadata(&path).is_err() {
+                    self.push(Category::BrokenLinks, CleanupItem { path, size: 0, is_dir: false });
+                }
+            } else if meta.is_dir() {
+                if let Some(item) = self.junk(&path, &name, true) {
+                    empty = false;
+                    self.push(Category::Junk, item);
+                } else if SKIP_DIRS.contains(&name.as_str()) || (self.dev.is_some() && device(&meta) != self.dev) {
+                    empty = false;
+                } else if self.walk(&path, false) {
+                    empty_children.push(path);
+                } else {
+                    empty = false;
+                }
+            } else {
+                empty = false;
+                if let Some(item) = self.junk(&path, &name, false) {
+                    self.push(Category::Junk, item);
+                } else if meta.is_file() && meta.len() == 0 {
+                    self.push(Category::EmptyFiles, CleanupItem { path, size: 0, is_dir: false });
+                }
+            }
+        }
+        if !empty || is_root {
+            for path in empty_children {
+                self.push(Category::EmptyDirs, CleanupItem { path, size: 0, is_dir: true });
+            }
+        }
+        let found = self.found.iter().map(Vec::len).sum();
+        set_progress(self.progress, |p| {
+            p.dirs += 1;
+            p.found = found;
+            p.current = dir.to_string_lossy().to_string();
+        });
+        empty
+    }
+}
+
+/// Обход дерева в пределах одной файловой системы, без перехода по ссылкам
+pub fn find_cleanup(root: &Path, patterns: &[JunkPattern], progress: &Mutex<CleanupProgress>, cancel: &AtomicBool) -> Vec<CleanupGroup> {
+    let dev = fs::metadata(root).ok().as_ref().and_then(device);
+    let mut walker = Walker { patterns, dev, now: SystemTime::now(), progress, cancel, found: Default::default() };
+    walker.walk(root, true);
+    Category::ALL
+        .into_iter()
+        .zip(walker.found)
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(category, mut items)| {
+            items.sort_by(|a, b| a.path.cmp(&b.path));
+            CleanupGroup { category, items }
+        })
+        .collect()
+}
+
+impl App {
+    pub fn open_cleanup(&mut self, dir: Option<&str>) {
+        let root = match dir {
+            Some(d) => crate::commands::expand_path(&self.current_dir, d),
+            None => self.current_dir.clone(),
+        };
+        if !root.is_dir() {
+            self.message = format!("Cleanup: {} is not a directory", root.display());
+            return;
+        }
+        let mut patterns = Vec::new();
+        for spec in &self.config.cleanup_patterns {
+            match JunkPattern::parse(spec) {
+                Some(p) => patterns.push(p),
+                None => {
+                    self.message = format!("Cleanup: bad pattern '{}' (expected e.g. \"*.tmp\" or \"target/ 30d\")", spec);
+                    return;
+                }
+            }
+        }
+        let progress = Arc::new(Mutex::new(CleanupProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (shared, stop, scan_root) = (progress.clone(), cancel.clone(), root.clone());
+        thread::spawn(move || {
+            let groups = find_cleanup(&scan_root, &patterns, &shared, &stop);
+            set_progress(&shared, |p| {
+                p.groups = groups;
+                p.current.clear();
+                p.finished = true;
+            });
+        });
+        self.cleanup = Some(CleanupView {
+            root,
+            progress,
+            cancel,
+            groups: Vec::new(),
+            loaded: false,
+            state: ListState::default(),
+            marked: HashSet::new(),
+        });
+    }
+
+    /// Забирает результат скана; всё найденное сразу отмечено (вызывается из `tick`)
+    pub fn poll_cleanup(&mut self) {
+        let Some(view) = self.cleanup.as_mut().filter(|v| !v.loaded) else {
+            return;
+        };
+        let Ok(mut progress) = view.progress.lock() else {
+            return;
+        };
+        if !progress.finished {
+            return;
+        }
+        view.groups = std::mem::take(&mut progress.groups);
+        let errors = progress.errors;
+        drop(progress);
+        view.loaded = true;
+        view.mark_all();
+        view.state.select(if view.groups.is_empty() { None } else { Some(1) });
+        let (count, bytes) = view.marked_total();
+        self.message = format!(
+            "Found {} item(s) to clean up, {}{}",
+            count,
+            crate::ui::format_size(bytes),
+            if errors > 0 { format!(", {} unreadable", errors) } else { String::new() }
+        );
+    }
+
+    /// Отмеченное уходит в обычное подтверждение удаления, но в корзину
+    fn prompt_cleanup_trash(&mut self) {
+        let Some(view) = self.cleanup.as_ref() else {
+            return;
+        };
+        let (count, bytes) = view.marked_total();
+        if count == 0 {
+            self.message = "Nothing marked (Space to mark, a for all)".to_string();
+            return;
+        }
+        self.pending_trash = view.marked_paths();
+        self.pending_delete = None;
+        self.confirm_delete = true;
+        self.message = format!("Trash {} item(s), {}? (y/N)", count, crate::ui::format_size(bytes));
+    }
+
+    /// Переносит `pending_trash` в корзину и убирает перенесённое из отчёта
+    pub fn trash_pending(&mut self) {
+        let mut done = HashSet::new();
+        let mut errors = Vec::new();
+        for path in std::mem::take(&mut self.pending_trash) {
+            match crate::trash::trash(&path) {
+                Ok(_) => {
+                    done.insert(path);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if let Some(view) = self.cleanup.as_mut() {
+            view.forget(&done);
+        }
+        self.message = match errors.first() {
+            None => format!("Trashed {} item(s).", done.len()),
+            Some(first) => format!("Trashed {} item(s), {} failed: {}", done.len(), errors.len(), first),
+        };
+    }
+
+    pub fn handle_cleanup_input(&mut self, key: KeyEvent) {
+        let Some(view) = self.cleanup.as_mut() else {
+            return;
+        };
+
+        match self.keymap.lookup(&key) {
+            Some("down") => return view.move_by(1),
+            Some("up") => return view.move_by(-1),
+            Some("top") => return view.state.select(Some(0)),
+            Some("bottom") => return view.state.select(Some(view.rows().len().saturating_sub(1))),
+            Some("select") => {
+                view.toggle_selected();
+                return view.move_by(1);
+            }
+            Some("delete") => return self.prompt_cleanup_trash(),
+            _ => {}
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if !view.loaded {
+                    self.message = "Cleanup scan cancelled".to_string();
+                }
+                self.cleanup = None;
+            }
+            KeyCode::Down => view.move_by(1),
+            KeyCode::Up => view.move_by(-1),
+            KeyCode::Char('a') => view.mark_all(),
+            KeyCode::Char('u') => view.marked.clear(),
+            KeyCode::Char('d') | KeyCode::Delete => self.prompt_cleanup_trash(),
+            KeyCode::Enter => {
+                if let Some(CleanupRow::Item(g, i)) = view.selected_row() {
+                    let path = view.groups[g].items[i].path.clone();
+                    self.cleanup = None;
+                    self.reveal_path(&path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_patterns_and_finds_candidates() {
+        assert_eq!(
+            JunkPattern::parse("target/ 30d"),
+            Some(JunkPattern { glob: "target".to_string(), dir_only: true, min_age: Some(Duration::from_secs(30 * 86400)) })
+        );
+        assert_eq!(JunkPattern::parse("*.tmp"), Some(JunkPattern { glob: "*.tmp".to_string(), dir_only: false, min_age: None }));
+        assert_eq!(JunkPattern::parse("target/ soon"), None);
+
+        let dir = std::env::temp_dir().join(format!("ffm_cleanup_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b/c")).unwrap();
+        fs::create_dir_all(dir.join("keep/empty")).unwrap();
+        fs::create_dir_all(dir.join("__pycache__")).unwrap();
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::create_dir_all(dir.join(".git/refs/tags")).unwrap();
+        fs::write(dir.join("__pycache__/m.pyc"), "xx").unwrap();
+        fs::write(dir.join("target/debug/bin"), "fresh").unwrap();
+        fs::write(dir.join("keep/file.txt"), "data").unwrap();
+        fs::write(dir.join("note.txt~"), "old").unwrap();
+        fs::write(dir.join("zero"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+
+        let patterns: Vec<JunkPattern> = ["*~", "__pycache__/", "target/ 30d"].iter().filter_map(|s| JunkPattern::parse(s)).collect();
+        let progress = Mutex::new(CleanupProgress::default());
+        let groups = find_cleanup(&dir, &patterns, &progress, &AtomicBool::new(false));
+        let names = |c: Category| -> Vec<String> {
+            groups
+                .iter()
+                .filter(|g| g.category == c)
+                .flat_map(|g| g.items.iter().map(|i| i.path.strip_prefix(&dir).unwrap().to_string_lossy().to_string()))
+                .collect()
+        };
+        // Свежий target/ не трогаем, внутрь .git не заходим
+        assert_eq!(names(Category::Junk), vec!["__pycache__", "note.txt~"]);
+        assert_eq!(names(Category::EmptyFiles), vec!["zero"]);
+        assert_eq!(names(Category::EmptyDirs), vec!["a", "keep/empty"]);
+        #[cfg(unix)]
+        assert_eq!(names(Category::BrokenLinks), vec!["dangling"]);
+        assert_eq!(groups[0].size(), 5);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,721 @@
+use crate::app::{App, Focus, InputMode, SortMode};
+use crate::links::LinkKind;
+use crate::sftp;
+use crate::vfs::WriteMode;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Описание встроенной команды `:` режима
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub section: &'static str,
+}
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec { name: "cd", usage: "cd <path>", description: "Change directory (~, relative paths and sftp://user@host/path work)", section: "Navigation" },
+    CommandSpec { name: "mkdir", usage: "mkdir [-p] <path>", description: "Create a directory", section: "File Operations" },
+    CommandSpec { name: "touch", usage: "touch <name>...", description: "Create empty file(s)", section: "File Operations" },
+    CommandSpec { name: "rename", usage: "rename [new name]", description: "Rename highlighted item", section: "File Operations" },
+    CommandSpec { name: "bulkrename", usage: "bulkrename", description: "Rename selected (or all) items in $EDITOR", section: "File Operations" },
+    CommandSpec { name: "patternrename", usage: "patternrename", description: "Rename selected (or all) items by regex/template with preview", section: "File Operations" },
+    CommandSpec { name: "undo", usage: "undo", description: "Undo the last rename batch", section: "File Operations" },
+    CommandSpec { name: "link", usage: "link [abs|rel|hard]", description: "Link clipboard items here (symlink, relative symlink or hard link)", section: "File Operations" },
+    CommandSpec { name: "follow", usage: "follow", description: "Jump to the real location of a symlink", section: "Navigation" },
+    CommandSpec { name: "properties", usage: "properties", description: "Show and edit permissions, owner and timestamps", section: "File Operations" },
+    CommandSpec { name: "xattr", usage: "xattr", description: "View and edit extended attributes and ACL", section: "File Operations" },
+    CommandSpec { name: "tag", usage: "tag [+|-]<name>...", description: "Add or remove colour tags on highlighted/selected items", section: "File Operations" },
+    CommandSpec { name: "untag", usage: "untag [name]", description: "Remove a tag (or all tags) from highlighted/selected items", section: "File Operations" },
+    CommandSpec { name: "checksum", usage: "checksum [verify [file]]", description: "Compute checksums of highlighted/selected files or verify a SUMS file", section: "File Operations" },
+    CommandSpec { name: "dupes", usage: "dupes [dir]", description: "Find duplicate files under a folder", section: "File Operations" },
+    CommandSpec { name: "cleanup", usage: "cleanup [dir]", description: "Find empty folders, empty files, broken symlinks and junk to trash", section: "File Operations" },
+    CommandSpec { name: "extract", usage: "extract [here|folder|<dir>]", description: "Unpack the highlighted archive (zip, tar, tar.gz, tar.xz, tar.zst, 7z)", section: "File Operations" },
+    CommandSpec { name: "compress", usage: "compress [name.zip|.tar|.tar.gz|.tar.xz|.tar.zst] [-0..-9] [-x pattern]...", description: "Pack highlighted/selected items into an archive in the background", section: "File Operations" },
+    CommandSpec { name: "share", usage: "share [port] [upload] [lan] [user] | share stop", description: "Serve the current folder (or selection) over HTTP with a QR code", section: "File Operations" },
+    CommandSpec { name: "du", usage: "du [dir]", description: "Disk usage analyzer for a folder (or the highlighted drive)", section: "File Operations" },
+    CommandSpec { name: "dirsize", usage: "dirsize [all]", description: "Calculate folder sizes in the background (highlighted/selected or all)", section: "File Operations" },
+    CommandSpec { name: "delete", usage: "delete", description: "Delete highlighted/selected items", section: "File Operations" },
+    CommandSpec { name: "create", usage: "create [names]", description: "Create files/folders (a.rs, dir/, x_{1..3}.txt) or open the prompt", section: "File Operations" },
+    CommandSpec { name: "edit", usage: "edit", description: "Open highlighted file in $EDITOR", section: "File Operations" },
+    CommandSpec { name: "sort", usage: "sort [name|size|date] [asc|desc]", description: "Set or cycle sort mode", section: "File Operations" },
+    CommandSpec { name: "filter", usage: "filter [query]", description: "Filter the file list (empty clears)", section: "Search" },
+    CommandSpec { name: "search", usage: "search", description: "Start interactive search", section: "Search" },
+    CommandSpec { name: "select", usage: "select [glob|all|none]", description: "Select by pattern or toggle highlighted", section: "File Operations" },
+    CommandSpec { name: "copy", usage: "copy", description: "Copy to clipboard", section: "File Operations" },
+    CommandSpec { name: "cut", usage: "cut", description: "Cut (move) to clipboard", section: "File Operations" },
+    CommandSpec { name: "paste", usage: "paste", description: "Paste clipboard here", section: "File Operations" },
+    CommandSpec { name: "fav", usage: "fav [add|remove]", description: "Add/remove favorite", section: "Favorites" },
+    CommandSpec { name: "focus", usage: "focus <files|drives|favorites|next>", description: "Move focus between panels", section: "Global" },
+    CommandSpec { name: "set", usage: "set <option>[!]", description: "Set option: hidden, statusbar (no… to unset, ! to toggle)", section: "Global" },
+    CommandSpec { name: "resize", usage: "resize <+n|-n>", description: "Resize center panel", section: "Navigation" },
+    CommandSpec { name: "open", usage: "open", description: "Open highlighted entry", section: "Navigation" },
+    CommandSpec { name: "back", usage: "back", description: "Go to parent directory", section: "Navigation" },
+    CommandSpec { name: "down", usage: "down", description: "Move down", section: "Navigation" },
+    CommandSpec { name: "top", usage: "top", description: "Jump to first item", section: "Navigation" },
+    CommandSpec { name: "bottom", usage: "bottom", description: "Jump to last item", section: "Navigation" },
+    CommandSpec { name: "up", usage: "up", description: "Move up", section: "Navigation" },
+    CommandSpec { name: "command", usage: "command", description: "Open command line", section: "Global" },
+    CommandSpec { name: "palette", usage: "palette", description: "Open the command palette", section: "Global" },
+    CommandSpec { name: "help", usage: "help", description: "Toggle help popup", section: "Global" },
+    CommandSpec { name: "macro", usage: "macro <record|play> [reg] [count]", description: "Record or replay a keyboard macro", section: "Global" },
+    CommandSpec { name: "reload", usage: "reload", description: "Hot-reload config", section: "Global" },
+    CommandSpec { name: "quit", usage: "quit", description: "Quit", section: "Global" },
+];
+
+/// Команды для выделенного в списке файлов; с другой панели не срабатывают
+const FILE_LIST_COMMANDS: &[&str] = &["create", "rename", "edit", "select", "copy", "cut", "paste"];
+
+/// Порядок разделов в справке
+pub const SECTIONS: &[&str] = &["Navigation", "File Operations", "Favorites", "Search", "Global"];
+
+/// Варианты команд с аргументами, у которых своё описание
+const VARIANTS: &[(&str, &str)] = &[
+    ("resize -5", "Shrink center panel"),
+    ("resize +5", "Expand center panel"),
+    ("focus files", "Focus Files panel"),
+    ("focus drives", "Focus Drives panel"),
+    ("focus favorites", "Focus Favorites panel"),
+    ("focus tags", "Focus Tags panel"),
+    ("focus next", "Switch focus: Files → Drives → Favorites → Tags"),
+    ("cd ~", "Go to home directory"),
+    ("cd /", "Go to filesystem root"),
+    ("cd sftp://", "Open a folder on a server over SSH: sftp://user@host/path"),
+    ("set statusbar!", "Toggle status bar"),
+    ("link rel", "Symlink clipboard items here with relative paths"),
+    ("link hard", "Hard link clipboard items here"),
+    ("checksum verify", "Verify files against SHA256SUMS / *.sha256"),
+    ("set hidden!", "Toggle hidden files"),
+    ("fav remove", "Remove from Favorites"),
+    ("macro record", "Start/stop recording a macro into a register"),
+    ("macro play", "Replay a macro (count prefix repeats it)"),
+    ("fav", "Add highlighted item to Favorites"),
+    ("open", "Open directory / drive / favorite"),
+];
+
+/// Человекочитаемое описание строки команды
+pub fn describe(command: &str) -> String {
+    if let Some((_, desc)) = VARIANTS.iter().find(|(c, _)| *c == command) {
+        return desc.to_string();
+    }
+    match spec_for(command) {
+        Some(spec) if spec.name == command.trim() => spec.description.to_string(),
+        _ => format!(":{}", command),
+    }
+}
+
+/// Раздел справки для строки команды; пользовательские команды — в "Custom"
+pub fn section_of(command: &str) -> &'static str {
+    let known = VARIANTS.iter().any(|(c, _)| *c == command)
+        || spec_for(command).is_some_and(|s| s.name == command.trim());
+    match spec_for(command) {
+        Some(spec) if known => spec.section,
+        _ => "Custom",
+    }
+}
+
+/// Справка по первому слову строки (для подсказки в командной строке)
+pub fn spec_for(line: &str) -> Option<&'static CommandSpec> {
+    let name = line.split_whitespace().next()?;
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+const SET_OPTIONS: &[&str] = &["hidden", "nohidden", "statusbar", "nostatusbar", "copyxattrs", "nocopyxattrs", "dirsizes", "nodirsizes"];
+
+/// Разбивает строку команды на слова; поддерживает кавычки и `\` экранирование
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut cur = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => cur.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(n) = chars.next() {
+                    cur.push(n);
+                }
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut cur));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                cur.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        args.push(cur);
+    }
+    args
+}
+
+/// Раскрывает `~` и относительные пути от `base`
+pub fn expand_path(base: &Path, arg: &str) -> PathBuf {
+    if (arg == "~" || arg.starts_with("~/"))
+        && let Some(home) = directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf())
+    {
+        return home.join(arg.trim_start_matches('~').trim_start_matches('/'));
+    }
+    base.join(arg)
+}
+
+/// Простое сопоставление с шаблоном: `*` — любая последовательность, `?` — один символ
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+/// Состояние Tab-дополнения: повторный Tab перебирает варианты
+pub struct Completion {
+    pub base: String,
+    pub candidates: Vec<String>,
+    pub index: usize,
+    /// Текст после курсора — остаётся на месте при листании вариантов
+    pub tail: String,
+}
+
+/// Варианты дополнения последнего слова в строке команды
+pub fn complete(line: &str, cwd: &Path, tag_names: &[String]) -> (String, Vec<String>) {
+    let words = split_args(line);
+    let ends_with_space = line.ends_with(' ');
+    let (done, word): (Vec<String>, String) = if ends_with_space || words.is_empty() {
+        (words, String::new())
+    } else {
+        let mut w = words;
+        let last = w.pop().unwrap_or_default();
+        (w, last)
+    };
+    // Начало последнего слова — после последнего неэкранированного пробела
+    let mut word_start = 0;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ' ' {
+            word_start = i + 1;
+        }
+    }
+    let base = line[..word_start].to_string();
+
+    let candidates: Vec<String> = if done.is_empty() {
+        COMMANDS
+            .iter()
+            .map(|c| c.name)
+            .filter(|n| n.starts_with(word.as_str()))
+            .map(|n| format!("{} ", n))
+            .collect()
+    } else {
+        let words_of = |list: &[&str]| -> Vec<String> {
+            list.iter()
+                .filter(|o| o.starts_with(word.as_str()))
+                .map(|o| format!("{} ", o))
+                .collect()
+        };
+        match (done[0].as_str(), done.len()) {
+            ("sort", 1) => words_of(&["name", "size", "date"]),
+            ("sort", 2) => words_of(&["asc", "desc"]),
+            ("set", 1) => words_of(SET_OPTIONS),
+            ("fav", 1) => words_of(&["add", "remove"]),
+            ("select", 1) => words_of(&["all", "none"]),
+            ("focus", 1) => words_of(&["files", "drives", "favorites", "tags", "next"]),
+            ("macro", 1) => words_of(&["record", "play"]),
+            ("link", 1) => words_of(&["abs", "rel", "hard"]),
+            ("checksum", 1) => words_of(&["verify"]),
+            ("dirsize", 1) => words_of(&["all"]),
+            ("compress", 1) => words_of(&["cancel"]),
+            ("share", _) => words_of(&["stop", "upload", "lan"]),
+            ("extract", 1) => {
+                let mut out = words_of(&["here", "folder", "cancel"]);
+                out.extend(complete_path(cwd, &word, true));
+                out
+            }
+            ("checksum", 2) => complete_path(cwd, &word, false),
+            ("dupes" | "du" | "cleanup", 1) => complete_path(cwd, &word, true),
+            ("tag" | "untag", _) => {
+                // `+`/`-` перед именем сохраняется
+                let sign = word.chars().next().filter(|c| *c == '+' || *c == '-');
+                let name = sign.map_or(word.as_str(), |_| &word[1..]);
+                tag_names
+                    .iter()
+                    .filter(|t| t.starts_with(name))
+                    .map(|t| format!("{}{} ", sign.map(String::from).unwrap_or_default(), t))
+                    .collect()
+            }
+            ("cd", _) => complete_path(cwd, &word, true),
+            ("mkdir" | "touch" | "rename", _) => complete_path(cwd, &word, false),
+            _ => Vec::new(),
+        }
+    };
+    (base, candidates)
+}
+
+fn complete_path(cwd: &Path, word: &str, dirs_only: bool) -> Vec<String> {
+    let (dir_part, name_part) = match word.rfind('/') {
+        Some(pos) => (&word[..=pos], &word[pos + 1..]),
+        None => ("", word),
+    };
+    let dir = expand_path(cwd, if dir_part.is_empty() { "." } else { dir_part });
+    let mut out: Vec<String> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let is_dir = e.path().is_dir();
+                if !name.starts_with(name_part) || (dirs_only && !is_dir) {
+                    return None;
+                }
+                if name.starts_with('.') && !name_part.starts_with('.') {
+                    return None;
+                }
+                let escaped = name.replace(' ', "\\ ");
+                Some(if is_dir { format!("{}{}/", dir_part, escaped) } else { format!("{}{} ", dir_part, escaped) })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    out.sort();
+    out
+}
+
+impl App {
+    pub fn start_command(&mut self) {
+        self.input_mode = InputMode::Command;
+        self.command_input.clear();
+        self.completion = None;
+    }
+
+    pub fn handle_command_input(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+        if key.code != KeyCode::Tab {
+            self.completion = None;
+        }
+        match key.code {
+            KeyCode::Enter => {
+                self.command_input.push_history();
+                let line = self.command_input.text().to_string();
+                self.command_input.clear();
+                self.input_mode = InputMode::Normal;
+                if !line.trim().is_empty() {
+                    self.execute_command(&line);
+                }
+            }
+            KeyCode::Esc => {
+                self.input_mode = InputMode::Normal;
+                self.command_input.clear();
+            }
+            KeyCode::Tab => self.complete_command(),
+            // Backspace на пустой строке закрывает `:` как в vim
+            KeyCode::Backspace if self.command_input.is_empty() => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {
+                self.command_input.handle_key(key);
+            }
+        }
+    }
+
+    fn complete_command(&mut self) {
+        if let Some(c) = self.completion.as_mut() {
+            c.index = (c.index + 1) % c.candidates.len();
+            let head = format!("{}{}", c.base, c.candidates[c.index]);
+            self.command_input.set(format!("{}{}", head, c.tail));
+            self.command_input.set_cursor_chars(head.chars().count());
+            return;
+        }
+        // Дополняем слово под курсором; хвост после курсора сохраняем
+        let cursor = self.command_input.cursor_chars();
+        let head: String = self.command_input.text().chars().take(cursor).collect();
+        let tail: String = self.command_input.text().chars().skip(cursor).collect();
+        let (base, candidates) = complete(&head, &self.current_dir, &self.tag_names());
+        if candidates.is_empty() {
+            self.message = "No completions".to_string();
+            return;
+        }
+        let head = format!("{}{}", base, candidates[0]);
+        self.command_input.set(format!("{}{}", head, tail));
+        self.command_input.set_cursor_chars(head.chars().count());
+        if candidates.len() > 1 {
+            self.completion = Some(Completion { base, candidates, index: 0, tail });
+        }
+    }
+
+    /// Выполняет строку команды. Общий путь для `:` режима и клавиш из раскладки.
+    pub fn execute_command(&mut self, line: &str) {
+        let args = split_args(line);
+        let Some((name, rest)) = args.split_first() else {
+            return;
+        };
+        let arg = rest.first().map(|s| s.as_str());
+        if self.archive_read_only(name) || self.remote_local_only(name) {
+            return;
+        }
+        // Действия над списком файлов работают, только когда он в фокусе
+        if self.focus != Focus::FileList && FILE_LIST_COMMANDS.contains(&name.as_str()) {
+            return;
+        }
+
+        match name.as_str() {
+            "quit" | "q" => self.should_quit = true,
+            "command" => self.start_command(),
+            "palette" => self.open_palette(),
+            "bulkrename" => self.start_bulk_rename(),
+            "patternrename" => self.start_pattern_rename(),
+            "undo" => self.undo(),
+            "link" => match LinkKind::parse(arg) {
+                Some(kind) => self.link_clipboard(kind),
+                None => self.message = "Usage: link [abs|rel|hard]".to_string(),
+            },
+            "follow" => self.follow_link(),
+            "tag" if rest.is_empty() => {
+                self.start_command();
+                self.command_input.set("tag ");
+            }
+            "tag" => self.tag_command(rest),
+            "untag" => self.untag_command(arg),
+            "dupes" => self.open_dupes(arg),
+            "cleanup" => self.open_cleanup(arg),
+            "share" => self.share_command(rest),
+            "compress" if rest.is_empty() => self.prompt_compress(),
+            "compress" => self.compress_command(rest),
+            "extract" if rest.is_empty() => self.prompt_extract(),
+            "extract" if arg == Some("cancel") => self.cancel_extract(),
+            "extract" => self.extract_command(arg),
+            "du" => self.open_disk_usage(arg),
+            "dirsize" => self.dir_size_command(arg),
+            "checksum" => match arg {
+                None => self.start_checksums(),
+                Some("verify") => self.verify_checksums(rest.get(1).map(|s| s.as_str())),
+                Some(_) => self.message = "Usage: checksum [verify [file]]".to_string(),
+            },
+            #[cfg(unix)]
+            "properties" => self.open_properties(),
+            #[cfg(not(unix))]
+            "properties" => self.message = "Properties are only supported on Unix".to_string(),
+            #[cfg(unix)]
+            "xattr" => self.open_xattrs(),
+            #[cfg(not(unix))]
+            "xattr" => self.message = "Extended attributes are only supported on Unix".to_string(),
+            "macro" => self.macro_command(rest),
+            "search" => {
+                self.input_mode = InputMode::Search;
+                self.search_query.clear();
+                self.update_search();
+            }
+            "help" => {
+                self.show_help = !self.show_help;
+                self.help_scroll = 0;
+                self.help_query.clear();
+            }
+            "reload" => self.reload_config(),
+            "down" => match self.focus {
+                Focus::FileList => self.next_item(),
+                Focus::DriveList => self.next_drive(),
+                Focus::Favorites => self.next_favorite(),
+                Focus::Tags => self.next_tag_row(),
+            },
+            "up" => match self.focus {
+                Focus::FileList => self.previous_item(),
+                Focus::DriveList => self.previous_drive(),
+                Focus::Favorites => self.previous_favorite(),
+                Focus::Tags => self.previous_tag_row(),
+            },
+            "top" => {
+                if self.focus == Focus::FileList && !self.filtered_items.is_empty() {
+                    self.state.select(Some(0));
+                    self.update_preview();
+                }
+            }
+            "bottom" => {
+                if self.focus == Focus::FileList && !self.filtered_items.is_empty() {
+                    self.state.select(Some(self.filtered_items.len() - 1));
+                    self.update_preview();
+                }
+            }
+            "open" => match self.focus {
+                Focus::FileList => self.open_selected(),
+                Focus::DriveList => self.open_drive(),
+                Focus::Favorites => self.open_favorite(),
+                Focus::Tags => self.open_tag_row(),
+            },
+            "back" => {
+                if self.focus == Focus::FileList && self.at_remote_root() {
+                    self.message = format!("Top of {}", self.current_dir.display());
+                } else if self.focus == Focus::FileList
+                    && let Some(parent) = self.current_dir.parent()
+                {
+                    self.current_dir = parent.to_path_buf();
+                    self.refresh_items();
+                }
+            }
+            "focus" => {
+                self.focus = match arg {
+                    Some("files") => Focus::FileList,
+                    Some("drives") => Focus::DriveList,
+                    Some("favorites") => Focus::Favorites,
+                    Some("tags") => Focus::Tags,
+                    Some("next") | None => match self.focus {
+                        Focus::FileList => Focus::DriveList,
+                        Focus::DriveList => Focus::Favorites,
+                        Focus::Favorites => Focus::Tags,
+                        Focus::Tags => Focus::FileList,
+                    },
+                    Some(other) => {
+                        self.message = format!("Unknown panel: {}", other);
+                        return;
+                    }
+                }
+            }
+            "resize" => match arg.and_then(|a| a.parse::<i16>().ok()) {
+                Some(delta) => self.resize_center(delta),
+                None => self.message = "Usage: resize <+n|-n>".to_string(),
+            },
+            "cd" if arg.is_some_and(|a| a.starts_with(sftp::SCHEME)) => self.open_remote(arg.unwrap_or_default()),
+            // Относительный путь на сервере раскрывает сам сервер
+            "cd" if self.remote_at(&self.current_dir).is_some() && arg.is_some_and(|a| !a.starts_with(['/', '~'])) => {
+                let url = self.current_dir.join(arg.unwrap_or_default());
+                self.open_remote(&url.to_string_lossy());
+            }
+            "cd" => {
+                let target = match arg {
+                    Some(a) => expand_path(&self.current_dir, a),
+                    None => expand_path(&self.current_dir, "~"),
+                };
+                match fs::canonicalize(&target) {
+                    Ok(p) if p.is_dir() => {
+                        self.current_dir = p;
+                        self.focus = Focus::FileList;
+                        self.refresh_items();
+                    }
+                    Ok(_) => self.message = format!("Not a directory: {}", target.display()),
+                    Err(e) => self.message = format!("cd: {}: {}", target.display(), e),
+                }
+            }
+            "mkdir" => {
+                let parents = rest.iter().any(|a| a == "-p");
+                let names: Vec<&String> = rest.iter().filter(|a| *a != "-p").collect();
+                if names.is_empty() {
+                    self.message = "Usage: mkdir [-p] <path>".to_string();
+                    return;
+                }
+                for name in names {
+                    let path = expand_path(&self.current_dir, name);
+                    let res = self.fs_for(&path).mkdir(&path, parents);
+                    match res {
+                        Ok(_) => self.message = format!("Created: {}", name),
+                        Err(e) => {
+                            self.message = format!("mkdir: {}: {}", name, e);
+                            break;
+                        }
+                    }
+                }
+                self.refresh_items();
+            }
+            "touch" => {
+                if rest.is_empty() {
+                    self.message = "Usage: touch <name>...".to_string();
+                    return;
+                }
+                for name in rest {
+                    let path = expand_path(&self.current_dir, name);
+                    let res = self.fs_for(&path).write(&path, WriteMode::Append);
+                    match res {
+                        Ok(_) => self.message = format!("Created: {}", name),
+                        Err(e) => {
+                            self.message = format!("touch: {}: {}", name, e);
+                            break;
+                        }
+                    }
+                }
+                self.refresh_items();
+            }
+            "create" => {
+                self.start_create();
+                // `:create a.rs, b/{1..3}.txt` — сразу создать, без строки ввода
+                if !rest.is_empty() {
+                    self.create_input.set(rest.join(" "));
+                    self.create_item();
+                    self.input_mode = InputMode::Normal;
+                    self.create_input.clear();
+                }
+            }
+            "rename" => match arg {
+                Some(new_name) => {
+                    self.rename_input.set(new_name);
+                    self.rename_item();
+                }
+                None => self.start_rename(),
+            },
+            "delete" => match self.focus {
+                Focus::Favorites => self.remove_favorite(),
+                Focus::Tags => self.remove_tag_row(),
+                Focus::FileList => self.delete_prompt(),
+                Focus::DriveList => {}
+            },
+            "edit" => self.edit_selected(),
+            "sort" => {
+                if arg.is_none() {
+                    self.cycle_sort_mode();
+                    return;
+                }
+                let mode = match arg {
+                    Some("name") => SortMode::Name,
+                    Some("size") => SortMode::Size,
+                    Some("date") => SortMode::Date,
+                    _ => {
+                        self.message = "Usage: sort [name|size|date] [asc|desc]".to_string();
+                        return;
+                    }
+                };
+                let ascending = match rest.get(1).map(|s| s.as_str()) {
+                    Some("asc") => true,
+                    Some("desc") => false,
+                    _ => mode == SortMode::Name,
+                };
+                self.set_sort(mode, ascending);
+            }
+            "filter" => {
+                self.search_query.set(rest.join(" "));
+                self.update_search();
+                self.message = if self.search_query.is_empty() {
+                    "Filter cleared".to_string()
+                } else {
+                    format!("Filter: {} ({} match)", self.search_query.text(), self.filtered_items.len())
+                };
+            }
+            "select" => match arg {
+                None => self.toggle_selection(),
+                Some("none") => self.selected_indices.clear(),
+                Some("all") => self.selected_indices = (0..self.filtered_items.len()).collect(),
+                Some(_) => {
+                    let mut count = 0;
+                    for (i, path) in self.filtered_items.iter().enumerate() {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        if rest.iter().any(|pat| glob_match(pat, &name)) {
+                            self.selected_indices.insert(i);
+                            count += 1;
+                        }
+                    }
+                    self.message = format!("Selected {} item(s)", count);
+                }
+            },
+            "copy" => self.copy_item(),
+            "cut" => self.cut_item(),
+            "paste" => self.paste_item(),
+            "fav" => match (arg, &self.focus) {
+                (Some("remove"), Focus::Favorites) => self.remove_favorite(),
+                (Some("remove"), _) => {
+                    if let Some(path) = self.state.selected().and_then(|i| self.filtered_items.get(i)).cloned()
+                        && let Some(pos) = self.favorites.iter().position(|p| *p == path)
+                    {
+                        self.favorites_state.select(Some(pos));
+                        self.remove_favorite();
+                    }
+                }
+                (Some("add") | None, Focus::FileList) => self.add_favorite(),
+                (Some("add") | None, _) => {}
+                (Some(other), _) => self.message = format!("Unknown fav action: {}", other),
+            },
+            "set" => {
+                let Some(opt) = arg else {
+                    self.message = format!(
+                        "hidden={} statusbar={}",
+                        self.show_hidden, self.show_statusbar
+                    );
+                    return;
+                };
+                let (name, value) = if let Some(n) = opt.strip_suffix('!') {
+                    (n, None)
+                } else if let Some(n) = opt.strip_prefix("no") {
+                    (n, Some(false))
+                } else {
+                    (opt, Some(true))
+                };
+                match name {
+                    "hidden" => {
+                        self.show_hidden = value.unwrap_or(!self.show_hidden);
+                        self.refresh_items();
+                    }
+                    "statusbar" => self.show_statusbar = value.unwrap_or(!self.show_statusbar),
+                    "copyxattrs" => {
+                        self.config.copy_xattrs = value.unwrap_or(!self.config.copy_xattrs);
+                        let state = if self.config.copy_xattrs { "on" } else { "off" };
+                        self.message = format!("Copy xattrs: {}", state);
+                    }
+                    "dirsizes" => {
+                        self.config.dir_sizes = value.unwrap_or(!self.config.dir_sizes);
+                        let state = if self.config.dir_sizes { "on" } else { "off" };
+                        self.message = format!("Folder sizes: {}", state);
+                        self.auto_dir_sizes();
+                    }
+                    _ => self.message = format!("Unknown option: {}", name),
+                }
+            }
+            other => self.message = format!("Unknown command: {}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_handles_quotes_and_escapes() {
+        assert_eq!(split_args("mkdir -p a/b"), vec!["mkdir", "-p", "a/b"]);
+        assert_eq!(split_args("rename \"my file.txt\""), vec!["rename", "my file.txt"]);
+        assert_eq!(split_args("cd my\\ dir"), vec!["cd", "my dir"]);
+    }
+
+    #[test]
+    fn glob_match_stars_and_questions() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rsx"));
+        assert!(glob_match("a?c*", "abcdef"));
+    }
+
+    #[test]
+    fn complete_command_names_and_args() {
+        let cwd = Path::new(".");
+        let (base, c) = complete("so", cwd, &[]);
+        assert_eq!(base, "");
+        assert_eq!(c, vec!["sort "]);
+        let (base, c) = complete("sort size d", cwd, &[]);
+        assert_eq!(base, "sort size ");
+        assert_eq!(c, vec!["desc "]);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
 use std::error::Error;
 use std::fs;
@@ -7,6 +8,22 @@ use std::path::PathBuf;
 #[derive(Deserialize, Clone)]
 #[serde(default = "Config::default")]
 pub struct Config {
+    /// Задержка перед показом подсказки which-key, мс
+    pub which_key_delay_ms: u64,
+    /// Переносить расширенные атрибуты (xattr, ACL) при копировании
+    pub copy_xattrs: bool,
+    /// Считать размеры папок в списке в фоне
+    pub dir_sizes: bool,
+    /// Дублировать теги в атрибут `user.xdg.tags`
+    pub tags_xattr: bool,
+    /// Ключ для sftp://; пусто — ssh-agent и ключи из `~/.ssh`
+    pub ssh_identity: String,
+    /// Порт `:share` по умолчанию; 0 — любой свободный
+    pub share_port: u16,
+    /// Шаблоны мусора для очистки: `*.tmp`, `__pycache__/` (папки), `target/ 30d` (старше 30 дней)
+    pub cleanup_patterns: Vec<String>,
+    /// Цвета тегов: имя → цвет
+    pub tag_colors: BTreeMap<String, String>,
     pub theme: ThemeConfig,
     pub keys: KeysConfig,
 }
@@ -40,8 +57,46 @@ pub struct KeysConfig {
     pub reload: String,
     pub edit: String,
     pub rename: String,
+    pub bulk_rename: String,
+    pub pattern_rename: String,
+    pub undo: String,
+    pub link: String,
+    pub follow_link: String,
+    pub properties: String,
+    pub xattrs: String,
+    pub tag: String,
+    pub checksum: String,
+    pub dupes: String,
+    pub cleanup: String,
+    pub compress: String,
+    pub extract: String,
+    pub disk_usage: String,
+    pub dir_size: String,
+    pub share: String,
     pub help: String,
     pub sort: String,
+    pub command: String,
+    pub copy: String,
+    pub cut: String,
+    pub paste: String,
+    pub favorite: String,
+    pub select: String,
+    pub palette: String,
+    pub macro_record: String,
+    pub macro_play: String,
+    /// Дополнительные привязки: клавиша → команда (как в `:` режиме)
+    pub bindings: BTreeMap<String, String>,
+}
+
+fn default_cleanup_patterns() -> Vec<String> {
+    ["*.tmp", "*~", ".DS_Store", "Thumbs.db", "__pycache__/", "target/ 30d"].into_iter().map(String::from).collect()
+}
+
+fn default_tag_colors() -> BTreeMap<String, String> {
+    [("todo", "#E06C75"), ("review", "#E5C07B"), ("done", "#98C379")]
+        .into_iter()
+        .map(|(tag, color)| (tag.to_string(), color.to_string()))
+        .collect()
 }
 
 impl ThemeConfig {
@@ -75,14 +130,48 @@ impl KeysConfig {
             reload: "F5".to_string(),
             edit: "e".to_string(),
             rename: "r".to_string(),
+            bulk_rename: "R".to_string(),
+            pattern_rename: "ctrl-r".to_string(),
+            undo: "u".to_string(),
+            link: "L".to_string(),
+            follow_link: "g l".to_string(),
+            properties: "i".to_string(),
+            xattrs: "X".to_string(),
+            tag: "t".to_string(),
+            checksum: "c".to_string(),
+            dupes: "g d".to_string(),
+            cleanup: "g c".to_string(),
+            compress: "Z".to_string(),
+            extract: "E".to_string(),
+            disk_usage: "g u".to_string(),
+            dir_size: "S".to_string(),
+            share: "g s".to_string(),
             help: "?".to_string(),
             sort: "s".to_string(),
+            command: ":".to_string(),
+            copy: "y".to_string(),
+            cut: "x".to_string(),
+            paste: "p".to_string(),
+            favorite: "f".to_string(),
+            select: "space".to_string(),
+            palette: "ctrl-p".to_string(),
+            macro_record: "Q".to_string(),
+            macro_play: "@".to_string(),
+            bindings: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    const DEFAULT_TOML: &'static str = r##"[theme]
+    const DEFAULT_TOML: &'static str = r##"which_key_delay_ms = 400
+copy_xattrs = false
+dir_sizes = false
+tags_xattr = false
+ssh_identity = ""
+share_port = 8000
+cleanup_patterns = ["*.tmp", "*~", ".DS_Store", "Thumbs.db", "__pycache__/", "target/ 30d"]
+
+[theme]
 background = "Reset"
 text = "#EADBB8"
 selected_bg = "#D2B48C"
@@ -91,6 +180,12 @@ directory = "#E0C097"
 file = "#C8B6A6"
 highlight_symbol = "> "
 
+[tag_colors]
+todo = "#E06C75"
+review = "#E5C07B"
+done = "#98C379"
+
+# Letters are case-sensitive: "D" is Shift+D, "d" a plain d; "ctrl-d" and "ctrl-D" are the same
 [keys]
 quit = "q"
 search = "/"
@@ -106,8 +201,40 @@ back_dir = "h"
 reload = "F5"
 edit = "e"
 rename = "r"
+bulk_rename = "R"
+pattern_rename = "ctrl-r"
+undo = "u"
+link = "L"
+follow_link = "g l"
+properties = "i"
+xattrs = "X"
+tag = "t"
+checksum = "c"
+dupes = "g d"
+cleanup = "g c"
+compress = "Z"
+extract = "E"
+disk_usage = "g u"
+dir_size = "S"
+share = "g s"
 help = "?"
 sort = "s"
+command = ":"
+copy = "y"
+cut = "x"
+paste = "p"
+favorite = "f"
+select = "space"
+palette = "ctrl-p"
+# "Q", not vim's "q": "q" is quit. Set macro_record = "q" and move quit for vim-style q<reg> … q
+macro_record = "Q"
+macro_play = "@"
+
+# Any key (or space-separated key sequence) can run a command line, e.g.:
+# [keys.bindings]
+# "ctrl-d" = "cd ~/Downloads"
+# "O" = "sort size asc"
+# "g w" = "cd ~/Downloads"
 "##;
 
     /// "Умная" загрузка конфига
@@ -156,6 +283,14 @@ sort = "s"
 
     pub fn default() -> Self {
         Config {
+            which_key_delay_ms: 400,
+            copy_xattrs: false,
+            dir_sizes: false,
+            tags_xattr: false,
+            ssh_identity: String::new(),
+            share_port: 8000,
+            cleanup_patterns: default_cleanup_patterns(),
+            tag_colors: default_tag_colors(),
             theme: ThemeConfig::default_theme(),
             keys: KeysConfig::default_keys(),
         }
@@ -0,0 +1,311 @@
+use crate::app::{App, InputMode};
+use crate::commands::expand_path;
+use crate::vfs::WriteMode;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Защита от опечаток вроде `{1..100000}`
+const MAX_EXPANSION: usize = 1000;
+
+/// Разбирает строку создания: имена через запятую (`\,` — литеральная запятая),
+/// в каждом раскрываются скобки: `{a,b}`, `{1..5}`, `{01..10}`, `{a..e}`.
+pub fn parse_create_input(input: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&',') => current.push(chars.next().unwrap_or(',')),
+            '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            }
+            ',' if depth == 0 => names.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    names.push(current);
+
+    let mut out = Vec::new();
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        out.extend(expand_braces(name)?);
+        if out.len() > MAX_EXPANSION {
+            return Err(format!("Too many names (over {})", MAX_EXPANSION));
+        }
+    }
+    Ok(out)
+}
+
+/// Раскрытие скобок как в shell; несколько групп дают декартово произведение
+pub fn expand_braces(s: &str) -> Result<Vec<String>, String> {
+    // Первая группа верхнего уровня
+    let Some(open) = s.find('{') else {
+        return Ok(vec![s.to_string()]);
+    };
+    let mut depth = 0;
+    let mut close = None;
+    for (i, c) in s[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return Err(format!("Unclosed '{{' in '{}'", s));
+    };
+
+    let (prefix, body, suffix) = (&s[..open], &s[open + 1..close], &s[close + 1..]);
+    let alternatives = match expand_range(body)? {
+        Some(range) => range,
+        None if split_top_level(body).len() > 1 => split_top_level(body),
+        // `{x}` без запятых и диапазона остаётся как есть
+        None => {
+            return Ok(expand_braces(suffix)?
+                .into_iter()
+                .map(|rest| format!("{}{{{}}}{}", prefix, body, rest))
+                .collect());
+        }
+    };
+
+    let mut out = Vec::new();
+    for alt in alternatives {
+        for head in expand_braces(&alt)? {
+            for rest in expand_braces(suffix)? {
+                out.push(format!("{}{}{}", prefix, head, rest));
+                if out.len() > MAX_EXPANSION {
+                    return Err(format!("Too many names (over {})", MAX_EXPANSION));
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn split_top_level(body: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in body.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+/// `1..5`, `05..1` (ширина по самой длинной границе с ведущим нулём), `a..e`;
+/// `Ok(None)` — это не диапазон
+fn expand_range(body: &str) -> Result<Option<Vec<String>>, String> {
+    let Some((from, to)) = body.split_once("..") else {
+        return Ok(None);
+    };
+    let numeric = |s: &str| {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    if numeric(from) && numeric(to) {
+        // Длина считается до выделения памяти: `{1..99999999999}` не должен ничего собирать
+        let too_many = || format!("Too many names (over {})", MAX_EXPANSION);
+        let (Ok(a), Ok(b)) = (from.parse::<i64>(), to.parse::<i64>()) else {
+            return Err(too_many());
+        };
+        if a.abs_diff(b) >= MAX_EXPANSION as u64 {
+            return Err(too_many());
+        }
+        let padded = from.starts_with('0') && from.len() > 1 || to.starts_with('0') && to.len() > 1;
+        let width = if padded { from.len().max(to.len()) } else { 0 };
+        let values: Vec<i64> = if a <= b { (a..=b).collect() } else { (b..=a).rev().collect() };
+        return Ok(Some(values.iter().map(|v| format!("{:0w$}", v, w = width)).collect()));
+    }
+    let (mut a, mut b) = (from.chars(), to.chars());
+    Ok(match (a.next(), a.next(), b.next(), b.next()) {
+        (Some(a), None, Some(b), None) if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+            let values: Vec<char> = if a <= b { (a..=b).collect() } else { (b..=a).rev().collect() };
+            Some(values.iter().map(|c| c.to_string()).collect())
+        }
+        _ => None,
+    })
+}
+
+/// Каталог шаблонов: `<config dir>/templates`
+pub fn templates_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "ffm").map(|p| p.config_dir().join("templates"))
+}
+
+fn load_templates() -> Vec<PathBuf> {
+    let Some(dir) = templates_dir() else {
+        return Vec::new();
+    };
+    let mut templates: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+    templates.sort();
+    templates
+}
+
+impl App {
+    pub fn start_create(&mut self) {
+        self.templates = load_templates();
+        self.create_template = None;
+        self.create_input.clear();
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Выбранный шаблон (имя файла) для заголовка строки создания
+    pub fn create_template_name(&self) -> Option<String> {
+        self.create_template
+            .and_then(|i| self.templates.get(i))
+            .map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string())
+    }
+
+    /// Tab / Shift+Tab: без шаблона → шаблоны по порядку → снова без шаблона
+    fn cycle_template(&mut self, forward: bool) {
+        if self.templates.is_empty() {
+            let dir = templates_dir().map(|d| d.display().to_string()).unwrap_or_default();
+            self.message = format!("No templates in {}", dir);
+            return;
+        }
+        let len = self.templates.len();
+        self.create_template = match (self.create_template, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(len - 1),
+            (Some(i), true) if i + 1 < len => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            _ => None,
+        };
+    }
+
+    pub fn handle_editing_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                self.create_input.push_history();
+                self.create_item();
+                self.input_mode = InputMode::Normal;
+                self.create_input.clear();
+            }
+            KeyCode::Esc => {
+                self.input_mode = InputMode::Normal;
+                self.create_input.clear();
+            }
+            KeyCode::Tab => self.cycle_template(true),
+            KeyCode::BackTab => self.cycle_template(false),
+            _ => {
+                self.create_input.handle_key(key);
+            }
+        }
+    }
+
+    /// Создаёт всё, что перечислено в строке: `/` в конце — каталог,
+    /// недостающие родители создаются (`mkdir -p`), файлы берут содержимое шаблона.
+    pub fn create_item(&mut self) {
+        let names = match parse_create_input(self.create_input.text()) {
+            Ok(names) if names.is_empty() => {
+                self.message = "Nothing to create".to_string();
+                return;
+            }
+            Ok(names) => names,
+            Err(e) => {
+                self.message = format!("Error: {}", e);
+                return;
+            }
+        };
+        let template = match self.create_template.and_then(|i| self.templates.get(i)) {
+            Some(path) => match fs::read(path) {
+                Ok(content) => Some(content),
+                Err(e) => {
+                    self.message = format!("Template error: {}", e);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        let mut created = 0;
+        let mut errors: Vec<String> = Vec::new();
+        for name in &names {
+            let path = expand_path(&self.current_dir, name);
+            let fs = self.fs_for(&path);
+            let res = if name.ends_with('/') {
+                fs.mkdir(&path, true)
+            } else {
+                let parent_ok = match path.parent() {
+                    Some(parent) => fs.mkdir(parent, true),
+                    None => Ok(()),
+                };
+                // CreateNew: существующий файл не обнуляем
+                parent_ok.and_then(|_| fs.write(&path, WriteMode::CreateNew)).and_then(|mut file| {
+                    if let Some(content) = &template {
+                        file.write_all(content)?;
+                    }
+                    file.flush()
+                })
+            };
+            match res {
+                Ok(_) => created += 1,
+                Err(e) => errors.push(format!("{}: {}", name, e)),
+            }
+        }
+
+        self.message = match (created, errors.first()) {
+            (_, Some(err)) if names.len() == 1 => format!("Error: {}", err),
+            (_, Some(err)) => format!("Created {} of {}; {} failed ({})", created, names.len(), errors.len(), err),
+            (1, None) => format!("Created: {}", names[0]),
+            (n, None) => format!("Created {} items", n),
+        };
+        self.refresh_items();
+        // Курсор на первый созданный элемент текущего каталога
+        if let Some(first) = names.first() {
+            let top = first.split('/').next().unwrap_or(first);
+            let path = self.current_dir.join(top);
+            if let Some(i) = self.filtered_items.iter().position(|p| *p == path) {
+                self.state.select(Some(i));
+            }
+        }
+        self.update_preview();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braces_and_lists() {
+        assert_eq!(expand_braces("test_{1..3}.txt").unwrap(), vec!["test_1.txt", "test_2.txt", "test_3.txt"]);
+        assert_eq!(expand_braces("{a,b}/{x,y}.rs").unwrap(), vec!["a/x.rs", "a/y.rs", "b/x.rs", "b/y.rs"]);
+        assert_eq!(expand_braces("img{08..10}").unwrap(), vec!["img08", "img09", "img10"]);
+        assert_eq!(expand_braces("{c..a}").unwrap(), vec!["c", "b", "a"]);
+        assert_eq!(expand_braces("keep{x}").unwrap(), vec!["keep{x}"]);
+        assert!(expand_braces("bad{1..2").is_err());
+        assert_eq!(expand_braces("f{1..1000}").unwrap().len(), 1000);
+        assert_eq!(expand_braces("f{1..100000}").unwrap_err(), "Too many names (over 1000)");
+        assert!(parse_create_input("f{1..99999999999}").is_err());
+        assert!(parse_create_input("f{0..99999999999999999999999}").is_err());
+        assert_eq!(
+            parse_create_input("a.rs, b.rs,src/{lib,main}.rs, x\\,y").unwrap(),
+            vec!["a.rs", "b.rs", "src/lib.rs", "src/main.rs", "x,y"]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::app::{App, SortMode};
+use crate::du::{DuProgress, Scanner};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Размеры папок для колонки размера: посчитанные, ожидающие и общий канал с потоками
+#[derive(Default)]
+pub struct DirSizer {
+    pub known: HashMap<PathBuf, u64>,
+    pub pending: HashSet<PathBuf>,
+    results: Arc<Mutex<Vec<(PathBuf, u64)>>>,
+    cancel: Arc<AtomicBool>,
+    /// Каталог, для которого запущены подсчёты
+    dir: PathBuf,
+}
+
+impl DirSizer {
+    /// При смене каталога незаконченные подсчёты больше не нужны
+    pub fn entered(&mut self, dir: &PathBuf) {
+        if self.dir != *dir {
+            self.cancel.store(true, Ordering::Relaxed);
+            self.cancel = Arc::new(AtomicBool::new(false));
+            self.pending.clear();
+            self.dir = dir.clone();
+        }
+    }
+}
+
+impl App {
+    /// Запускает фоновый подсчёт; папки считаются по очереди, каждая — в несколько потоков
+    pub fn compute_dir_sizes(&mut self, dirs: Vec<PathBuf>) {
+        let dirs: Vec<PathBuf> = dirs.into_iter().filter(|d| !self.dir_sizer.pending.contains(d)).collect();
+        if dirs.is_empty() {
+            return;
+        }
+        self.dir_sizer.pending.extend(dirs.iter().cloned());
+        let results = self.dir_sizer.results.clone();
+        let cancel = self.dir_sizer.cancel.clone();
+        thread::spawn(move || {
+            let progress = Mutex::new(DuProgress::default());
+            for dir in dirs {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let (size, _) = Scanner::new(&dir, None, &progress, &cancel).apparent().scan_parallel(&dir);
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Ok(mut r) = results.lock() {
+                    r.push((dir, size));
+                }
+            }
+        });
+    }
+
+    /// `:dirsize [all]` — выбранные папки, папка под курсором или все папки списка
+    pub fn dir_size_command(&mut self, arg: Option<&str>) {
+        let is_dir = |app: &App, p: &PathBuf| app.meta_cache.get(p).is_some_and(|m| m.is_dir && m.link_target.is_none());
+        let dirs: Vec<PathBuf> = match arg {
+            Some("all") => self.items.iter().filter(|p| is_dir(self, p)).cloned().collect(),
+            None => self.bulk_targets_or_highlighted().into_iter().filter(|p| is_dir(self, p)).collect(),
+            Some(other) => {
+                self.message = format!("Usage: dirsize [all], got {}", other);
+                return;
+            }
+        };
+        if dirs.is_empty() {
+            self.message = "No folders to measure".to_string();
+            return;
+        }
+        // Явный запрос пересчитывает и уже известные размеры
+        for d in &dirs {
+            self.dir_sizer.known.remove(d);
+        }
+        self.message = format!("Calculating size of {} folder(s)…", dirs.len());
+        self.compute_dir_sizes(dirs);
+    }
+
+    /// Папки текущего списка без известного размера (для `dir_sizes = true`)
+    pub fn auto_dir_sizes(&mut self) {
+        if !self.config.dir_sizes || !self.fs_in(&self.current_dir).is_local() {
+            return;
+        }
+        let dirs = self
+            .items
+            .iter()
+            .filter(|p| self.meta_cache.get(*p).is_some_and(|m| m.is_dir && !m.size_known && m.link_target.is_none()))
+            .cloned()
+            .collect();
+        self.compute_dir_sizes(dirs);
+    }
+
+    /// Переносит готовые размеры в `meta_cache` (вызывается из `tick`)
+    pub fn poll_dir_sizes(&mut self) {
+        let done: Vec<(PathBuf, u64)> = match self.dir_sizer.results.lock() {
+            Ok(mut r) if !r.is_empty() => std::mem::take(&mut *r),
+            _ => return,
+        };
+        let mut changed = false;
+        for (dir, size) in done {
+            self.dir_sizer.pending.remove(&dir);
+            self.dir_sizer.known.insert(dir.clone(), size);
+            if let Some(meta) = self.meta_cache.get_mut(&dir) {
+                meta.size = size;
+                meta.size_known = true;
+                changed = true;
+            }
+        }
+        if changed && self.sort_mode == SortMode::Size {
+            self.resort_items();
+        }
+        if self.dir_sizer.pending.is_empty() && self.message.starts_with("Calculating size") {
+            self.message = "Folder sizes ready".to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    fn wait(app: &mut App) {
+        let start = Instant::now();
+        while !app.dir_sizer.pending.is_empty() && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(10));
+            app.poll_dir_sizes();
+        }
+        assert!(app.dir_sizer.pending.is_empty());
+    }
+
+    #[test]
+    fn measures_folders_and_resorts() {
+        let root = std::env::temp_dir().join(format!("ffm_dir_sizes_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("big/deep")).unwrap();
+        fs::create_dir_all(root.join("small")).unwrap();
+        fs::write(root.join("big/a"), vec![0u8; 3000]).unwrap();
+        fs::write(root.join("big/deep/b"), vec![0u8; 5000]).unwrap();
+        fs::write(root.join("small/c"), vec![0u8; 10]).unwrap();
+
+        let mut app = App::with_config(Config::default(), root.clone());
+        app.sort_mode = SortMode::Size;
+        app.sort_ascending = false;
+        app.refresh_items();
+        app.dir_size_command(Some("all"));
+        assert_eq!(app.message, "Calculating size of 2 folder(s)…");
+        wait(&mut app);
+        assert_eq!(app.message, "Folder sizes ready");
+        assert_eq!(app.meta_cache[&root.join("big")].size, 8000);
+        assert_eq!(app.meta_cache[&root.join("small")].size, 10);
+        assert_eq!(app.items, vec![root.join("big"), root.join("small")]);
+
+        // Известные размеры переживают обновление списка
+        app.refresh_items();
+        assert_eq!(app.meta_cache[&root.join("big")].size, 8000);
+
+        // Уход из каталога отменяет ожидание
+        app.compute_dir_sizes(vec![root.join("small")]);
+        app.current_dir = root.join("big");
+        app.refresh_items();
+        assert!(app.dir_sizer.pending.is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,474 @@
+use crate::app::{App, Focus};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Clone)]
+pub struct DuEntry {
+    pub name: String,
+    pub size: u64,
+    pub files: u64,
+    pub is_dir: bool,
+}
+
+/// Содержимое каталога, отсортированное по размеру
+#[derive(Default)]
+pub struct DirUsage {
+    pub entries: Vec<DuEntry>,
+    pub size: u64,
+    pub files: u64,
+}
+
+#[derive(Default)]
+pub struct DuProgress {
+    pub files: u64,
+    pub bytes: u64,
+    pub errors: u64,
+    pub current: String,
+    pub finished: bool,
+}
+
+/// Место на диске: занятые блоки на Unix, длина файла на остальных системах
+pub fn disk_size(meta: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        meta.len()
+    }
+}
+
+#[cfg(unix)]
+fn device(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Рекурсивный подсчёт в пределах одной файловой системы, без перехода по ссылкам.
+/// Каждый каталог попадает в `cache`, если он задан.
+pub struct Scanner<'a> {
+    dev: Option<u64>,
+    cache: Option<&'a Mutex<HashMap<PathBuf, DirUsage>>>,
+    progress: &'a Mutex<DuProgress>,
+    cancel: &'a AtomicBool,
+    /// Файлы с несколькими жёсткими ссылками считаются один раз
+    inodes: Mutex<HashSet<(u64, u64)>>,
+    /// Длина файлов вместо занятых блоков, как в колонке размера списка
+    apparent: bool,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(
+        root: &Path,
+        cache: Option<&'a Mutex<HashMap<PathBuf, DirUsage>>>,
+        progress: &'a Mutex<DuProgress>,
+        cancel: &'a AtomicBool,
+    ) -> Self {
+        let dev = fs::metadata(root).ok().as_ref().and_then(device);
+        Scanner { dev, cache, progress, cancel, inodes: Mutex::new(HashSet::new()), apparent: false }
+    }
+
+    pub fn apparent(mut self) -> Self {
+        self.apparent = true;
+        self
+    }
+
+    fn counted_once(&self, meta: &fs::Metadata) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if meta.nlink() > 1 {
+                return self.inodes.lock().map(|mut s| s.insert((meta.dev(), meta.ino()))).unwrap_or(true);
+            }
+        }
+        let _ = meta;
+        true
+    }
+
+    /// Записи каталога: файлы сразу, подкаталоги — отдельным списком
+    fn read(&self, dir: &Path) -> (Vec<DuEntry>, Vec<(String, PathBuf)>) {
+        let mut entries = Vec::new();
+        let mut subdirs = Vec::new();
+        let Ok(read) = fs::read_dir(dir) else {
+            if let Ok(mut p) = self.progress.lock() {
+                p.errors += 1;
+            }
+            return (entries, subdirs);
+        };
+        let (mut files, mut bytes) = (0, 0);
+        for entry in read.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                // Точки монтирования других дисков не считаем
+                if self.dev.is_none() || device(&meta) == self.dev {
+                    subdirs.push((name, entry.path()));
+                } else {
+                    entries.push(DuEntry { name, size: 0, files: 0, is_dir: true });
+                }
+                continue;
+            }
+            let size = match self.counted_once(&meta) {
+                false => 0,
+                true if self.apparent => meta.len(),
+                true => disk_size(&meta),
+            };
+            files += 1;
+            bytes += size;
+            entries.push(DuEntry { name, size, files: 1, is_dir: false });
+        }
+        if let Ok(mut p) = self.progress.lock() {
+            p.files += files;
+            p.bytes += bytes;
+            p.current = dir.to_string_lossy().to_string();
+        }
+        (entries, subdirs)
+    }
+
+    fn store(&self, dir: &Path, mut entries: Vec<DuEntry>) -> (u64, u64) {
+        let size = entries.iter().map(|e| e.size).sum();
+        let files = entries.iter().map(|e| e.files).sum();
+        if let Some(cache) = self.cache {
+            entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            if let Ok(mut c) = cache.lock() {
+                c.insert(dir.to_path_buf(), DirUsage { entries, size, files });
+            }
+        }
+        (size, files)
+    }
+
+    /// Размер и число файлов поддерева (последовательно)
+    pub fn scan(&self, dir: &Path) -> (u64, u64) {
+        let (mut entries, subdirs) = self.read(dir);
+        for (name, path) in subdirs {
+            if self.cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let (size, files) = self.scan(&path);
+            entries.push(DuEntry { name, size, files, is_dir: true });
+        }
+        self.store(dir, entries)
+    }
+
+    /// То же, но подкаталоги верхнего уровня разбирают несколько потоков
+    pub fn scan_parallel(&self, root: &Path) -> (u64, u64) {
+        let (mut entries, subdirs) = self.read(root);
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+        let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(subdirs.len().max(1));
+        thread::scope(|s| {
+            for _ in 0..workers {
+                s.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((name, path)) = subdirs.get(i) else {
+                            break;
+                        };
+                        if self.cancel.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let (size, files) = self.scan(path);
+                        if let Ok(mut r) = results.lock() {
+                            r.push(DuEntry { name: name.clone(), size, files, is_dir: true });
+                        }
+                    }
+                });
+            }
+        });
+        entries.extend(results.into_inner().unwrap_or_default());
+        self.store(root, entries)
+    }
+}
+
+pub struct DiskUsage {
+    pub root: PathBuf,
+    pub current: PathBuf,
+    pub cache: Arc<Mutex<HashMap<PathBuf, DirUsage>>>,
+    pub progress: Arc<Mutex<DuProgress>>,
+    cancel: Arc<AtomicBool>,
+    pub state: ListState,
+    /// Позиция курсора в уже открытых каталогах
+    positions: HashMap<PathBuf, usize>,
+    pub confirm_delete: Option<DuEntry>,
+}
+
+impl DiskUsage {
+    pub fn finished(&self) -> bool {
+        self.progress.lock().map(|p| p.finished).unwrap_or(true)
+    }
+
+    fn selected_entry(&self) -> Option<DuEntry> {
+        let cache = self.cache.lock().ok()?;
+        cache.get(&self.current)?.entries.get(self.state.selected()?).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.cache.lock().ok().and_then(|c| c.get(&self.current).map(|u| u.entries.len())).unwrap_or(0)
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state.select(Some((current + delta).rem_euclid(len as isize) as usize));
+    }
+
+    fn enter(&mut self, dir: PathBuf) {
+        if let Some(i) = self.state.selected() {
+            self.positions.insert(self.current.clone(), i);
+        }
+        let selected = self.positions.get(&dir).copied().unwrap_or(0);
+        self.current = dir;
+        self.state.select(if self.len() == 0 { None } else { Some(selected.min(self.len() - 1)) });
+    }
+
+    /// Убирает удалённую запись из кэша и вычитает её из всех родителей до корня
+    fn forget(&mut self, entry: &DuEntry) {
+        let Ok(mut cache) = self.cache.lock() else {
+            return;
+        };
+        let path = self.current.join(&entry.name);
+        cache.retain(|p, _| !p.starts_with(&path));
+        if let Some(usage) = cache.get_mut(&self.current) {
+            usage.entries.retain(|e| e.name != entry.name);
+        }
+        let mut dir = self.current.clone();
+        loop {
+            if let Some(usage) = cache.get_mut(&dir) {
+                usage.size = usage.size.saturating_sub(entry.size);
+                usage.files = usage.files.saturating_sub(entry.files);
+            }
+            if dir == self.root {
+                break;
+            }
+            let Some(parent) = dir.parent().map(Path::to_path_buf) else {
+                break;
+            };
+            let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if let Some(usage) = cache.get_mut(&parent) {
+                if let Some(e) = usage.entries.iter_mut().find(|e| e.name == name) {
+                    e.size = e.size.saturating_sub(entry.size);
+                    e.files = e.files.saturating_sub(entry.files);
+                }
+                usage.entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            }
+            dir = parent;
+        }
+        let len = cache.get(&self.current).map(|u| u.entries.len()).unwrap_or(0);
+        drop(cache);
+        self.state.select(if len == 0 { None } else { Some(self.state.selected().unwrap_or(0).min(len - 1)) });
+    }
+}
+
+impl Drop for DiskUsage {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl App {
+    /// Анализ занятого места: указанный каталог, выбранный диск или текущая папка
+    pub fn open_disk_usage(&mut self, dir: Option<&str>) {
+        let root = match dir {
+            Some(d) => crate::commands::expand_path(&self.current_dir, d),
+            None if self.focus == Focus::DriveList => match self.drive_state.selected().and_then(|i| self.drives.get(i)) {
+                Some((mount, _)) => PathBuf::from(mount),
+                None => self.current_dir.clone(),
+            },
+            None => self.current_dir.clone(),
+        };
+        if !root.is_dir() {
+            self.message = format!("Disk usage: {} is not a directory", root.display());
+            return;
+        }
+        let cache = Arc::new(Mutex::new(HashMap::new()));
+        let progress = Arc::new(Mutex::new(DuProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (shared_cache, shared, stop, scan_root) = (cache.clone(), progress.clone(), cancel.clone(), root.clone());
+        thread::spawn(move || {
+            Scanner::new(&scan_root, Some(&shared_cache), &shared, &stop).scan_parallel(&scan_root);
+            if let Ok(mut p) = shared.lock() {
+                p.current.clear();
+                p.finished = true;
+            }
+        });
+        self.disk_usage = Some(DiskUsage {
+            current: root.clone(),
+            root,
+            cache,
+            progress,
+            cancel,
+            state: ListState::default(),
+            positions: HashMap::new(),
+            confirm_delete: None,
+        });
+    }
+
+    fn delete_du_entry(&mut self, entry: DuEntry) {
+        let Some(view) = self.disk_usage.as_mut() else {
+            return;
+        };
+        let path = view.current.join(&entry.name);
+        let res = if entry.is_dir { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        match res {
+            Ok(_) => {
+                view.forget(&entry);
+                self.message = format!("Deleted {} ({})", entry.name, crate::ui::format_size(entry.size));
+            }
+            Err(e) => self.message = format!("Delete {}: {}", entry.name, e),
+        }
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    pub fn handle_disk_usage_input(&mut self, key: KeyEvent) {
+        let Some(view) = self.disk_usage.as_mut() else {
+            return;
+        };
+
+        if let Some(entry) = view.confirm_delete.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.delete_du_entry(entry);
+            } else {
+                self.message = "Delete cancelled.".to_string();
+            }
+            return;
+        }
+
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+            if !view.finished() {
+                self.message = "Disk usage scan cancelled".to_string();
+            }
+            self.disk_usage = None;
+            return;
+        }
+        if !view.finished() {
+            return;
+        }
+
+        let command = match self.keymap.lookup(&key) {
+            Some(c) => c,
+            None => match key.code {
+                KeyCode::Enter | KeyCode::Right => "open",
+                KeyCode::Left | KeyCode::Backspace => "back",
+                KeyCode::Down => "down",
+                KeyCode::Up => "up",
+                KeyCode::Char('d') | KeyCode::Delete => "delete",
+                _ => return,
+            },
+        };
+        match command {
+            "down" => view.move_by(1),
+            "up" => view.move_by(-1),
+            "top" => view.state.select(if view.len() == 0 { None } else { Some(0) }),
+            "bottom" => view.state.select(view.len().checked_sub(1)),
+            "open" => match view.selected_entry() {
+                Some(entry) if entry.is_dir => {
+                    let dir = view.current.join(&entry.name);
+                    if view.cache.lock().is_ok_and(|c| c.contains_key(&dir)) {
+                        view.enter(dir);
+                    } else {
+                        self.message = format!("{} is on another filesystem", entry.name);
+                    }
+                }
+                // Файл: закрыть анализ и показать его в списке
+                Some(entry) => {
+                    let path = view.current.join(&entry.name);
+                    self.disk_usage = None;
+                    self.reveal_path(&path);
+                }
+                None => {}
+            },
+            "back" => {
+                if view.current != view.root
+                    && let Some(parent) = view.current.parent().map(Path::to_path_buf)
+                {
+                    view.enter(parent);
+                }
+            }
+            "delete" => {
+                if let Some(entry) = view.selected_entry() {
+                    self.message = format!("Delete '{}' ({})? (y/N)", entry.name, crate::ui::format_size(entry.size));
+                    view.confirm_delete = Some(entry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Забирает выделение по готовности скана (вызывается из `tick`)
+    pub fn poll_disk_usage(&mut self) {
+        if let Some(view) = self.disk_usage.as_mut()
+            && view.state.selected().is_none()
+            && view.finished()
+            && view.len() > 0
+        {
+            view.state.select(Some(0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_and_forgets() {
+        let dir = std::env::temp_dir().join(format!("ffm_du_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::create_dir_all(dir.join("c")).unwrap();
+        fs::write(dir.join("a/b/big"), vec![1u8; 64 * 1024]).unwrap();
+        fs::write(dir.join("a/small"), "x").unwrap();
+        fs::write(dir.join("c/one"), "y").unwrap();
+
+        let cache = Mutex::new(HashMap::new());
+        let progress = Mutex::new(DuProgress::default());
+        let cancel = AtomicBool::new(false);
+        let (size, files) = Scanner::new(&dir, Some(&cache), &progress, &cancel).scan_parallel(&dir);
+        assert_eq!(files, 3);
+        let cache = cache.into_inner().unwrap();
+        let root = &cache[&dir];
+        assert_eq!(root.size, size);
+        assert_eq!(root.entries[0].name, "a");
+        assert_eq!(root.entries[0].size, cache[&dir.join("a")].size);
+        assert!(cache[&dir.join("a/b")].size >= 64 * 1024);
+
+        let mut view = DiskUsage {
+            root: dir.clone(),
+            current: dir.join("a"),
+            cache: Arc::new(Mutex::new(cache)),
+            progress: Arc::new(Mutex::new(DuProgress { finished: true, ..Default::default() })),
+            cancel: Arc::new(AtomicBool::new(false)),
+            state: ListState::default(),
+            positions: HashMap::new(),
+            confirm_delete: None,
+        };
+        let b = view.cache.lock().unwrap()[&dir.join("a")].entries[0].clone();
+        assert_eq!(b.name, "b");
+        view.forget(&b);
+        let cache = view.cache.lock().unwrap();
+        assert!(!cache.contains_key(&dir.join("a/b")));
+        assert_eq!(cache[&dir].size, size - b.size);
+        assert_eq!(cache[&dir].files, 2);
+        drop(cache);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
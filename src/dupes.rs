@@ -0,0 +1,581 @@
+use crate::app::App;
+use crate::hashes::{Algorithm, MultiHasher};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+/// Сколько байт с начала файла хешируется на втором этапе
+const PARTIAL_LEN: u64 = 16 * 1024;
+
+#[derive(Clone)]
+pub struct DupeFile {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+}
+
+pub struct DupeGroup {
+    pub size: u64,
+    pub files: Vec<DupeFile>,
+}
+
+impl DupeGroup {
+    /// Место, которое освободится, если оставить одну копию
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len() as u64).saturating_sub(1)
+    }
+}
+
+/// Общее состояние с фоновым сканированием
+#[derive(Default)]
+pub struct DupeProgress {
+    pub phase: &'static str,
+    pub files_seen: usize,
+    pub done_bytes: u64,
+    pub total_bytes: u64,
+    pub current: String,
+    pub errors: usize,
+    pub groups: Vec<DupeGroup>,
+    pub finished: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Keep {
+    Oldest,
+    Newest,
+    Shortest,
+}
+
+impl Keep {
+    pub fn label(self) -> &'static str {
+        match self {
+            Keep::Oldest => "oldest",
+            Keep::Newest => "newest",
+            Keep::Shortest => "shortest path",
+        }
+    }
+
+    /// Индекс копии, которая остаётся
+    pub fn keeper(self, files: &[DupeFile]) -> usize {
+        let indices = 0..files.len();
+        let found = match self {
+            Keep::Oldest => indices.min_by_key(|&i| (files[i].modified, &files[i].path)),
+            Keep::Newest => indices.max_by_key(|&i| (files[i].modified, std::cmp::Reverse(&files[i].path))),
+            Keep::Shortest => indices.min_by_key(|&i| (files[i].path.as_os_str().len(), &files[i].path)),
+        };
+        found.unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DupeAction {
+    Trash,
+    HardLink,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DupeRow {
+    Group(usize),
+    File(usize, usize),
+}
+
+pub struct DupeView {
+    pub root: PathBuf,
+    pub progress: Arc<Mutex<DupeProgress>>,
+    cancel: Arc<AtomicBool>,
+    /// Группы переезжают сюда из `progress`, когда скан закончен
+    pub groups: Vec<DupeGroup>,
+    pub loaded: bool,
+    pub state: ListState,
+    pub marked: HashSet<PathBuf>,
+    pub confirm: Option<DupeAction>,
+}
+
+impl DupeView {
+    pub fn rows(&self) -> Vec<DupeRow> {
+        let mut rows = Vec::new();
+        for (g, group) in self.groups.iter().enumerate() {
+            rows.push(DupeRow::Group(g));
+            rows.extend((0..group.files.len()).map(|f| DupeRow::File(g, f)));
+        }
+        rows
+    }
+
+    pub fn reclaimable(&self) -> u64 {
+        self.groups.iter().map(DupeGroup::reclaimable).sum()
+    }
+
+    /// (число, объём) отмеченных копий
+    pub fn marked_total(&self) -> (usize, u64) {
+        self.groups
+            .iter()
+            .flat_map(|g| g.files.iter().map(move |f| (g.size, f)))
+            .filter(|(_, f)| self.marked.contains(&f.path))
+            .fold((0, 0), |(n, bytes), (size, _)| (n + 1, bytes + size))
+    }
+
+    fn selected_row(&self) -> Option<DupeRow> {
+        self.rows().get(self.state.selected()?).copied()
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let len = self.rows().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state.select(Some((current + delta).rem_euclid(len as isize) as usize));
+    }
+
+    /// Отмечает все копии, кроме одной, в каждой группе
+    fn mark_all_but_one(&mut self, keep: Keep) {
+        self.marked.clear();
+        for group in &self.groups {
+            let keeper = keep.keeper(&group.files);
+            for (i, file) in group.files.iter().enumerate() {
+                if i != keeper {
+                    self.marked.insert(file.path.clone());
+                }
+            }
+        }
+    }
+
+    /// Убирает обработанные файлы и группы, где осталась одна копия
+    fn forget(&mut self, done: &HashSet<PathBuf>) {
+        for group in &mut self.groups {
+            group.files.retain(|f| !done.contains(&f.path));
+        }
+        self.groups.retain(|g| g.files.len() > 1);
+        self.marked.retain(|p| !done.contains(p));
+        let len = self.rows().len();
+        self.state.select(if len == 0 { None } else { Some(self.state.selected().unwrap_or(0).min(len - 1)) });
+    }
+}
+
+impl Drop for DupeView {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn set_progress(progress: &Mutex<DupeProgress>, f: impl FnOnce(&mut DupeProgress)) {
+    if let Ok(mut p) = progress.lock() {
+        f(&mut p);
+    }
+}
+
+/// Обход дерева без перехода по ссылкам; жёсткие ссылки на один inode считаются одним файлом
+fn collect_files(root: &Path, hidden: bool, progress: &Mutex<DupeProgress>, cancel: &AtomicBool) -> HashMap<u64, Vec<DupeFile>> {
+    let mut by_size: HashMap<u64, Vec<DupeFile>> = HashMap::new();
+    #[cfg(unix)]
+    let mut inodes = HashSet::new();
+    let mut stack = vec![root.to_path_buf()];
+    let mut seen = 0;
+    while let Some(dir) = stack.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            set_progress(progress, |p| p.errors += 1);
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(entry.path());
+                continue;
+            }
+            if !meta.is_file() || meta.len() == 0 {
+                continue;
+            }
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                if !inodes.insert((meta.dev(), meta.ino())) {
+                    continue;
+                }
+            }
+            by_size.entry(meta.len()).or_default().push(DupeFile { path: entry.path(), modified: meta.modified().ok() });
+            seen += 1;
+            if seen % 64 == 0 {
+                set_progress(progress, |p| {
+                    p.files_seen = seen;
+                    p.current = dir.to_string_lossy().to_string();
+                });
+            }
+        }
+    }
+    set_progress(progress, |p| p.files_seen = seen);
+    by_size
+}
+
+/// BLAKE3 первых `limit` байт (или всего файла)
+fn hash_file(path: &Path, limit: Option<u64>, progress: &Mutex<DupeProgress>, cancel: &AtomicBool) -> io::Result<String> {
+    let file = fs::File::open(path)?;
+    let mut reader: Box<dyn Read> = match limit {
+        Some(n) => Box::new(file.take(n)),
+        None => Box::new(file),
+    };
+    let mut hasher = MultiHasher::new(&[Algorithm::Blake3]);
+    let mut buf = vec![0u8; 256 * 1024];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        set_progress(progress, |p| p.done_bytes += n as u64);
+    }
+    Ok(hasher.finish().pop().map(|(_, hex)| hex).unwrap_or_default())
+}
+
+/// Делит группы одинакового размера по хешу (частичному или полному)
+fn split_by_hash(
+    groups: Vec<(u64, Vec<DupeFile>)>,
+    limit: Option<u64>,
+    progress: &Mutex<DupeProgress>,
+    cancel: &AtomicBool,
+) -> Vec<(u64, Vec<DupeFile>)> {
+    let mut out = Vec::new();
+    for (size, files) in groups {
+        let mut by_hash: HashMap<String, Vec<DupeFile>> = HashMap::new();
+        for file in files {
+            if cancel.load(Ordering::Relaxed) {
+                return Vec::new();
+            }
+            set_progress(progress, |p| p.current = file.path.to_string_lossy().to_string());
+            match hash_file(&file.path, limit, progress, cancel) {
+                Ok(hash) => by_hash.entry(hash).or_default().push(file),
+                Err(_) => set_progress(progress, |p| p.errors += 1),
+            }
+        }
+        out.extend(by_hash.into_values().filter(|f| f.len() > 1).map(|f| (size, f)));
+    }
+    out
+}
+
+/// Размер → первые 16 КиБ → весь файл
+pub fn find_duplicates(root: &Path, hidden: bool, progress: &Mutex<DupeProgress>, cancel: &AtomicBool) -> Vec<DupeGroup> {
+    set_progress(progress, |p| p.phase = "Scanning");
+    let by_size = collect_files(root, hidden, progress, cancel);
+    let candidates: Vec<(u64, Vec<DupeFile>)> = by_size.into_iter().filter(|(_, f)| f.len() > 1).collect();
+
+    let partial_bytes = candidates.iter().map(|(size, f)| (*size).min(PARTIAL_LEN) * f.len() as u64).sum();
+    set_progress(progress, |p| {
+        p.phase = "Comparing starts";
+        p.done_bytes = 0;
+        p.total_bytes = partial_bytes;
+    });
+    let partial = split_by_hash(candidates, Some(PARTIAL_LEN), progress, cancel);
+
+    // Маленькие файлы уже прочитаны целиком
+    let (small, large): (Vec<_>, Vec<_>) = partial.into_iter().partition(|(size, _)| *size <= PARTIAL_LEN);
+    let full_bytes = large.iter().map(|(size, f)| size * f.len() as u64).sum();
+    set_progress(progress, |p| {
+        p.phase = "Comparing contents";
+        p.done_bytes = 0;
+        p.total_bytes = full_bytes;
+    });
+    let full = split_by_hash(large, None, progress, cancel);
+
+    let mut groups: Vec<DupeGroup> = small
+        .into_iter()
+        .chain(full)
+        .map(|(size, mut files)| {
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            DupeGroup { size, files }
+        })
+        .collect();
+    groups.sort_by(|a, b| b.reclaimable().cmp(&a.reclaimable()).then_with(|| a.files[0].path.cmp(&b.files[0].path)));
+    groups
+}
+
+/// Обе копии такие же, как при скане: размер, время изменения и первые 16 КиБ совпадают
+fn still_same(size: u64, keeper: &DupeFile, file: &DupeFile) -> bool {
+    let unchanged = |f: &DupeFile| {
+        fs::symlink_metadata(&f.path).is_ok_and(|m| m.is_file() && m.len() == size && m.modified().ok() == f.modified)
+    };
+    let head = |f: &DupeFile| -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        fs::File::open(&f.path)?.take(PARTIAL_LEN).read_to_end(&mut buf)?;
+        Ok(buf)
+    };
+    unchanged(keeper) && unchanged(file) && matches!((head(keeper), head(file)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Заменяет `path` жёсткой ссылкой на `target`: ссылка создаётся рядом и переименовывается поверх
+fn replace_with_hard_link(target: &Path, path: &Path) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.ffm-link", name));
+    fs::hard_link(target, &tmp)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+impl App {
+    pub fn open_dupes(&mut self, dir: Option<&str>) {
+        let root = match dir {
+            Some(d) => crate::commands::expand_path(&self.current_dir, d),
+            None => self.current_dir.clone(),
+        };
+        if !root.is_dir() {
+            self.message = format!("Dupes: {} is not a directory", root.display());
+            return;
+        }
+        let progress = Arc::new(Mutex::new(DupeProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (shared, stop, scan_root, hidden) = (progress.clone(), cancel.clone(), root.clone(), self.show_hidden);
+        thread::spawn(move || {
+            let groups = find_duplicates(&scan_root, hidden, &shared, &stop);
+            set_progress(&shared, |p| {
+                p.groups = groups;
+                p.current.clear();
+                p.finished = true;
+            });
+        });
+        self.dupes = Some(DupeView {
+            root,
+            progress,
+            cancel,
+            groups: Vec::new(),
+            loaded: false,
+            state: ListState::default(),
+            marked: HashSet::new(),
+            confirm: None,
+        });
+    }
+
+    /// Забирает результат скана, когда он готов (вызывается из `tick`)
+    pub fn poll_dupes(&mut self) {
+        let Some(view) = self.dupes.as_mut().filter(|v| !v.loaded) else {
+            return;
+        };
+        let Ok(mut progress) = view.progress.lock() else {
+            return;
+        };
+        if !progress.finished {
+            return;
+        }
+        view.groups = std::mem::take(&mut progress.groups);
+        let errors = progress.errors;
+        drop(progress);
+        view.loaded = true;
+        view.state.select(if view.groups.is_empty() { None } else { Some(1) });
+        let count: usize = view.groups.iter().map(|g| g.files.len()).sum();
+        self.message = format!(
+            "Found {} duplicate group(s), {} file(s), {} reclaimable{}",
+            view.groups.len(),
+            count,
+            crate::ui::format_size(view.reclaimable()),
+            if errors > 0 { format!(", {} unreadable", errors) } else { String::new() }
+        );
+    }
+
+    fn apply_dupe_action(&mut self, action: DupeAction) {
+        let Some(view) = self.dupes.as_mut() else {
+            return;
+        };
+        view.confirm = None;
+        // Хотя бы одна копия в каждой группе должна остаться
+        if view.groups.iter().any(|g| g.files.iter().all(|f| view.marked.contains(&f.path))) {
+            self.message = "Every copy in a group is marked — unmark one to keep".to_string();
+            return;
+        }
+        let mut done = HashSet::new();
+        let mut errors = Vec::new();
+        let mut changed = Vec::new();
+        let mut freed = 0;
+        for group in &view.groups {
+            let Some(keeper) = group.files.iter().find(|f| !view.marked.contains(&f.path)) else {
+                continue;
+            };
+            for file in group.files.iter().filter(|f| view.marked.contains(&f.path)) {
+                // Скан мог устареть: изменённую копию не трогаем
+                if !still_same(group.size, keeper, file) {
+                    changed.push(file.path.display().to_string());
+                    continue;
+                }
+                let res = match action {
+                    DupeAction::Trash => crate::trash::trash(&file.path).map(|_| ()),
+                    DupeAction::HardLink => replace_with_hard_link(&keeper.path, &file.path),
+                };
+                match res {
+                    Ok(_) => {
+                        done.insert(file.path.clone());
+                        freed += group.size;
+                    }
+                    Err(e) => errors.push(format!("{}: {}", file.path.display(), e)),
+                }
+            }
+        }
+        view.forget(&done);
+        let verb = if action == DupeAction::Trash { "Trashed" } else { "Hard-linked" };
+        let mut message = match errors.first() {
+            None => format!("{} {} file(s), freed {}", verb, done.len(), crate::ui::format_size(freed)),
+            Some(first) => format!("{} {} file(s), {} failed: {}", verb, done.len(), errors.len(), first),
+        };
+        if let Some(first) = changed.first() {
+            message.push_str(&format!("; {} skipped, changed since the scan: {}", changed.len(), first));
+        }
+        self.message = message;
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    fn mark_dupes(&mut self, keep: Keep) {
+        let Some(view) = self.dupes.as_mut() else {
+            return;
+        };
+        view.mark_all_but_one(keep);
+        let (count, bytes) = view.marked_total();
+        self.message = format!("Keeping {}: marked {} file(s), {}", keep.label(), count, crate::ui::format_size(bytes));
+    }
+
+    fn prompt_dupe_action(&mut self, action: DupeAction) {
+        let Some(view) = self.dupes.as_mut() else {
+            return;
+        };
+        let (count, bytes) = view.marked_total();
+        if count == 0 {
+            self.message = "Mark copies first (Space, or o/n/s to keep one per group)".to_string();
+            return;
+        }
+        view.confirm = Some(action);
+        self.message = match action {
+            DupeAction::Trash => format!("Trash {} file(s), {}? (y/N)", count, crate::ui::format_size(bytes)),
+            DupeAction::HardLink => format!("Replace {} file(s) with hard links? (y/N)", count),
+        };
+    }
+
+    pub fn handle_dupes_input(&mut self, key: KeyEvent) {
+        let Some(view) = self.dupes.as_mut() else {
+            return;
+        };
+
+        if let Some(action) = view.confirm {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.apply_dupe_action(action);
+            } else {
+                view.confirm = None;
+                self.message = "Cancelled".to_string();
+            }
+            return;
+        }
+
+        // Перемещение, отметка и удаление — теми же клавишами, что в списке файлов
+        match self.keymap.lookup(&key) {
+            Some("down") => return view.move_by(1),
+            Some("up") => return view.move_by(-1),
+            Some("top") => return view.state.select(Some(0)),
+            Some("bottom") => return view.state.select(Some(view.rows().len().saturating_sub(1))),
+            Some("select") => {
+                if let Some(DupeRow::File(g, f)) = view.selected_row() {
+                    let path = view.groups[g].files[f].path.clone();
+                    if !view.marked.remove(&path) {
+                        view.marked.insert(path);
+                    }
+                }
+                return view.move_by(1);
+            }
+            Some("delete") => return self.prompt_dupe_action(DupeAction::Trash),
+            _ => {}
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if !view.loaded {
+                    self.message = "Duplicate scan cancelled".to_string();
+                }
+                self.dupes = None;
+            }
+            KeyCode::Down => view.move_by(1),
+            KeyCode::Up => view.move_by(-1),
+            KeyCode::Char('o') => self.mark_dupes(Keep::Oldest),
+            KeyCode::Char('n') => self.mark_dupes(Keep::Newest),
+            KeyCode::Char('s') => self.mark_dupes(Keep::Shortest),
+            KeyCode::Char('u') => view.marked.clear(),
+            KeyCode::Char('d') | KeyCode::Delete => self.prompt_dupe_action(DupeAction::Trash),
+            KeyCode::Char('h') => self.prompt_dupe_action(DupeAction::HardLink),
+            KeyCode::Enter => {
+                if let Some(DupeRow::File(g, f)) = view.selected_row() {
+                    let path = view.groups[g].files[f].path.clone();
+                    self.dupes = None;
+                    self.reveal_path(&path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn finds_groups_and_picks_keepers() {
+        let dir = std::env::temp_dir().join(format!("ffm_dupes_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let big: Vec<u8> = (0..40_000u32).map(|i| (i % 251) as u8).collect();
+        let mut other = big.clone();
+        // Отличие после первых 16 КиБ — отсеивается только полным хешем
+        other[30_000] ^= 1;
+        fs::write(dir.join("a.bin"), &big).unwrap();
+        fs::write(dir.join("sub/copy.bin"), &big).unwrap();
+        fs::write(dir.join("other.bin"), &other).unwrap();
+        fs::write(dir.join("x.txt"), "same").unwrap();
+        fs::write(dir.join("y.txt"), "same").unwrap();
+        fs::write(dir.join("z.txt"), "diff").unwrap();
+        fs::write(dir.join("empty1"), "").unwrap();
+        fs::write(dir.join("empty2"), "").unwrap();
+
+        let progress = Mutex::new(DupeProgress::default());
+        let groups = find_duplicates(&dir, true, &progress, &AtomicBool::new(false));
+        let names: Vec<Vec<String>> = groups
+            .iter()
+            .map(|g| g.files.iter().map(|f| f.path.strip_prefix(&dir).unwrap().to_string_lossy().to_string()).collect())
+            .collect();
+        assert_eq!(names, vec![vec!["a.bin", "sub/copy.bin"], vec!["x.txt", "y.txt"]]);
+        assert_eq!(groups[0].reclaimable(), 40_000);
+
+        // Перед действием копии перепроверяются
+        let (keeper, copy) = (&groups[1].files[0], &groups[1].files[1]);
+        assert!(still_same(4, keeper, copy));
+        let stamp = fs::metadata(&copy.path).unwrap().modified().unwrap();
+        fs::write(&copy.path, "SAME").unwrap();
+        fs::File::options().write(true).open(&copy.path).unwrap().set_modified(stamp).unwrap();
+        assert!(!still_same(4, keeper, copy));
+        fs::write(&copy.path, "same!").unwrap();
+        assert!(!still_same(4, keeper, copy));
+        let _ = fs::remove_dir_all(&dir);
+
+        let t = SystemTime::UNIX_EPOCH;
+        let files = vec![
+            DupeFile { path: PathBuf::from("/a/long/path"), modified: Some(t + Duration::from_secs(5)) },
+            DupeFile { path: PathBuf::from("/b"), modified: Some(t + Duration::from_secs(9)) },
+            DupeFile { path: PathBuf::from("/c/old"), modified: Some(t + Duration::from_secs(1)) },
+        ];
+        assert_eq!(Keep::Oldest.keeper(&files), 2);
+        assert_eq!(Keep::Newest.keeper(&files), 1);
+        assert_eq!(Keep::Shortest.keeper(&files), 1);
+    }
+}
@@ -0,0 +1,1141 @@
+use crate::app::{App, ClipboardOp};
+use crate::archive::Format;
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Куда распаковывать
+#[derive(Clone, PartialEq, Debug)]
+pub enum Destination {
+    /// В текущую папку
+    Here,
+    /// В папку с именем архива
+    Folder,
+    /// В указанную папку
+    Path(PathBuf),
+}
+
+/// Что умеем читать: форматы `compress` и 7z
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Native(Format),
+    SevenZip,
+}
+
+pub fn kind_of(name: &str) -> Option<Kind> {
+    match Format::from_name(name) {
+        Some(f) => Some(Kind::Native(f)),
+        None if name.to_lowercase().ends_with(".7z") => Some(Kind::SevenZip),
+        None => None,
+    }
+}
+
+/// Имя архива без расширений архива: `foo.tar.gz` → `foo`
+pub fn archive_stem(name: &str) -> String {
+    let lower = name.to_lowercase();
+    for ext in [".tar.gz", ".tar.xz", ".tar.zst", ".tgz", ".txz", ".tzst", ".tar", ".zip", ".7z"] {
+        if lower.ends_with(ext) && name.len() > ext.len() {
+            return name[..name.len() - ext.len()].to_string();
+        }
+    }
+    name.to_string()
+}
+
+#[derive(Default)]
+pub struct ExtractProgress {
+    pub total_bytes: u64,
+    pub done_bytes: u64,
+    /// Пропущенные записи: небезопасные пути и ссылки, ошибки отдельных файлов
+    pub refused: Vec<String>,
+    pub result: Option<Result<(), String>>,
+}
+
+/// Распаковка идёт во временную папку внутри цели, потом переносится как вставка
+pub struct ExtractJob {
+    pub archive: PathBuf,
+    pub target: PathBuf,
+    destination: Destination,
+    /// `.ffm-extract-*` в цели; распакованное лежит в `staging/.content`
+    staging: PathBuf,
+    pub progress: Arc<Mutex<ExtractProgress>>,
+    cancel: Arc<AtomicBool>,
+    refused: Vec<String>,
+}
+
+impl ExtractJob {
+    fn content(&self) -> PathBuf {
+        self.staging.join(".content")
+    }
+}
+
+fn set_progress(progress: &Mutex<ExtractProgress>, f: impl FnOnce(&mut ExtractProgress)) {
+    if let Ok(mut p) = progress.lock() {
+        f(&mut p);
+    }
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "cancelled")
+}
+
+/// Путь записи внутри цели; `None`, если он абсолютный или содержит `..`
+pub fn safe_relative(name: &str) -> Option<PathBuf> {
+    let name = name.replace('\\', "/");
+    let bytes = name.as_bytes();
+    if name.starts_with('/') || (bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut out = PathBuf::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            p => out.push(p),
+        }
+    }
+    Some(out)
+}
+
+/// Куда ведёт ссылка из `stack` (компоненты её папки) с учётом уже созданных ссылок;
+/// `None` — если путь выходит за `root`
+fn resolve_inside(root: &Path, mut stack: Vec<OsString>, target: &Path, depth: usize) -> Option<Vec<OsString>> {
+    if depth > 40 {
+        return None;
+    }
+    for comp in target.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                stack.pop()?;
+            }
+            Component::Normal(name) => {
+                stack.push(name.to_os_string());
+                let here = root.join(stack.iter().collect::<PathBuf>());
+                if let Ok(next) = fs::read_link(&here) {
+                    stack.pop();
+                    stack = resolve_inside(root, stack, &next, depth + 1)?;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(stack)
+}
+
+/// Проходит ли папка записи через ссылку или не-папку: тогда `create_dir_all` и `symlink` ушли бы по ней
+fn parent_unsafe(root: &Path, rel: &Path) -> bool {
+    let mut path = root.to_path_buf();
+    for comp in rel.parent().into_iter().flat_map(|p| p.iter()) {
+        path.push(comp);
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if !meta.is_dir() => return true,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+fn link_escapes(root: &Path, rel: &Path) -> bool {
+    let Ok(target) = fs::read_link(root.join(rel)) else {
+        return false;
+    };
+    let parent: Vec<OsString> = rel.parent().map(|p| p.iter().map(|c| c.to_os_string()).collect()).unwrap_or_default();
+    resolve_inside(root, parent, &target, 0).is_none()
+}
+
+/// Считает прочитанные из архива байты для прогресса
+struct CountingReader<'a, R: Read> {
+    inner: R,
+    progress: &'a Mutex<ExtractProgress>,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        set_progress(self.progress, |p| p.done_bytes += n as u64);
+        Ok(n)
+    }
+}
+
+/// Принимает записи архива и раскладывает их под `root`. Ссылки создаются в самом конце,
+/// чтобы через них ничего не записать; каждая проверяется до создания.
+pub struct Sink<'a> {
+    root: PathBuf,
+    refused: Vec<String>,
+    links: Vec<(PathBuf, String)>,
+    dirs: Vec<(PathBuf, Option<u32>, Option<u64>)>,
+    cancel: &'a AtomicBool,
+}
+
+impl<'a> Sink<'a> {
+    pub fn new(root: &Path, cancel: &'a AtomicBool) -> Self {
+        Sink { root: root.to_path_buf(), refused: Vec::new(), links: Vec::new(), dirs: Vec::new(), cancel }
+    }
+
+    pub fn refuse(&mut self, reason: String) {
+        self.refused.push(reason);
+    }
+
+    fn checked(&mut self, name: &str) -> Option<PathBuf> {
+        match safe_relative(name) {
+            Some(rel) if rel.as_os_str().is_empty() => None,
+            Some(rel) => Some(rel),
+            None => {
+                self.refused.push(format!("{}: path leaves the target", name));
+                None
+            }
+        }
+    }
+
+    pub fn dir(&mut self, name: &str, mode: Option<u32>, mtime: Option<u64>) -> io::Result<()> {
+        if let Some(rel) = self.checked(name) {
+            fs::create_dir_all(self.root.join(&rel))?;
+            self.dirs.push((rel, mode, mtime));
+        }
+        Ok(())
+    }
+
+    /// Записывает файл; возвращает его путь, если он создан
+    pub fn file(&mut self, name: &str, data: &mut dyn Read, mode: Option<u32>, mtime: Option<u64>) -> io::Result<Option<PathBuf>> {
+        let Some(rel) = self.checked(name) else {
+            return Ok(None);
+        };
+        let path = self.root.join(&rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if path.is_dir() {
+            self.refused.push(format!("{}: a folder with that name exists", name));
+            return Ok(None);
+        }
+        let mut out = fs::File::create(&path)?;
+        let mut buf = vec![0u8; 256 * 1024];
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(cancelled());
+            }
+            let n = match data.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            io::Write::write_all(&mut out, &buf[..n])?;
+        }
+        if let Some(secs) = mtime {
+            let _ = out.set_modified(UNIX_EPOCH + Duration::from_secs(secs));
+        }
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = out.set_permissions(fs::Permissions::from_mode(mode & 0o777));
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        Ok(Some(path))
+    }
+
+    pub fn symlink(&mut self, name: &str, target: &str) {
+        let Some(rel) = self.checked(name) else {
+            return;
+        };
+        if target.is_empty() || Path::new(target).has_root() {
+            self.refused.push(format!("{} -> {}: symlink points outside the target", name, target));
+            return;
+        }
+        self.links.push((rel, target.to_string()));
+    }
+
+    pub fn hard_link(&mut self, name: &str, target: &str) -> io::Result<()> {
+        let Some(rel) = self.checked(name) else {
+            return Ok(());
+        };
+        let Some(target_rel) = safe_relative(target) else {
+            self.refused.push(format!("{} => {}: hard link points outside the target", name, target));
+            return Ok(());
+        };
+        let path = self.root.join(&rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _ = fs::remove_file(&path);
+        fs::hard_link(self.root.join(target_rel), &path).or_else(|e| {
+            self.refused.push(format!("{}: {}", name, e));
+            Ok(())
+        })
+    }
+
+    /// Ссылки, права и время папок; ссылки, которые выводят наружу, удаляются.
+    /// Возвращает пропущенные записи.
+    pub fn finish(mut self) -> Vec<String> {
+        let links = std::mem::take(&mut self.links);
+        for (rel, target) in &links {
+            if parent_unsafe(&self.root, rel) {
+                self.refused.push(format!("{}: path goes through a symlink", rel.display()));
+                continue;
+            }
+            let parent: Vec<OsString> = rel.parent().map(|p| p.iter().map(|c| c.to_os_string()).collect()).unwrap_or_default();
+            if resolve_inside(&self.root, parent, Path::new(target), 0).is_none() {
+                self.refused.push(format!("{} -> {}: symlink points outside the target", rel.display(), target));
+                continue;
+            }
+            let path = self.root.join(rel);
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if path.symlink_metadata().is_ok() {
+                self.refused.push(format!("{}: already exists", rel.display()));
+                continue;
+            }
+            #[cfg(unix)]
+            if let Err(e) = std::os::unix::fs::symlink(target, &path) {
+                self.refused.push(format!("{}: {}", rel.display(), e));
+            }
+            #[cfg(not(unix))]
+            self.refused.push(format!("{} -> {}: symlinks are not supported here", rel.display(), target));
+        }
+        for (rel, target) in &links {
+            if link_escapes(&self.root, rel) {
+                let _ = fs::remove_file(self.root.join(rel));
+                self.refused.push(format!("{} -> {}: symlink points outside the target", rel.display(), target));
+            }
+        }
+        // Глубокие папки первыми: права родителя не мешают менять детей
+        let mut dirs = std::mem::take(&mut self.dirs);
+        dirs.sort_by_key(|(rel, ..)| std::cmp::Reverse(rel.components().count()));
+        for (rel, mode, mtime) in dirs {
+            let path = self.root.join(&rel);
+            if let Some(secs) = mtime
+                && let Ok(dir) = fs::File::open(&path)
+            {
+                let _ = dir.set_modified(UNIX_EPOCH + Duration::from_secs(secs));
+            }
+            #[cfg(unix)]
+            if let Some(mode) = mode {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777 | 0o700));
+            }
+            #[cfg(not(unix))]
+            let _ = mode;
+        }
+        self.refused
+    }
+}
+
+// --- TAR ---
+
+fn tar_number(field: &[u8]) -> u64 {
+    if field[0] & 0x80 != 0 {
+        return field[1..].iter().fold((field[0] & 0x7f) as u64, |acc, &b| (acc << 8) | b as u64);
+    }
+    let text: String = field.iter().take_while(|&&b| b != 0).map(|&b| b as char).collect();
+    u64::from_str_radix(text.trim(), 8).unwrap_or(0)
+}
+
+fn tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+fn skip(r: &mut impl Read, n: u64) -> io::Result<()> {
+    let copied = io::copy(&mut r.take(n), &mut io::sink())?;
+    if copied < n {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar archive"));
+    }
+    Ok(())
+}
+
+/// Записи PAX: `<длина> <ключ>=<значение>\n`
+fn pax_records(data: &[u8]) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space]).ok().and_then(|s| s.parse::<usize>().ok()) else {
+            break;
+        };
+        if len <= space + 1 || len > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..len - 1]).to_string();
+        if let Some((k, v)) = record.split_once('=') {
+            out.push((k.to_string(), v.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Запись tar: имя уже с учётом GNU/PAX расширений
+pub struct TarEntry {
+    pub name: String,
+    pub link: String,
+    /// Тип из заголовка: `0`/`7` файл, `5` папка, `2` ссылка, `1` жёсткая ссылка
+    pub kind: u8,
+    pub size: u64,
+    pub mode: u32,
+    pub mtime: u64,
+    /// Смещение данных от начала потока
+    pub offset: u64,
+}
+
+impl TarEntry {
+    pub fn is_file(&self) -> bool {
+        matches!(self.kind, b'0' | 0 | b'7')
+    }
+}
+
+/// Поток tar по записям; данные текущей записи читаются через `Read`
+pub struct TarReader<R: Read> {
+    inner: R,
+    pos: u64,
+    left: u64,
+    pad: u64,
+}
+
+impl<R: Read> TarReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, pos: 0, left: 0, pad: 0 }
+    }
+
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        skip(&mut self.inner, n)?;
+        self.pos += n;
+        Ok(())
+    }
+
+    fn block_data(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        if size > 1 << 20 {
+            return Err(invalid("tar extended header too large"));
+        }
+        let mut data = vec![0u8; size as usize];
+        self.inner.read_exact(&mut data)?;
+        self.pos += size;
+        self.skip((512 - size % 512) % 512)?;
+        Ok(data)
+    }
+
+    /// Следующая запись; недочитанные данные предыдущей пропускаются
+    pub fn next_entry(&mut self) -> io::Result<Option<TarEntry>> {
+        self.skip(self.left + self.pad)?;
+        (self.left, self.pad) = (0, 0);
+        let (mut long_name, mut long_link, mut pax_path, mut pax_link, mut pax_size) = (None, None, None, None, None);
+        loop {
+            let mut h = [0u8; 512];
+            match self.inner.read_exact(&mut h) {
+                Ok(()) => self.pos += 512,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            if h.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            let stored = tar_number(&h[148..156]);
+            let sum: u64 = h.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 }).sum();
+            if stored != sum {
+                return Err(invalid("bad tar header checksum"));
+            }
+            let kind = h[156];
+            let size = tar_number(&h[124..136]);
+            match kind {
+                b'L' => long_name = Some(tar_string(&self.block_data(size)?)),
+                b'K' => long_link = Some(tar_string(&self.block_data(size)?)),
+                b'x' => {
+                    for (key, value) in pax_records(&self.block_data(size)?) {
+                        match key.as_str() {
+                            "path" => pax_path = Some(value),
+                            "linkpath" => pax_link = Some(value),
+                            "size" => pax_size = value.parse::<u64>().ok(),
+                            _ => {}
+                        }
+                    }
+                }
+                b'g' => {
+                    self.block_data(size)?;
+                }
+                _ => {
+                    let size = pax_size.unwrap_or(size);
+                    let mut name = tar_string(&h[..100]);
+                    if &h[257..262] == b"ustar" && h[345] != 0 {
+                        name = format!("{}/{}", tar_string(&h[345..500]), name);
+                    }
+                    // У ссылок и папок данных нет, даже если размер указан
+                    let data = if matches!(kind, b'1' | b'2' | b'5') { 0 } else { size };
+                    (self.left, self.pad) = (data, (512 - data % 512) % 512);
+                    return Ok(Some(TarEntry {
+                        name: long_name.or(pax_path).unwrap_or(name),
+                        link: long_link.or(pax_link).unwrap_or_else(|| tar_string(&h[157..257])),
+                        kind,
+                        size: data,
+                        mode: tar_number(&h[100..108]) as u32,
+                        mtime: tar_number(&h[136..148]),
+                        offset: self.pos,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for TarReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let want = buf.len().min(self.left.min(usize::MAX as u64) as usize);
+        if want == 0 {
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..want])?;
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar archive"));
+        }
+        self.pos += n as u64;
+        self.left -= n as u64;
+        Ok(n)
+    }
+}
+
+fn read_tar(r: impl Read, sink: &mut Sink) -> io::Result<()> {
+    let mut tar = TarReader::new(r);
+    while let Some(entry) = tar.next_entry()? {
+        if sink.cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let (mode, mtime) = (Some(entry.mode), Some(entry.mtime));
+        match entry.kind {
+            _ if entry.is_file() => {
+                sink.file(&entry.name, &mut tar, mode, mtime)?;
+            }
+            b'5' => sink.dir(&entry.name, mode, mtime)?,
+            b'2' => sink.symlink(&entry.name, &entry.link),
+            b'1' => sink.hard_link(&entry.name, &entry.link)?,
+            kind => sink.refused.push(format!("{}: unsupported entry type '{}'", entry.name, kind as char)),
+        }
+    }
+    Ok(())
+}
+
+// --- ZIP ---
+
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([b[i], b[i + 1]])
+}
+
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]])
+}
+
+fn u64_at(b: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(b[i..i + 8].try_into().unwrap_or_default())
+}
+
+/// Запись центрального каталога zip
+pub struct ZipEntry {
+    pub name: String,
+    method: u16,
+    encrypted: bool,
+    crc: u32,
+    compressed: u64,
+    pub size: u64,
+    /// Смещение локального заголовка
+    offset: u64,
+    pub mode: Option<u32>,
+    pub mtime: u64,
+}
+
+impl ZipEntry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode.is_some_and(|m| m & 0o170000 == 0o120000)
+    }
+
+    /// Почему запись не прочитать (шифрование, незнакомый метод)
+    pub fn unsupported(&self) -> Option<String> {
+        if self.encrypted {
+            Some(format!("{}: encrypted entries are not supported", self.name))
+        } else if self.method != 0 && self.method != 8 {
+            Some(format!("{}: unsupported compression method {}", self.name, self.method))
+        } else {
+            None
+        }
+    }
+}
+
+fn dos_to_unix(time: u16, date: u16) -> u64 {
+    let (y, m, d) = (1980 + (date >> 9) as i64, ((date >> 5) & 15) as i64, (date & 31) as i64);
+    // Дни от эпохи по гражданскому календарю
+    let (y2, m2) = if m <= 2 { (y - 1, m + 9) } else { (y, m - 3) };
+    let era = y2.div_euclid(400);
+    let yoe = y2 - era * 400;
+    let doy = (153 * m2 + 2) / 5 + d - 1;
+    let days = era * 146097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719468;
+    let secs = days * 86400 + ((time >> 11) as i64) * 3600 + (((time >> 5) & 63) as i64) * 60 + ((time & 31) as i64) * 2;
+    secs.max(0) as u64
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Центральный каталог (с zip64)
+pub fn zip_entries(file: &mut fs::File) -> io::Result<Vec<ZipEntry>> {
+    let len = file.metadata()?.len();
+    let tail_len = len.min(65_557);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0u8; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let eocd = (0..tail.len().saturating_sub(21)).rev().find(|&i| u32_at(&tail, i) == 0x06054b50).ok_or_else(|| invalid("not a zip archive"))?;
+    let mut count = u16_at(&tail, eocd + 10) as u64;
+    let mut cd_size = u32_at(&tail, eocd + 12) as u64;
+    let mut cd_offset = u32_at(&tail, eocd + 16) as u64;
+    if eocd >= 20 && u32_at(&tail, eocd - 20) == 0x07064b50 {
+        let mut rec = [0u8; 56];
+        file.seek(SeekFrom::Start(u64_at(&tail, eocd - 20 + 8)))?;
+        file.read_exact(&mut rec)?;
+        if u32_at(&rec, 0) == 0x06064b50 {
+            count = u64_at(&rec, 32);
+            cd_size = u64_at(&rec, 40);
+            cd_offset = u64_at(&rec, 48);
+        }
+    }
+    if cd_size > len {
+        return Err(invalid("corrupt zip directory"));
+    }
+    let mut cd = vec![0u8; cd_size as usize];
+    file.seek(SeekFrom::Start(cd_offset))?;
+    file.read_exact(&mut cd)?;
+    let mut entries = Vec::new();
+    let mut p = 0;
+    for _ in 0..count {
+        if p + 46 > cd.len() || u32_at(&cd, p) != 0x02014b50 {
+            return Err(invalid("corrupt zip directory"));
+        }
+        let (name_len, extra_len, comment_len) = (u16_at(&cd, p + 28) as usize, u16_at(&cd, p + 30) as usize, u16_at(&cd, p + 32) as usize);
+        if p + 46 + name_len + extra_len > cd.len() {
+            return Err(invalid("corrupt zip directory"));
+        }
+        let name = String::from_utf8_lossy(&cd[p + 46..p + 46 + name_len]).to_string();
+        let mut unpacked = u32_at(&cd, p + 24) as u64;
+        let mut compressed = u32_at(&cd, p + 20) as u64;
+        let mut offset = u32_at(&cd, p + 42) as u64;
+        // zip64: заменяет поля, равные 0xFFFFFFFF, в этом порядке
+        let extra = &cd[p + 46 + name_len..p + 46 + name_len + extra_len];
+        let mut e = 0;
+        while e + 4 <= extra.len() {
+            let (id, size) = (u16_at(extra, e), u16_at(extra, e + 2) as usize);
+            if id == 1 {
+                let mut q = e + 4;
+                for field in [&mut unpacked, &mut compressed, &mut offset] {
+                    if *field == 0xffff_ffff && q + 8 <= extra.len() {
+                        *field = u64_at(extra, q);
+                        q += 8;
+                    }
+                }
+            }
+            e += 4 + size;
+        }
+        let made_by_unix = cd[p + 5] == 3;
+        let attrs = u32_at(&cd, p + 38);
+        entries.push(ZipEntry {
+            name,
+            method: u16_at(&cd, p + 10),
+            encrypted: u16_at(&cd, p + 8) & 1 != 0,
+            crc: u32_at(&cd, p + 16),
+            compressed,
+            size: unpacked,
+            offset,
+            mode: if made_by_unix && attrs >> 16 != 0 { Some(attrs >> 16) } else { None },
+            mtime: dos_to_unix(u16_at(&cd, p + 12), u16_at(&cd, p + 14)),
+        });
+        p += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Считает CRC32 прочитанного
+struct CrcReader<R: Read> {
+    inner: R,
+    crc: crc32fast::Hasher,
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Распакованные данные записи; `unsupported()` проверяется заранее
+pub fn open_zip_entry<'a, R: Read + Seek + 'a>(mut file: R, entry: &ZipEntry) -> io::Result<Box<dyn Read + 'a>> {
+    let mut local = [0u8; 30];
+    file.seek(SeekFrom::Start(entry.offset))?;
+    file.read_exact(&mut local)?;
+    if u32_at(&local, 0) != 0x04034b50 {
+        return Err(invalid("corrupt zip entry header"));
+    }
+    let data_start = entry.offset + 30 + u16_at(&local, 26) as u64 + u16_at(&local, 28) as u64;
+    file.seek(SeekFrom::Start(data_start))?;
+    let raw = file.take(entry.compressed);
+    Ok(if entry.method == 8 { Box::new(DeflateDecoder::new(raw)) } else { Box::new(raw) })
+}
+
+fn read_zip(path: &Path, sink: &mut Sink, progress: &Mutex<ExtractProgress>) -> io::Result<()> {
+    let mut file = fs::File::open(path)?;
+    let entries = zip_entries(&mut file)?;
+    for entry in entries {
+        if sink.cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        set_progress(progress, |p| p.done_bytes = entry.offset + entry.compressed);
+        if entry.is_dir() {
+            sink.dir(&entry.name, entry.mode, Some(entry.mtime))?;
+            continue;
+        }
+        if let Some(reason) = entry.unsupported() {
+            sink.refused.push(reason);
+            continue;
+        }
+        let mut data = CrcReader { inner: open_zip_entry(&mut file, &entry)?, crc: crc32fast::Hasher::new() };
+        if entry.is_symlink() {
+            let mut target = String::new();
+            data.by_ref().take(4096).read_to_string(&mut target)?;
+            sink.symlink(&entry.name, &target);
+            continue;
+        }
+        let written = sink.file(&entry.name, &mut data, entry.mode, Some(entry.mtime))?;
+        if data.crc.finalize() != entry.crc
+            && let Some(written) = written
+        {
+            let _ = fs::remove_file(written);
+            sink.refused.push(format!("{}: CRC mismatch, archive is damaged", entry.name));
+        }
+    }
+    Ok(())
+}
+
+// --- Внешние программы ---
+
+/// Вывод внешней программы; код выхода проверяется в конце потока, при закрытии раньше — kill
+pub struct ChildReader {
+    program: &'static str,
+    child: std::process::Child,
+    stdout: std::process::ChildStdout,
+}
+
+impl ChildReader {
+    pub fn spawn(program: &'static str, args: &[&std::ffi::OsStr]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("{} not found ({})", program, e)))?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::other("no stdout"))?;
+        Ok(Self { program, child, stdout })
+    }
+}
+
+impl Read for ChildReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stdout.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("{} failed ({})", self.program, status)));
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for ChildReader {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// Архив, кроме zip, как поток tar: xz и zstd распаковываются внешней программой,
+/// 7z перепаковывается в tar через `bsdtar`
+pub fn tar_stream(kind: Kind, archive: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let dc = ["-dc".as_ref(), archive.as_os_str()];
+    Ok(match kind {
+        Kind::Native(Format::Tar) => Box::new(io::BufReader::new(fs::File::open(archive)?)),
+        Kind::Native(Format::TarGz) => Box::new(MultiGzDecoder::new(io::BufReader::new(fs::File::open(archive)?))),
+        Kind::Native(Format::TarXz) => Box::new(ChildReader::spawn("xz", &dc)?),
+        Kind::Native(Format::TarZst) => Box::new(ChildReader::spawn("zstd", &dc)?),
+        Kind::SevenZip => {
+            let source = OsString::from(format!("@{}", archive.display()));
+            Box::new(ChildReader::spawn("bsdtar", &["-cf".as_ref(), "-".as_ref(), "--format".as_ref(), "pax".as_ref(), source.as_os_str()])?)
+        }
+        Kind::Native(Format::Zip) => return Err(invalid("zip is not a tar stream")),
+    })
+}
+
+/// tar.xz / tar.zst: распаковщик пишет tar в канал
+fn read_tar_through(program: &'static str, archive: &Path, sink: &mut Sink, progress: &Mutex<ExtractProgress>) -> io::Result<()> {
+    let child = ChildReader::spawn(program, &["-dc".as_ref(), archive.as_os_str()])?;
+    let total = progress.lock().map_or(0, |p| p.total_bytes);
+    // Сжатый размер известен, распакованный нет — прогресс грубый
+    let mut reader = CountingReader { inner: child, progress };
+    // Дочитываем хвост после конца архива: иначе распаковщик получит SIGPIPE, а код выхода не проверится
+    let res = read_tar(&mut reader, sink).and_then(|_| io::copy(&mut reader, &mut io::sink()).map(drop));
+    set_progress(progress, |p| p.done_bytes = p.done_bytes.min(total));
+    res
+}
+
+/// 7z — внешней программой; небезопасные ссылки убираются после
+fn extract_7z(archive: &Path, root: &Path) -> io::Result<()> {
+    let out_dir = format!("-o{}", root.display());
+    let tools: [(&str, Vec<&std::ffi::OsStr>); 4] = [
+        ("7zz", vec!["x".as_ref(), "-y".as_ref(), "-bd".as_ref(), out_dir.as_ref(), archive.as_os_str()]),
+        ("7z", vec!["x".as_ref(), "-y".as_ref(), "-bd".as_ref(), out_dir.as_ref(), archive.as_os_str()]),
+        ("7za", vec!["x".as_ref(), "-y".as_ref(), "-bd".as_ref(), out_dir.as_ref(), archive.as_os_str()]),
+        ("bsdtar", vec!["-xf".as_ref(), archive.as_os_str(), "-C".as_ref(), root.as_os_str()]),
+    ];
+    for (program, args) in tools {
+        match Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => return Err(io::Error::other(format!("{} failed ({})", program, status))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::other("7z archives need 7z, 7zz, 7za or bsdtar"))
+}
+
+/// Все ссылки в дереве, путями от `root`
+fn symlinks_under(root: &Path, rel: &Path, out: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(root.join(rel)).into_iter().flatten().flatten() {
+        let child = rel.join(entry.file_name());
+        match entry.file_type() {
+            Ok(t) if t.is_symlink() => out.push(child),
+            Ok(t) if t.is_dir() => symlinks_under(root, &child, out),
+            _ => {}
+        }
+    }
+}
+
+pub fn extract_into(kind: Kind, archive: &Path, root: &Path, progress: &Mutex<ExtractProgress>, cancel: &AtomicBool) -> io::Result<Vec<String>> {
+    fs::create_dir_all(root)?;
+    let mut sink = Sink::new(root, cancel);
+    let counted = || -> io::Result<_> { Ok(CountingReader { inner: io::BufReader::new(fs::File::open(archive)?), progress }) };
+    match kind {
+        Kind::Native(Format::Zip) => read_zip(archive, &mut sink, progress)?,
+        Kind::Native(Format::Tar) => read_tar(counted()?, &mut sink)?,
+        Kind::Native(Format::TarGz) => read_tar(MultiGzDecoder::new(counted()?), &mut sink)?,
+        Kind::Native(Format::TarXz) => read_tar_through("xz", archive, &mut sink, progress)?,
+        Kind::Native(Format::TarZst) => read_tar_through("zstd", archive, &mut sink, progress)?,
+        Kind::SevenZip => {
+            extract_7z(archive, root)?;
+            let mut links = Vec::new();
+            symlinks_under(root, Path::new(""), &mut links);
+            for rel in links {
+                if link_escapes(root, &rel) {
+                    let _ = fs::remove_file(root.join(&rel));
+                    sink.refused.push(format!("{}: symlink points outside the target", rel.display()));
+                }
+            }
+        }
+    }
+    Ok(sink.finish())
+}
+
+impl App {
+    /// `extract` без аргументов: строка команды с вариантом по умолчанию
+    pub fn prompt_extract(&mut self) {
+        self.start_command();
+        self.command_input.set("extract folder");
+    }
+
+    /// `extract [here|folder|<dir>]` — распаковать архив под курсором
+    pub fn extract_command(&mut self, arg: Option<&str>) {
+        if self.extract.is_some() {
+            self.message = "An archive is already being extracted".to_string();
+            return;
+        }
+        let targets = self.bulk_targets_or_highlighted();
+        let [archive] = targets.as_slice() else {
+            self.message = "Extract one archive at a time".to_string();
+            return;
+        };
+        let name = archive.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let Some(kind) = kind_of(&name).filter(|_| archive.is_file()) else {
+            self.message = format!("'{}' is not a zip, tar, tar.gz, tar.xz, tar.zst or 7z archive", name);
+            return;
+        };
+        let destination = match arg {
+            None | Some("folder") => Destination::Folder,
+            Some("here") => Destination::Here,
+            Some(dir) => Destination::Path(crate::commands::expand_path(&self.current_dir, dir)),
+        };
+        let target = match &destination {
+            Destination::Path(dir) => dir.clone(),
+            _ => archive.parent().map_or_else(|| self.current_dir.clone(), Path::to_path_buf),
+        };
+        if let Err(e) = fs::create_dir_all(&target) {
+            self.message = format!("Extract: {}: {}", target.display(), e);
+            return;
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+        let staging = target.join(format!(".ffm-extract-{}-{}", std::process::id(), nanos));
+
+        let progress = Arc::new(Mutex::new(ExtractProgress { total_bytes: fs::metadata(archive).map_or(0, |m| m.len()), ..Default::default() }));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (shared, stop, src, root) = (progress.clone(), cancel.clone(), archive.clone(), staging.join(".content"));
+        thread::spawn(move || {
+            let res = extract_into(kind, &src, &root, &shared, &stop);
+            set_progress(&shared, |p| {
+                p.result = Some(match res {
+                    Ok(refused) => {
+                        p.refused = refused;
+                        Ok(())
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => Err("Extraction cancelled".to_string()),
+                    Err(e) => Err(format!("Extract error: {}", e)),
+                });
+            });
+        });
+        self.message = format!("Extracting {}…", name);
+        self.extract = Some(ExtractJob { archive: archive.clone(), target, destination, staging, progress, cancel, refused: Vec::new() });
+    }
+
+    pub fn cancel_extract(&mut self) {
+        match self.extract.as_ref() {
+            Some(job) => {
+                job.cancel.store(true, Ordering::Relaxed);
+                self.message = "Cancelling extraction…".to_string();
+            }
+            None => self.message = "No extraction in progress".to_string(),
+        }
+    }
+
+    /// Прогресс; когда всё распаковано — перенос в цель через обычную вставку (вызывается из `tick`)
+    pub fn poll_extract(&mut self) {
+        let Some(job) = self.extract.as_mut() else {
+            return;
+        };
+        let Ok(mut progress) = job.progress.lock() else {
+            return;
+        };
+        let Some(result) = progress.result.take() else {
+            if self.message.starts_with("Extracting") && progress.total_bytes > 0 {
+                self.message = format!(
+                    "Extracting {}… {}%",
+                    job.archive.file_name().unwrap_or_default().to_string_lossy(),
+                    progress.done_bytes.min(progress.total_bytes) * 100 / progress.total_bytes
+                );
+            }
+            return;
+        };
+        job.refused = std::mem::take(&mut progress.refused);
+        drop(progress);
+        if let Err(e) = result {
+            self.message = e;
+            self.finish_extract(None);
+            return;
+        }
+        match self.staged_items() {
+            Ok(items) if items.is_empty() => {
+                self.message = "Archive is empty".to_string();
+                self.finish_extract(None);
+            }
+            Ok(items) => self.paste_next(items, ClipboardOp::Extract, 0),
+            Err(e) => {
+                self.message = format!("Extract error: {}", e);
+                self.finish_extract(None);
+            }
+        }
+    }
+
+    /// Что переносить в цель. Архив с одной папкой верхнего уровня не даёт `foo/foo/`.
+    fn staged_items(&self) -> io::Result<Vec<PathBuf>> {
+        let Some(job) = self.extract.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let content = job.content();
+        let mut children: Vec<PathBuf> = fs::read_dir(&content)?.map(|e| e.map(|e| e.path())).collect::<io::Result<_>>()?;
+        children.sort();
+        let single_dir = match children.as_slice() {
+            [only] if only.symlink_metadata().is_ok_and(|m| m.is_dir()) => Some(only.clone()),
+            _ => None,
+        };
+        if job.destination != Destination::Folder {
+            return Ok(match single_dir {
+                Some(dir) => vec![dir],
+                None => children,
+            });
+        }
+        let name = archive_stem(&job.archive.file_name().unwrap_or_default().to_string_lossy());
+        let folder = job.staging.join(&name);
+        fs::rename(single_dir.as_ref().unwrap_or(&content), &folder)?;
+        Ok(vec![folder])
+    }
+
+    /// Убирает временную папку и пишет итог; `moved` — сколько элементов перенесено
+    pub fn finish_extract(&mut self, moved: Option<usize>) {
+        let Some(job) = self.extract.take() else {
+            return;
+        };
+        let _ = fs::remove_dir_all(&job.staging);
+        if let Some(count) = moved {
+            self.message = format!(
+                "Extracted {} into {} ({} item(s))",
+                job.archive.file_name().unwrap_or_default().to_string_lossy(),
+                job.target.display(),
+                count
+            );
+        }
+        if let Some(first) = job.refused.first() {
+            self.message.push_str(&format!(" — {} entr(ies) refused: {}", job.refused.len(), first));
+        }
+        self.refresh_items();
+        self.update_preview();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unsafe_paths_and_links() {
+        assert_eq!(safe_relative("a/./b/"), Some(PathBuf::from("a/b")));
+        assert_eq!(safe_relative("../etc/passwd"), None);
+        assert_eq!(safe_relative("a/../../b"), None);
+        assert_eq!(safe_relative("/etc/passwd"), None);
+        assert_eq!(safe_relative("C:\\Windows"), None);
+        assert_eq!(archive_stem("foo.tar.gz"), "foo");
+        assert_eq!(dos_to_unix(0, (1 << 5) | 1), 315_532_800);
+
+        let root = std::env::temp_dir().join(format!("ffm_extract_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("d")).unwrap();
+        #[cfg(unix)]
+        {
+            // «x» ведёт на корень, поэтому «x/..» — уже за его пределами
+            std::os::unix::fs::symlink(".", root.join("x")).unwrap();
+            std::os::unix::fs::symlink("x/..", root.join("y")).unwrap();
+            std::os::unix::fs::symlink("../d", root.join("d/ok")).unwrap();
+            assert!(!link_escapes(&root, Path::new("x")));
+            assert!(link_escapes(&root, Path::new("y")));
+            assert!(!link_escapes(&root, Path::new("d/ok")));
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    /// Запись zip без сжатия: имя, unix-права, данные, CRC — свой или посчитанный
+    type ZipSpec<'a> = (&'a str, Option<u32>, &'a [u8], Option<u32>);
+
+    fn stored_zip(entries: &[ZipSpec]) -> Vec<u8> {
+        let (mut out, mut central) = (Vec::new(), Vec::new());
+        for &(name, mode, data, crc) in entries {
+            let crc = crc.unwrap_or_else(|| crc32fast::hash(data));
+            let sizes = [crc, data.len() as u32, data.len() as u32].map(u32::to_le_bytes).concat();
+            let offset = out.len() as u32;
+            out.extend(0x04034b50u32.to_le_bytes());
+            out.extend([20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+            out.extend(&sizes);
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0, 0]);
+            out.extend(name.as_bytes());
+            out.extend(data);
+            central.extend(0x02014b50u32.to_le_bytes());
+            central.extend([20, 3, 20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+            central.extend(&sizes);
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 8]);
+            central.extend((mode.unwrap_or(0o100644) << 16).to_le_bytes());
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+        let (cd_offset, count) = (out.len() as u32, entries.len() as u16);
+        out.extend(&central);
+        out.extend(0x06054b50u32.to_le_bytes());
+        out.extend([0, 0, 0, 0]);
+        out.extend([count.to_le_bytes(), count.to_le_bytes()].concat());
+        out.extend((central.len() as u32).to_le_bytes());
+        out.extend(cd_offset.to_le_bytes());
+        out.extend([0, 0]);
+        out
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hostile_archives_stay_inside_the_target() {
+        use crate::archive_view::tests::tar_entry;
+        let dir = std::env::temp_dir().join(format!("ffm_extract_hostile_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        fs::write(dir.join("outside/secret.txt"), "secret").unwrap();
+        let cancel = AtomicBool::new(false);
+        let progress = Mutex::new(ExtractProgress::default());
+
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "a.txt", b'0', "", b"inside");
+        // Ссылка наружу, затем файл «через» неё
+        tar_entry(&mut tar, "esc", b'2', "../outside", b"");
+        tar_entry(&mut tar, "esc/planted.txt", b'0', "", b"planted");
+        tar_entry(&mut tar, "abs", b'2', "/etc", b"");
+        tar_entry(&mut tar, "../evil.txt", b'0', "", b"evil");
+        tar_entry(&mut tar, "sub/../../evil2.txt", b'0', "", b"evil");
+        tar_entry(&mut tar, "hard", b'1', "../outside/secret.txt", b"");
+        tar_entry(&mut tar, "same", b'1', "a.txt", b"");
+        tar_entry(&mut tar, "up", b'2', "sub/../..", b"");
+        // Ссылка наружу, затем ссылка внутри неё: вторая не должна лечь снаружи
+        tar_entry(&mut tar, "esc2", b'2', "../outside", b"");
+        tar_entry(&mut tar, "esc2/rel", b'2', "x", b"");
+        tar_entry(&mut tar, "inlink", b'2', "d", b"");
+        tar_entry(&mut tar, "inlink/through", b'2', "x", b"");
+        tar.resize(tar.len() + 1024, 0);
+        fs::write(dir.join("t.tar"), &tar).unwrap();
+        let target = dir.join("tar");
+        let refused = extract_into(Kind::Native(Format::Tar), &dir.join("t.tar"), &target, &progress, &cancel).unwrap();
+
+        assert!(!dir.join("outside/planted.txt").exists());
+        assert!(!dir.join("evil.txt").exists() && !dir.join("evil2.txt").exists());
+        assert!(!fs::symlink_metadata(target.join("esc")).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(target.join("esc/planted.txt")).unwrap(), "planted");
+        assert!(fs::symlink_metadata(target.join("abs")).is_err());
+        assert!(fs::symlink_metadata(target.join("up")).is_err());
+        assert!(!target.join("hard").exists());
+        assert_eq!(fs::read_to_string(target.join("same")).unwrap(), "inside");
+        assert_eq!(fs::read_to_string(dir.join("outside/secret.txt")).unwrap(), "secret");
+        assert!(fs::symlink_metadata(dir.join("outside/rel")).is_err());
+        assert!(fs::symlink_metadata(target.join("esc2/rel")).unwrap().file_type().is_symlink());
+        assert!(fs::symlink_metadata(target.join("inlink")).unwrap().file_type().is_symlink());
+        assert!(fs::symlink_metadata(target.join("d")).is_err());
+        for name in ["esc", "abs", "../evil.txt", "sub/../../evil2.txt", "hard", "up", "esc2 ", "inlink/through: path goes through a symlink"] {
+            assert!(refused.iter().any(|r| r.starts_with(name)), "{} not in {:?}", name, refused);
+        }
+
+        let zip = stored_zip(&[
+            ("good.txt", None, b"fine", None),
+            ("bad.txt", None, b"damaged", Some(0xdead_beef)),
+            ("../zip-evil.txt", None, b"evil", None),
+            ("zlink", Some(0o120777), b"../../outside", None),
+        ]);
+        fs::write(dir.join("t.zip"), &zip).unwrap();
+        let target = dir.join("zip");
+        let refused = extract_into(Kind::Native(Format::Zip), &dir.join("t.zip"), &target, &progress, &cancel).unwrap();
+        assert_eq!(fs::read_to_string(target.join("good.txt")).unwrap(), "fine");
+        assert!(!target.join("bad.txt").exists());
+        assert!(!dir.join("zip-evil.txt").exists());
+        assert!(fs::symlink_metadata(target.join("zlink")).is_err());
+        assert!(refused.iter().any(|r| r == "bad.txt: CRC mismatch, archive is damaged"), "{:?}", refused);
+        assert_eq!(refused.len(), 3, "{:?}", refused);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
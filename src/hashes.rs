@@ -0,0 +1,147 @@
+//! Контрольные суммы: SHA-256, SHA-1, MD5, BLAKE3, CRC32 (крейты RustCrypto, `blake3`, `crc32fast`).
+//! Все считаются за один проход по файлу.
+
+use sha2::Digest;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Algorithm {
+    Sha256,
+    Sha1,
+    Md5,
+    Blake3,
+    Crc32,
+}
+
+pub const ALGORITHMS: &[Algorithm] = &[Algorithm::Sha256, Algorithm::Sha1, Algorithm::Md5, Algorithm::Blake3, Algorithm::Crc32];
+
+impl Algorithm {
+    pub fn label(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha1 => "SHA-1",
+            Algorithm::Md5 => "MD5",
+            Algorithm::Blake3 => "BLAKE3",
+            Algorithm::Crc32 => "CRC32",
+        }
+    }
+
+    /// Алгоритм по имени файла сумм: `SHA256SUMS`, `foo.sha256`, `MD5SUMS`, `x.b3`…
+    pub fn from_sums_name(name: &str) -> Option<Algorithm> {
+        let lower = name.to_lowercase();
+        if lower.contains("sha256") {
+            Some(Algorithm::Sha256)
+        } else if lower.contains("sha1") {
+            Some(Algorithm::Sha1)
+        } else if lower.contains("md5") {
+            Some(Algorithm::Md5)
+        } else if lower.contains("b3") || lower.contains("blake3") {
+            Some(Algorithm::Blake3)
+        } else {
+            None
+        }
+    }
+
+    /// Длина суммы в hex-символах
+    pub fn hex_len(self) -> usize {
+        match self {
+            Algorithm::Sha256 | Algorithm::Blake3 => 64,
+            Algorithm::Sha1 => 40,
+            Algorithm::Md5 => 32,
+            Algorithm::Crc32 => 8,
+        }
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Набор хешей для одного прохода
+pub struct MultiHasher {
+    algorithms: Vec<Algorithm>,
+    sha256: sha2::Sha256,
+    sha1: sha1::Sha1,
+    md5: md5::Md5,
+    blake3: blake3::Hasher,
+    crc32: crc32fast::Hasher,
+}
+
+impl MultiHasher {
+    pub fn new(algorithms: &[Algorithm]) -> Self {
+        MultiHasher {
+            algorithms: algorithms.to_vec(),
+            sha256: sha2::Sha256::new(),
+            sha1: sha1::Sha1::new(),
+            md5: md5::Md5::new(),
+            blake3: blake3::Hasher::new(),
+            crc32: crc32fast::Hasher::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for alg in &self.algorithms {
+            match alg {
+                Algorithm::Sha256 => self.sha256.update(data),
+                Algorithm::Sha1 => self.sha1.update(data),
+                Algorithm::Md5 => self.md5.update(data),
+                Algorithm::Blake3 => {
+                    self.blake3.update(data);
+                }
+                Algorithm::Crc32 => self.crc32.update(data),
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<(Algorithm, String)> {
+        let mut out = Vec::new();
+        for alg in &self.algorithms {
+            let digest = match alg {
+                Algorithm::Sha256 => hex(&self.sha256.clone().finalize()),
+                Algorithm::Sha1 => hex(&self.sha1.clone().finalize()),
+                Algorithm::Md5 => hex(&self.md5.clone().finalize()),
+                Algorithm::Blake3 => self.blake3.finalize().to_hex().to_string(),
+                Algorithm::Crc32 => format!("{:08x}", self.crc32.clone().finalize()),
+            };
+            out.push((*alg, digest));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(data: &[u8]) -> Vec<String> {
+        let mut h = MultiHasher::new(ALGORITHMS);
+        // Кусками разной длины — проверяем буферизацию
+        for part in data.chunks(37) {
+            h.update(part);
+        }
+        h.finish().into_iter().map(|(_, d)| d).collect()
+    }
+
+    #[test]
+    fn known_vectors() {
+        assert_eq!(
+            all(b""),
+            vec![
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+                "d41d8cd98f00b204e9800998ecf8427e",
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+                "00000000",
+            ]
+        );
+        assert_eq!(
+            all(b"abc"),
+            vec![
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "900150983cd24fb0d6963f7d28e17f72",
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+                "352441c2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use crate::commands::{COMMANDS, SECTIONS, describe, section_of};
+use crate::config::KeysConfig;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Одна привязка: клавиша из конфига → строка команды (`:` словарь).
+/// Последовательности записываются через пробел: "g h".
+#[derive(Clone)]
+pub struct Binding {
+    pub key: String,
+    pub command: String,
+}
+
+impl Binding {
+    pub fn sequence(&self) -> Vec<&str> {
+        self.key.split(' ').filter(|k| !k.is_empty()).collect()
+    }
+}
+
+/// Результат сопоставления набранных клавиш с раскладкой
+pub enum Resolution<'a> {
+    /// Последовательность завершена
+    Command(&'a str),
+    /// Набран префикс: точное совпадение (если есть) и продолжения —
+    /// (следующая клавиша, команда или None для группы)
+    Pending(Option<&'a str>, Vec<(String, Option<&'a str>)>),
+    NoMatch,
+}
+
+/// Активная раскладка, собранная из `KeysConfig`.
+/// Пользовательские `[keys.bindings]` идут первыми и перекрывают встроенные.
+#[derive(Clone, Default)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    pub fn from_config(keys: &KeysConfig) -> Keymap {
+        let mut bindings: Vec<Binding> = keys
+            .bindings
+            .iter()
+            .map(|(key, command)| Binding { key: key.clone(), command: command.clone() })
+            .collect();
+
+        let named = [
+            (&keys.quit, "quit"),
+            (&keys.search, "search"),
+            (&keys.command, "command"),
+            (&keys.palette, "palette"),
+            (&keys.macro_record, "macro record"),
+            (&keys.macro_play, "macro play"),
+            (&keys.down, "down"),
+            (&keys.up, "up"),
+            (&keys.submit, "open"),
+            (&keys.back_dir, "back"),
+            (&keys.create, "create"),
+            (&keys.delete, "delete"),
+            (&keys.rename, "rename"),
+            (&keys.bulk_rename, "bulkrename"),
+            (&keys.pattern_rename, "patternrename"),
+            (&keys.undo, "undo"),
+            (&keys.link, "link"),
+            (&keys.follow_link, "follow"),
+            (&keys.properties, "properties"),
+            (&keys.xattrs, "xattr"),
+            (&keys.tag, "tag"),
+            (&keys.checksum, "checksum"),
+            (&keys.dupes, "dupes"),
+            (&keys.cleanup, "cleanup"),
+            (&keys.compress, "compress"),
+            (&keys.extract, "extract"),
+            (&keys.disk_usage, "du"),
+            (&keys.dir_size, "dirsize"),
+            (&keys.share, "share"),
+            (&keys.edit, "edit"),
+            (&keys.sort, "sort"),
+            (&keys.select, "select"),
+            (&keys.copy, "copy"),
+            (&keys.cut, "cut"),
+            (&keys.paste, "paste"),
+            (&keys.favorite, "fav"),
+            (&keys.focus_files, "focus files"),
+            (&keys.focus_drives, "focus drives"),
+            (&keys.reload, "reload"),
+            (&keys.help, "help"),
+        ];
+        for (key, command) in named {
+            bindings.push(Binding { key: key.clone(), command: command.to_string() });
+        }
+
+        // Стрелки и прочие альтернативы, которые работали всегда
+        let builtin = [
+            ("down", "down"),
+            ("up", "up"),
+            ("enter", "open"),
+            ("right", "open"),
+            ("left", "back"),
+            ("backspace", "back"),
+            ("shift-left", "resize -5"),
+            ("shift-right", "resize +5"),
+            ("F5", "reload"),
+            ("g g", "top"),
+            ("G", "bottom"),
+            ("g h", "cd ~"),
+            ("g r", "cd /"),
+            ("tab", "focus next"),
+            ("ctrl-b", "set statusbar!"),
+            ("F", "fav remove"),
+        ];
+        for (key, command) in builtin {
+            bindings.push(Binding { key: key.to_string(), command: command.to_string() });
+        }
+
+        Keymap { bindings }
+    }
+
+    /// Команда для нажатой клавиши (первое совпадение)
+    pub fn lookup(&self, key: &KeyEvent) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|b| key_matches(key, &b.key))
+            .map(|b| b.command.as_str())
+    }
+
+    /// Сопоставляет набранную последовательность. Продолжения важнее точного
+    /// совпадения: "g" ждёт следующую клавишу, если есть "g g".
+    pub fn resolve(&self, pending: &[KeyEvent]) -> Resolution<'_> {
+        let mut exact: Option<&str> = None;
+        let mut next: Vec<(String, Option<&str>)> = Vec::new();
+        for b in &self.bindings {
+            let seq = b.sequence();
+            if seq.len() < pending.len() || !pending.iter().zip(&seq).all(|(k, s)| key_matches(k, s)) {
+                continue;
+            }
+            if seq.len() == pending.len() {
+                exact.get_or_insert(b.command.as_str());
+                continue;
+            }
+            let key = seq[pending.len()].to_string();
+            let command = if seq.len() == pending.len() + 1 { Some(b.command.as_str()) } else { None };
+            // Первая привязка побеждает, как и в lookup
+            if !next.iter().any(|(k, _)| *k == key) {
+                next.push((key, command));
+            }
+        }
+        match (exact, next.is_empty()) {
+            (exact, false) => Resolution::Pending(exact, next),
+            (Some(command), true) => Resolution::Command(command),
+            (None, true) => Resolution::NoMatch,
+        }
+    }
+
+    /// Все клавиши команды в читаемом виде
+    pub fn keys_for(&self, command: &str) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for b in self.bindings.iter().filter(|b| b.command == command) {
+            let k = display_key(&b.key);
+            if !keys.contains(&k) {
+                keys.push(k);
+            }
+        }
+        keys
+    }
+
+    /// Первая (основная) клавиша команды
+    pub fn key_for(&self, command: &str) -> Option<String> {
+        self.keys_for(command).into_iter().next()
+    }
+
+    /// Разделы справки: (раздел, [(клавиши, описание)]) в порядке `SECTIONS`,
+    /// затем пользовательские привязки и команды, доступные только через `:`
+    pub fn help_sections(&self) -> Vec<(String, Vec<(String, String)>)> {
+        let mut sections: Vec<(String, Vec<(String, String)>)> = SECTIONS
+            .iter()
+            .chain(std::iter::once(&"Custom"))
+            .map(|s| (s.to_string(), Vec::new()))
+            .collect();
+
+        let mut seen: Vec<&str> = Vec::new();
+        for b in &self.bindings {
+            if seen.contains(&b.command.as_str()) {
+                continue;
+            }
+            seen.push(&b.command);
+            let section = section_of(&b.command);
+            if let Some((_, rows)) = sections.iter_mut().find(|(s, _)| s == section) {
+                rows.push((self.keys_for(&b.command).join(" / "), describe(&b.command)));
+            }
+        }
+
+        let commands: Vec<(String, String)> = COMMANDS
+            .iter()
+            .filter(|c| !seen.contains(&c.name) || c.usage.contains('<'))
+            .map(|c| (format!(":{}", c.usage), c.description.to_string()))
+            .collect();
+        sections.push(("Commands".to_string(), commands));
+
+        sections.retain(|(_, rows)| !rows.is_empty());
+        sections
+    }
+}
+
+/// Клавиша в формате конфига ("ctrl-x", "shift-left", "a") — для сохранения макросов
+pub fn key_to_string(key: &KeyEvent) -> String {
+    let code = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        _ => return String::new(),
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        parts.push("ctrl");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        parts.push("alt");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) && !matches!(key.code, KeyCode::Char(_)) {
+        parts.push("shift");
+    }
+    parts.push(&code);
+    parts.join("-")
+}
+
+/// Обратное к `key_to_string`
+pub fn parse_key(s: &str) -> Option<KeyEvent> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+    // "ctrl--" — минус как клавиша
+    let (mods, key) = match s.strip_suffix("--") {
+        Some(prefix) => (prefix, "-"),
+        None => match s.rsplit_once('-') {
+            Some((mods, key)) => (mods, key),
+            None => ("", s),
+        },
+    };
+    let mut modifiers = KeyModifiers::NONE;
+    for m in mods.split('-').filter(|m| !m.is_empty()) {
+        match m {
+            "ctrl" => modifiers |= KeyModifiers::CONTROL,
+            "alt" => modifiers |= KeyModifiers::ALT,
+            "shift" => modifiers |= KeyModifiers::SHIFT,
+            _ => return None,
+        }
+    }
+    let code = match key {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        k if k.len() > 1 && k.starts_with('f') => KeyCode::F(k[1..].parse().ok()?),
+        k => {
+            let mut chars = k.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+/// Нажатая клавиша в виде для подсказок
+pub fn key_label(key: &KeyEvent) -> String {
+    display_key(&key_to_string(key))
+}
+
+/// Клавиша из конфига в виде для подсказок: "ctrl-b" → "Ctrl+B", "down" → "↓"
+pub fn display_key(binding: &str) -> String {
+    let parts: Vec<&str> = binding.split('-').collect();
+    let (mods, key) = match parts.split_last() {
+        Some((key, mods)) if !key.is_empty() => (mods, *key),
+        // "-" сам по себе или "ctrl--"
+        _ => (&parts[..parts.len().saturating_sub(2)], "-"),
+    };
+    let key = match key.to_lowercase().as_str() {
+        "down" => "↓".to_string(),
+        "up" => "↑".to_string(),
+        "left" => "←".to_string(),
+        "right" => "→".to_string(),
+        "enter" => "Enter".to_string(),
+        "esc" => "Esc".to_string(),
+        "space" => "Space".to_string(),
+        "backspace" => "Bksp".to_string(),
+        "tab" => "Tab".to_string(),
+        "delete" => "Del".to_string(),
+        k if k.len() > 1 && k.starts_with('f') => k.to_uppercase(),
+        _ if mods.is_empty() => key.to_string(),
+        k => k.to_uppercase(),
+    };
+    let mut out: Vec<String> = mods
+        .iter()
+        .map(|m| {
+            let mut c = m.chars();
+            match c.next() {
+                Some(first) => first.to_uppercase().chain(c).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    out.push(key);
+    out.join("+")
+}
+
+pub fn key_matches(key: &KeyEvent, binding: &str) -> bool {
+    // Одиночные символы сравниваются с учётом регистра: "d" и "D" — разные клавиши
+    if let KeyCode::Char(c) = key.code
+        && c != ' '
+        && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    {
+        let mut chars = binding.chars();
+        return chars.next() == Some(c) && chars.next().is_none();
+    }
+
+    let binding = binding.to_lowercase();
+    let code_str = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string().to_lowercase(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        _ => String::new(),
+    };
+
+    let mut parts = Vec::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        parts.push("ctrl");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        parts.push("alt");
+    }
+    // Shift для символов уже учтён в самом символе
+    if key.modifiers.contains(KeyModifiers::SHIFT) && !matches!(key.code, KeyCode::Char(_)) {
+        parts.push("shift");
+    }
+
+    if !parts.is_empty() || code_str.len() > 1 {
+        parts.push(&code_str);
+        let formed = parts.join("-");
+        formed == binding
+    } else {
+        code_str == binding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::config::Config;
+    use crate::vfs::memory::MemFs;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    fn press(app: &mut App, c: char) {
+        app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+
+    /// Приложение над папкой из `n` файлов в памяти
+    fn app_with(bindings: &[(&str, &str)], n: usize) -> App {
+        let mut config = Config::default();
+        for (key, command) in bindings {
+            config.keys.bindings.insert(key.to_string(), command.to_string());
+        }
+        let mem = MemFs::new();
+        for i in 0..n {
+            mem.add_file(&format!("/w/f{}", i), "");
+        }
+        let mut app = App::with_config(config, PathBuf::from("/w"));
+        app.fs = Box::new(mem);
+        app.refresh_items();
+        app
+    }
+
+    fn ev(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn letters_are_case_sensitive() {
+        assert!(key_matches(&ev('d'), "d"));
+        assert!(!key_matches(&ev('d'), "D"));
+        assert!(key_matches(&KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT), "D"));
+        assert!(!key_matches(&KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT), "d"));
+        assert!(key_matches(&KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL), "ctrl-D"));
+        assert!(key_matches(&KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT), "shift-left"));
+        assert!(key_matches(&ev(' '), "space"));
+
+        let keymap = Keymap::from_config(&Config::default().keys);
+        assert_eq!(keymap.lookup(&KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT)), Some("delete"));
+        assert_eq!(keymap.lookup(&ev('d')), None);
+    }
+
+    #[test]
+    fn resolves_sequences() {
+        let mut config = Config::default();
+        config.keys.bindings.insert("j".to_string(), "up".to_string());
+        config.keys.bindings.insert("z z z".to_string(), "top".to_string());
+        let keymap = Keymap::from_config(&config.keys);
+        // Пользовательская привязка перекрывает встроенную
+        assert_eq!(keymap.lookup(&ev('j')), Some("up"));
+
+        let Resolution::Pending(exact, next) = keymap.resolve(&[ev('g')]) else {
+            panic!("g is a prefix");
+        };
+        assert_eq!(exact, None);
+        assert!(next.contains(&("g".to_string(), Some("top"))));
+        assert!(next.contains(&("h".to_string(), Some("cd ~"))));
+        assert!(matches!(keymap.resolve(&[ev('g'), ev('g')]), Resolution::Command("top")));
+        assert!(matches!(keymap.resolve(&[ev('g'), ev('!')]), Resolution::NoMatch));
+
+        // Середина длинной последовательности — группа без команды
+        let Resolution::Pending(None, next) = keymap.resolve(&[ev('z')]) else {
+            panic!("z is a prefix");
+        };
+        assert_eq!(next, vec![("z".to_string(), None)]);
+        assert!(matches!(keymap.resolve(&[ev('z'), ev('z'), ev('z')]), Resolution::Command("top")));
+        assert_eq!(keymap.key_for("top").as_deref(), Some("z z z"));
+    }
+
+    #[test]
+    fn counts_repeat_commands() {
+        let mut app = app_with(&[], 10);
+        press(&mut app, '5');
+        press(&mut app, 'j');
+        assert_eq!(app.state.selected(), Some(5));
+        press(&mut app, '2');
+        press(&mut app, 'k');
+        assert_eq!(app.state.selected(), Some(3));
+        // Esc сбрасывает набранное число
+        press(&mut app, '4');
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        press(&mut app, 'j');
+        assert_eq!(app.state.selected(), Some(4));
+        // Последовательность тоже принимает число
+        press(&mut app, '1');
+        press(&mut app, '2');
+        press(&mut app, 'g');
+        press(&mut app, 'g');
+        assert_eq!(app.state.selected(), Some(0));
+        assert!(app.pending_count.is_none());
+    }
+
+    #[test]
+    fn ambiguous_prefix_times_out_with_count() {
+        // "g" — и команда, и префикс "g g"
+        let mut app = app_with(&[("g", "down")], 6);
+        assert_eq!(app.state.selected(), Some(0));
+        press(&mut app, '3');
+        press(&mut app, 'g');
+        assert_eq!(app.state.selected(), Some(0));
+        app.pending_since = Some(Instant::now() - Duration::from_secs(5));
+        app.tick();
+        assert_eq!(app.state.selected(), Some(3));
+        assert!(app.pending_keys.is_empty() && app.pending_count.is_none());
+    }
+}
@@ -0,0 +1,344 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+const HISTORY_LIMIT: usize = 100;
+
+/// Однострочный редактор для всех полей ввода: курсор, слова, kill-команды и история
+#[derive(Default, Clone)]
+pub struct LineEditor {
+    text: String,
+    /// Позиция курсора в байтах, всегда на границе символа
+    cursor: usize,
+    /// Второй конец выделения (байты); ввод заменяет выделенное
+    anchor: Option<usize>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    /// Набранное до листания истории — возвращается при выходе вниз
+    draft: String,
+}
+
+/// Что произошло после нажатия
+#[derive(PartialEq)]
+pub enum EditResult {
+    /// Текст изменился
+    Changed,
+    /// Сдвинулся только курсор
+    Moved,
+    /// Клавиша не для редактора (Enter, Esc, Tab, …)
+    Ignored,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Заменяет текст, курсор — в конец
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.anchor = None;
+        self.history_index = None;
+    }
+
+    /// Выделяет символы `start..end`, курсор — в конце выделения
+    pub fn select_chars(&mut self, start: usize, end: usize) {
+        self.set_cursor_chars(start);
+        let anchor = self.cursor;
+        self.set_cursor_chars(end);
+        self.anchor = (anchor != self.cursor).then_some(anchor);
+    }
+
+    /// Выделение в символах `(start, end)`, если есть
+    pub fn selection_chars(&self) -> Option<(usize, usize)> {
+        let (start, end) = self.selection()?;
+        let start_chars = self.text[..start].chars().count();
+        Some((start_chars, start_chars + self.text[start..end].chars().count()))
+    }
+
+    fn selection(&self) -> Option<(usize, usize)> {
+        self.anchor.map(|a| (a.min(self.cursor), a.max(self.cursor)))
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.text.replace_range(start..end, "");
+            self.cursor = start;
+        }
+        self.anchor = None;
+    }
+
+    /// Курсор в символах от начала строки
+    pub fn cursor_chars(&self) -> usize {
+        self.text[..self.cursor].chars().count()
+    }
+
+    /// Ставит курсор на позицию в символах
+    pub fn set_cursor_chars(&mut self, pos: usize) {
+        self.cursor = self
+            .text
+            .char_indices()
+            .nth(pos)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len());
+    }
+
+    /// Запоминает отправленную строку в истории этого поля
+    pub fn push_history(&mut self) {
+        let entry = self.text.clone();
+        self.history_index = None;
+        if entry.trim().is_empty() {
+            return;
+        }
+        self.history.retain(|h| h != &entry);
+        self.history.push(entry);
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditResult {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        // С выделением: ←/→ схлопывают его к краю, ввод и удаление заменяют его
+        if let Some((start, end)) = self.selection() {
+            match key.code {
+                KeyCode::Left if !ctrl => {
+                    self.cursor = start;
+                    self.anchor = None;
+                    return EditResult::Moved;
+                }
+                KeyCode::Right if !ctrl => {
+                    self.cursor = end;
+                    self.anchor = None;
+                    return EditResult::Moved;
+                }
+                KeyCode::Backspace | KeyCode::Delete if !alt => {
+                    self.delete_selection();
+                    return EditResult::Changed;
+                }
+                KeyCode::Char(_) if !ctrl && !alt => self.delete_selection(),
+                _ => self.anchor = None,
+            }
+        }
+        match key.code {
+            KeyCode::Left if ctrl => self.cursor = self.prev_word(),
+            KeyCode::Right if ctrl => self.cursor = self.next_word(),
+            KeyCode::Char('b') if alt => self.cursor = self.prev_word(),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('p') if ctrl => return self.history_prev(),
+            KeyCode::Char('n') if ctrl => return self.history_next(),
+            KeyCode::Left => self.cursor = self.prev_char(),
+            KeyCode::Right => self.cursor = self.next_char(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Up => return self.history_prev(),
+            KeyCode::Down => return self.history_next(),
+            KeyCode::Char('w') if ctrl => {
+                let start = self.prev_word();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+                return EditResult::Changed;
+            }
+            KeyCode::Backspace if alt => {
+                let start = self.prev_word();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+                return EditResult::Changed;
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.text.replace_range(..self.cursor, "");
+                self.cursor = 0;
+                return EditResult::Changed;
+            }
+            KeyCode::Char('k') if ctrl => {
+                self.text.truncate(self.cursor);
+                return EditResult::Changed;
+            }
+            KeyCode::Char('d') if alt => {
+                let end = self.next_word();
+                self.text.replace_range(self.cursor..end, "");
+                return EditResult::Changed;
+            }
+            KeyCode::Backspace => {
+                if self.cursor == 0 {
+                    return EditResult::Ignored;
+                }
+                let start = self.prev_char();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+                return EditResult::Changed;
+            }
+            KeyCode::Delete => {
+                let end = self.next_char();
+                self.text.replace_range(self.cursor..end, "");
+                return EditResult::Changed;
+            }
+            KeyCode::Char(c) if !ctrl && !alt => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+                return EditResult::Changed;
+            }
+            _ => return EditResult::Ignored,
+        }
+        EditResult::Moved
+    }
+
+    fn prev_char(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    fn next_char(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map(|c| self.cursor + c.len_utf8())
+            .unwrap_or(self.cursor)
+    }
+
+    /// Начало предыдущего слова (слово — буквы/цифры, остальное разделители)
+    fn prev_word(&self) -> usize {
+        let before: Vec<(usize, char)> = self.text[..self.cursor].char_indices().collect();
+        let mut i = before.len();
+        while i > 0 && !before[i - 1].1.is_alphanumeric() {
+            i -= 1;
+        }
+        while i > 0 && before[i - 1].1.is_alphanumeric() {
+            i -= 1;
+        }
+        before.get(i).map(|(pos, _)| *pos).unwrap_or(self.cursor)
+    }
+
+    /// Конец следующего слова
+    fn next_word(&self) -> usize {
+        let mut iter = self.text[self.cursor..].char_indices().peekable();
+        while let Some((_, c)) = iter.peek() {
+            if c.is_alphanumeric() {
+                break;
+            }
+            iter.next();
+        }
+        while let Some((_, c)) = iter.peek() {
+            if !c.is_alphanumeric() {
+                break;
+            }
+            iter.next();
+        }
+        iter.peek().map(|(i, _)| self.cursor + i).unwrap_or(self.text.len())
+    }
+
+    fn history_prev(&mut self) -> EditResult {
+        if self.history.is_empty() {
+            return EditResult::Ignored;
+        }
+        let i = match self.history_index {
+            Some(i) => i.saturating_sub(1),
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(i);
+        self.set(self.history[i].clone());
+        EditResult::Changed
+    }
+
+    fn history_next(&mut self) -> EditResult {
+        match self.history_index {
+            Some(i) if i + 1 < self.history.len() => {
+                self.history_index = Some(i + 1);
+                self.set(self.history[i + 1].clone());
+            }
+            Some(_) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.set(draft);
+            }
+            None => return EditResult::Ignored,
+        }
+        EditResult::Changed
+    }
+
+    /// Видимая часть строки шириной `width` и позиция курсора в ней.
+    /// Окно сдвигается так, чтобы курсор всегда был виден.
+    pub fn view(&self, width: usize) -> (String, usize) {
+        let width = width.max(1);
+        let cursor = self.cursor_chars();
+        // Последняя колонка остаётся под курсор в конце строки
+        let start = (cursor + 1).saturating_sub(width);
+        let visible: String = self.text.chars().skip(start).take(width).collect();
+        (visible, cursor - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(ed: &mut LineEditor, code: KeyCode, modifiers: KeyModifiers) {
+        ed.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn insert_in_the_middle_and_word_kill() {
+        let mut ed = LineEditor::default();
+        ed.set("hello world");
+        press(&mut ed, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut ed, KeyCode::Char('X'), KeyModifiers::NONE);
+        assert_eq!(ed.text(), "Xhello world");
+        press(&mut ed, KeyCode::End, KeyModifiers::NONE);
+        press(&mut ed, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(ed.text(), "Xhello ");
+        press(&mut ed, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut ed, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(ed.text(), "");
+    }
+
+    #[test]
+    fn history_restores_draft() {
+        let mut ed = LineEditor::default();
+        ed.set("first");
+        ed.push_history();
+        ed.set("draft");
+        press(&mut ed, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(ed.text(), "first");
+        press(&mut ed, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(ed.text(), "draft");
+    }
+
+    #[test]
+    fn typing_replaces_selection() {
+        let mut ed = LineEditor::default();
+        ed.set("report.txt");
+        ed.select_chars(0, 6);
+        press(&mut ed, KeyCode::Char('a'), KeyModifiers::NONE);
+        assert_eq!(ed.text(), "a.txt");
+        ed.select_chars(2, 5);
+        press(&mut ed, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!((ed.cursor_chars(), ed.selection_chars()), (2, None));
+    }
+
+    #[test]
+    fn view_scrolls_to_cursor() {
+        let mut ed = LineEditor::default();
+        ed.set("abcdefghij");
+        assert_eq!(ed.view(5), ("ghij".to_string(), 4));
+        ed.set_cursor_chars(0);
+        assert_eq!(ed.view(5), ("abcde".to_string(), 0));
+    }
+}
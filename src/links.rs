@@ -0,0 +1,139 @@
+use crate::app::{App, find_available_name};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LinkKind {
+    /// Символьная ссылка с абсолютным путём
+    Absolute,
+    /// Символьная ссылка с путём относительно каталога ссылки
+    Relative,
+    Hard,
+}
+
+impl LinkKind {
+    pub fn parse(arg: Option<&str>) -> Option<LinkKind> {
+        match arg {
+            None | Some("abs") | Some("absolute") | Some("sym") => Some(LinkKind::Absolute),
+            Some("rel") | Some("relative") => Some(LinkKind::Relative),
+            Some("hard") => Some(LinkKind::Hard),
+            _ => None,
+        }
+    }
+}
+
+/// Путь к `target` относительно каталога `from` (оба абсолютные)
+pub fn relative_path(from: &Path, target: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    for part in &to[common..] {
+        out.push(part);
+    }
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    // Windows различает ссылки на файлы и каталоги; относительную цель проверяем от каталога ссылки
+    let resolved = link.parent().map(|p| p.join(target)).unwrap_or_else(|| target.to_path_buf());
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+impl App {
+    /// Создаёт ссылки на элементы буфера обмена в текущем каталоге
+    pub fn link_clipboard(&mut self, kind: LinkKind) {
+        let Some((paths, _)) = self.clipboard.clone() else {
+            self.message = "Clipboard is empty — copy or cut items to link them".to_string();
+            return;
+        };
+        let mut created = 0;
+        let mut last_error = None;
+        for src in &paths {
+            let Some(name) = src.file_name() else {
+                continue;
+            };
+            let mut dest = self.current_dir.join(name);
+            if fs::symlink_metadata(&dest).is_ok() {
+                dest = find_available_name(&dest);
+            }
+            let res = match kind {
+                LinkKind::Absolute => {
+                    let target = fs::canonicalize(src).unwrap_or_else(|_| src.clone());
+                    symlink(&target, &dest)
+                }
+                LinkKind::Relative => {
+                    let target = fs::canonicalize(src).unwrap_or_else(|_| src.clone());
+                    let base = fs::canonicalize(&self.current_dir).unwrap_or_else(|_| self.current_dir.clone());
+                    symlink(&relative_path(&base, &target), &dest)
+                }
+                LinkKind::Hard if src.is_dir() => {
+                    Err(io::Error::other("hard links to directories are not supported"))
+                }
+                LinkKind::Hard => fs::hard_link(src, &dest),
+            };
+            match res {
+                Ok(_) => created += 1,
+                Err(e) => last_error = Some(format!("{}: {}", name.to_string_lossy(), e)),
+            }
+        }
+        let what = match kind {
+            LinkKind::Absolute => "symlink",
+            LinkKind::Relative => "relative symlink",
+            LinkKind::Hard => "hard link",
+        };
+        self.message = match last_error {
+            Some(e) => format!("Created {} {}(s); error: {}", created, what, e),
+            None => format!("Created {} {}(s)", created, what),
+        };
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    /// Переходит к настоящему расположению выделенной ссылки
+    pub fn follow_link(&mut self) {
+        let Some(path) = self.state.selected().and_then(|i| self.filtered_items.get(i)).cloned() else {
+            return;
+        };
+        let Ok(raw) = fs::read_link(&path) else {
+            self.message = "Not a symbolic link".to_string();
+            return;
+        };
+        match fs::canonicalize(&path) {
+            Ok(real) => {
+                self.reveal_path(&real);
+                self.message = format!("→ {}", real.display());
+            }
+            Err(_) => self.message = format!("Broken link → {}", raw.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/c/d.txt")), PathBuf::from("../c/d.txt"));
+        assert_eq!(relative_path(Path::new("/a"), Path::new("/a/x")), PathBuf::from("x"));
+        assert_eq!(relative_path(Path::new("/a/b/c"), Path::new("/a")), PathBuf::from("../.."));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::app::App;
+use crate::keymap::{key_to_string, parse_key};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+// Запись по умолчанию — `Q<reg>` … `Q`, а не vim-овское `q`: `q` в ffm всегда был выходом,
+// и менять его у всех ради макросов нельзя. `macro_record = "q"` в конфиге вернёт vim-вариант.
+
+/// Защита от макроса, который вызывает сам себя
+const MAX_MACRO_DEPTH: usize = 8;
+
+/// Что делать со следующей нажатой клавишей-регистром
+#[derive(Clone, Copy, PartialEq)]
+pub enum MacroAwait {
+    Record,
+    Play(usize),
+}
+
+impl App {
+    /// `macro record [reg]` / `macro play [reg] [count]`
+    pub fn macro_command(&mut self, args: &[String]) {
+        let reg = args.get(1).and_then(|r| r.chars().next());
+        match args.first().map(|s| s.as_str()) {
+            Some("record") => {
+                if let Some((reg, keys)) = self.macro_recording.take() {
+                    self.message = format!("Recorded @{} ({} keys)", reg, keys.len());
+                    self.macros.insert(reg, keys);
+                    if save_macros(&self.macros).contains(&reg) {
+                        self.message.push_str(": it has keys that cannot be saved, so it lasts until exit");
+                    }
+                } else if let Some(reg) = reg {
+                    self.start_recording(reg);
+                } else {
+                    self.macro_await = Some(MacroAwait::Record);
+                    self.message = "Record macro into register…".to_string();
+                }
+            }
+            Some("play") => {
+                let count = args.get(2).and_then(|c| c.parse().ok()).unwrap_or(1);
+                match reg {
+                    Some(reg) => self.play_macro(reg, count),
+                    None => {
+                        self.macro_await = Some(MacroAwait::Play(count));
+                        self.message = "Play macro from register…".to_string();
+                    }
+                }
+            }
+            _ => self.message = "Usage: macro <record|play> [reg] [count]".to_string(),
+        }
+    }
+
+    /// Следующая клавиша после `Q`/`@` — имя регистра
+    pub fn macro_register_key(&mut self, action: MacroAwait, key: KeyEvent) {
+        let KeyCode::Char(reg) = key.code else {
+            self.message = "Macro cancelled.".to_string();
+            return;
+        };
+        let valid = reg.is_ascii_alphanumeric() || (reg == '@' && matches!(action, MacroAwait::Play(_)));
+        if !valid {
+            self.message = format!("Invalid register: {}", reg);
+            return;
+        }
+        match action {
+            MacroAwait::Record => self.start_recording(reg),
+            MacroAwait::Play(count) => self.play_macro(reg, count),
+        }
+    }
+
+    fn start_recording(&mut self, reg: char) {
+        self.macro_recording = Some((reg, Vec::new()));
+        self.message = format!("Recording @{}", reg);
+    }
+
+    /// Записывает клавишу, пришедшую из терминала (не из воспроизведения)
+    pub fn record_key(&mut self, key: &KeyEvent) {
+        if let Some((_, keys)) = self.macro_recording.as_mut() {
+            keys.push(*key);
+        }
+    }
+
+    /// Прогоняет записанные клавиши через тот же `handle_key`, что и живой ввод
+    pub fn play_macro(&mut self, reg: char, count: usize) {
+        // `@@` — последний воспроизведённый
+        let reg = if reg == '@' { self.last_macro.unwrap_or(reg) } else { reg };
+        let Some(keys) = self.macros.get(&reg).cloned() else {
+            self.message = format!("Register @{} is empty", reg);
+            return;
+        };
+        if self.macro_depth >= MAX_MACRO_DEPTH {
+            self.message = "Macro recursion too deep".to_string();
+            return;
+        }
+        self.macro_depth += 1;
+        for _ in 0..count {
+            for key in &keys {
+                self.handle_key(*key);
+                if self.should_quit {
+                    break;
+                }
+            }
+        }
+        self.macro_depth -= 1;
+        self.last_macro = Some(reg);
+        if self.macro_depth == 0 && !self.should_quit {
+            self.message = format!("Played @{} ×{}", reg, count);
+        }
+    }
+}
+
+fn macros_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "ffm")
+        .map(|p| p.data_dir().join("macros.txt"))
+}
+
+/// Формат: `a<TAB>ctrl-x j j enter` — по строке на регистр
+pub fn load_macros() -> BTreeMap<char, Vec<KeyEvent>> {
+    macros_path().and_then(|path| fs::read_to_string(path).ok()).map(|c| parse_macros(&c)).unwrap_or_default()
+}
+
+fn parse_macros(content: &str) -> BTreeMap<char, Vec<KeyEvent>> {
+    let mut macros = BTreeMap::new();
+    for line in content.lines() {
+        let Some((reg, keys)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(reg) = reg.chars().next() else {
+            continue;
+        };
+        let keys: Vec<KeyEvent> = keys.split(' ').filter_map(parse_key).collect();
+        macros.insert(reg, keys);
+    }
+    macros
+}
+
+/// Клавиша переживает запись в файл: `key_to_string` её знает, и `parse_key` вернёт ту же.
+/// Shift у символов не важен — он уже в самом символе.
+fn storable(key: &KeyEvent) -> bool {
+    let mods = if matches!(key.code, KeyCode::Char(_)) { key.modifiers - KeyModifiers::SHIFT } else { key.modifiers };
+    parse_key(&key_to_string(key)).is_some_and(|k| k.code == key.code && k.modifiers == mods)
+}
+
+/// Текст файла и регистры, которые в него не попали: такой макрос при загрузке
+/// повторял бы не то, что записано
+fn serialize_macros(macros: &BTreeMap<char, Vec<KeyEvent>>) -> (String, Vec<char>) {
+    let mut lines = Vec::new();
+    let mut skipped = Vec::new();
+    for (reg, keys) in macros {
+        if keys.iter().all(storable) {
+            let keys: Vec<String> = keys.iter().map(key_to_string).collect();
+            lines.push(format!("{}\t{}", reg, keys.join(" ")));
+        } else {
+            skipped.push(*reg);
+        }
+    }
+    (lines.join("\n"), skipped)
+}
+
+/// Возвращает регистры, которые не сохранены
+fn save_macros(macros: &BTreeMap<char, Vec<KeyEvent>>) -> Vec<char> {
+    let (content, skipped) = serialize_macros(macros);
+    if let Some(path) = macros_path() {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(&path, content);
+    }
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::vfs::memory::MemFs;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn round_trips_storable_macros() {
+        let keys = vec![
+            key(KeyCode::Char('j'), KeyModifiers::NONE),
+            key(KeyCode::Char('R'), KeyModifiers::SHIFT),
+            key(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            key(KeyCode::Char('-'), KeyModifiers::CONTROL),
+            key(KeyCode::Char(' '), KeyModifiers::NONE),
+            key(KeyCode::Left, KeyModifiers::SHIFT),
+            key(KeyCode::F(5), KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ];
+        let mut macros = BTreeMap::new();
+        macros.insert('a', keys);
+        // Insert и Super в файле не записать — макрос не сохраняется целиком, а не урезается
+        macros.insert('b', vec![key(KeyCode::Char('j'), KeyModifiers::NONE), key(KeyCode::Insert, KeyModifiers::NONE)]);
+        macros.insert('c', vec![key(KeyCode::Char('j'), KeyModifiers::SUPER)]);
+
+        let (content, skipped) = serialize_macros(&macros);
+        assert_eq!(skipped, vec!['b', 'c']);
+        assert_eq!(content, "a\tj R ctrl-x ctrl-- space shift-left f5 enter");
+        let back = parse_macros(&content);
+        assert_eq!(back.len(), 1);
+        let codes: Vec<KeyCode> = back[&'a'].iter().map(|k| k.code).collect();
+        assert_eq!(codes, macros[&'a'].iter().map(|k| k.code).collect::<Vec<_>>());
+        assert_eq!(back[&'a'][2].modifiers, KeyModifiers::CONTROL);
+    }
+
+    #[test]
+    fn replays_with_counts() {
+        let mem = MemFs::new();
+        for i in 0..10 {
+            mem.add_file(&format!("/w/f{}", i), "");
+        }
+        let mut app = App::with_config(Config::default(), PathBuf::from("/w"));
+        app.fs = Box::new(mem);
+        app.refresh_items();
+        let j = key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.macros.insert('a', vec![j, j]);
+        let press = |app: &mut App, c: char| app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE));
+
+        press(&mut app, '@');
+        press(&mut app, 'a');
+        assert_eq!(app.state.selected(), Some(2));
+        press(&mut app, '3');
+        press(&mut app, '@');
+        press(&mut app, 'a');
+        assert_eq!(app.state.selected(), Some(8));
+        // `@@` — последний регистр
+        app.state.select(Some(0));
+        press(&mut app, '@');
+        press(&mut app, '@');
+        assert_eq!(app.state.selected(), Some(2));
+        assert_eq!(app.last_macro, Some('a'));
+        press(&mut app, '@');
+        press(&mut app, 'z');
+        assert_eq!(app.message, "Register @z is empty");
+    }
+}
@@ -1,7 +1,37 @@
 mod app;
+mod bulk_rename;
+mod commands;
+mod checksum;
 mod config;
+mod create;
+mod dir_sizes;
+mod du;
+mod dupes;
+mod archive;
+mod archive_view;
+mod extract;
+mod cleanup;
+mod hashes;
 mod icons;
+mod keymap;
+mod line_edit;
+mod links;
+mod macros;
+mod palette;
+mod pattern_rename;
+#[cfg(unix)]
+mod properties;
+mod rename;
+mod sftp;
+mod share;
+mod system_clipboard;
+mod tags;
 mod theme;
+mod trash;
+mod undo;
+mod vfs;
+#[cfg(unix)]
+mod xattr;
 mod ui;
 
 use crate::app::App;
@@ -0,0 +1,215 @@
+use crate::app::{App, fuzzy_match};
+use crate::commands::{COMMANDS, describe};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+use std::fs;
+use std::path::PathBuf;
+
+const RECENT_LIMIT: usize = 20;
+
+/// Строка палитры: команда, её описание и текущие клавиши
+#[derive(Clone)]
+pub struct PaletteEntry {
+    pub command: String,
+    pub description: String,
+    pub keys: Vec<String>,
+    /// Команде нужны аргументы — открываем `:` с подставленным именем
+    pub needs_args: bool,
+}
+
+pub struct Palette {
+    pub query: String,
+    pub entries: Vec<PaletteEntry>,
+    pub filtered: Vec<usize>,
+    pub state: ListState,
+}
+
+impl Palette {
+    pub fn update_filter(&mut self) {
+        // Совпадения по имени команды выше совпадений по описанию
+        let by_name = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| fuzzy_match(&e.command, &self.query))
+            .map(|(i, _)| i);
+        let by_description = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !fuzzy_match(&e.command, &self.query) && fuzzy_match(&e.description, &self.query))
+            .map(|(i, _)| i);
+        self.filtered = by_name.chain(by_description).collect();
+        self.state.select(if self.filtered.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn selected(&self) -> Option<&PaletteEntry> {
+        self.state
+            .selected()
+            .and_then(|i| self.filtered.get(i))
+            .and_then(|&i| self.entries.get(i))
+    }
+}
+
+impl App {
+    /// Собирает список действий из реестра команд и живой раскладки
+    pub fn palette_entries(&self) -> Vec<PaletteEntry> {
+        let mut entries: Vec<PaletteEntry> = Vec::new();
+        let keys_of = |command: &str| self.keymap.keys_for(command);
+
+        for spec in COMMANDS {
+            let needs_args = spec.usage.contains('<');
+            entries.push(PaletteEntry {
+                command: spec.name.to_string(),
+                description: spec.description.to_string(),
+                keys: keys_of(spec.name),
+                needs_args,
+            });
+        }
+
+        // Привязки с аргументами ("focus files", "cd ~/Downloads", ...) — отдельные действия
+        for binding in &self.keymap.bindings {
+            if entries.iter().any(|e| e.command == binding.command) {
+                continue;
+            }
+            entries.push(PaletteEntry {
+                command: binding.command.clone(),
+                description: describe(&binding.command),
+                keys: keys_of(&binding.command),
+                needs_args: false,
+            });
+        }
+
+        // Недавние — наверх, самые свежие первыми
+        for recent in self.palette_recent.iter().rev() {
+            if let Some(pos) = entries.iter().position(|e| &e.command == recent) {
+                let entry = entries.remove(pos);
+                entries.insert(0, entry);
+            }
+        }
+        entries
+    }
+
+    pub fn open_palette(&mut self) {
+        let mut palette = Palette {
+            query: String::new(),
+            entries: self.palette_entries(),
+            filtered: Vec::new(),
+            state: ListState::default(),
+        };
+        palette.update_filter();
+        self.palette = Some(palette);
+    }
+
+    pub fn handle_palette_input(&mut self, key: KeyEvent) {
+        let Some(palette) = self.palette.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Enter => {
+                let Some(entry) = palette.selected().cloned() else {
+                    return;
+                };
+                self.palette = None;
+                self.palette_recent.retain(|c| c != &entry.command);
+                self.palette_recent.insert(0, entry.command.clone());
+                self.palette_recent.truncate(RECENT_LIMIT);
+                save_palette_recent(&self.palette_recent);
+                if entry.needs_args {
+                    self.start_command();
+                    self.command_input.set(format!("{} ", entry.command));
+                } else {
+                    self.execute_command(&entry.command);
+                }
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                let len = palette.filtered.len();
+                if len > 0 {
+                    let i = palette.state.selected().map_or(0, |i| (i + 1) % len);
+                    palette.state.select(Some(i));
+                }
+            }
+            KeyCode::Up | KeyCode::BackTab => {
+                let len = palette.filtered.len();
+                if len > 0 {
+                    let i = palette.state.selected().map_or(0, |i| if i == 0 { len - 1 } else { i - 1 });
+                    palette.state.select(Some(i));
+                }
+            }
+            KeyCode::Backspace => {
+                palette.query.pop();
+                palette.update_filter();
+            }
+            KeyCode::Char(c) => {
+                palette.query.push(c);
+                palette.update_filter();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn palette_recent_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "ffm")
+        .map(|p| p.data_dir().join("palette_recent.txt"))
+}
+
+pub fn load_palette_recent() -> Vec<String> {
+    if let Some(path) = palette_recent_path()
+        && let Ok(content) = fs::read_to_string(&path) {
+            return content
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    Vec::new()
+}
+
+fn save_palette_recent(recent: &[String]) {
+    if let Some(path) = palette_recent_path() {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(&path, recent.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn palette(app: &App, query: &str) -> Palette {
+        let mut palette = Palette { query: query.to_string(), entries: app.palette_entries(), filtered: Vec::new(), state: ListState::default() };
+        palette.update_filter();
+        palette
+    }
+
+    #[test]
+    fn ranks_recent_then_names_then_descriptions() {
+        let mut app = App::with_config(Config::default(), PathBuf::from("/"));
+        let entries = app.palette_entries();
+        let delete = entries.iter().find(|e| e.command == "delete").unwrap();
+        assert_eq!(delete.keys, vec!["D"]);
+        assert!(entries.iter().any(|e| e.command == "cd ~" && !e.needs_args));
+        assert!(entries.iter().find(|e| e.command == "cd").unwrap().needs_args);
+
+        // Недавние — первыми, самое свежее выше
+        app.palette_recent = vec!["du".to_string(), "sort".to_string()];
+        let entries = app.palette_entries();
+        assert_eq!([entries[0].command.as_str(), entries[1].command.as_str()], ["du", "sort"]);
+
+        // Совпадения по имени выше тех, где слово есть только в описании («Undo the last rename batch»)
+        let p = palette(&app, "rename");
+        let names: Vec<&str> = p.filtered.iter().map(|&i| p.entries[i].command.as_str()).collect();
+        assert_eq!(names[..3], ["rename", "bulkrename", "patternrename"]);
+        assert!(names[3..].contains(&"undo"));
+        assert!(names[3..].iter().all(|n| !fuzzy_match(n, "rename")));
+        assert_eq!(p.selected().map(|e| e.command.as_str()), Some(names[0]));
+
+        let p = palette(&app, "qqqqqq");
+        assert!(p.filtered.is_empty() && p.selected().is_none());
+    }
+}
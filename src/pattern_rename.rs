@@ -0,0 +1,476 @@
+use crate::app::App;
+use crate::line_edit::LineEditor;
+use crate::rename::{apply_renames, check_renames, extension_start, name_error, rename_conflicts};
+use crate::ui::date_parts;
+use crate::undo::UndoEntry;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use regex::Regex;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Поля окна переименования по шаблону (Tab переключает)
+#[derive(Clone, Copy, PartialEq)]
+pub enum PatternField {
+    Find,
+    Replace,
+    Extension,
+    Case,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CaseTransform {
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+impl CaseTransform {
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseTransform::Keep => "keep",
+            CaseTransform::Lower => "lower",
+            CaseTransform::Upper => "UPPER",
+            CaseTransform::Title => "Title",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            CaseTransform::Keep => CaseTransform::Lower,
+            CaseTransform::Lower => CaseTransform::Upper,
+            CaseTransform::Upper => CaseTransform::Title,
+            CaseTransform::Title => CaseTransform::Keep,
+        }
+    }
+
+    fn apply(self, s: &str) -> String {
+        match self {
+            CaseTransform::Keep => s.to_string(),
+            CaseTransform::Lower => s.to_lowercase(),
+            CaseTransform::Upper => s.to_uppercase(),
+            CaseTransform::Title => {
+                let mut out = String::with_capacity(s.len());
+                let mut start = true;
+                for c in s.chars() {
+                    if start {
+                        out.extend(c.to_uppercase());
+                    } else {
+                        out.extend(c.to_lowercase());
+                    }
+                    start = !c.is_alphanumeric();
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Строка превью: старое и новое имя, ошибка (конфликт/недопустимое имя)
+pub struct PreviewRow {
+    pub old: String,
+    pub new: String,
+    pub error: Option<String>,
+}
+
+/// Переименование выбранных элементов по шаблону с живым превью
+pub struct PatternRename {
+    pub sources: Vec<PathBuf>,
+    pub find: LineEditor,
+    pub replace: LineEditor,
+    pub extension: LineEditor,
+    pub case: CaseTransform,
+    pub field: PatternField,
+    pub preview: Vec<PreviewRow>,
+    /// Ошибка в регулярном выражении или шаблоне
+    pub pattern_error: Option<String>,
+    pub scroll: u16,
+    /// Для каждого источника: каталог ли и время изменения
+    meta: Vec<(bool, Option<SystemTime>)>,
+}
+
+impl PatternRename {
+    fn active_input_mut(&mut self) -> Option<&mut LineEditor> {
+        match self.field {
+            PatternField::Find => Some(&mut self.find),
+            PatternField::Replace => Some(&mut self.replace),
+            PatternField::Extension => Some(&mut self.extension),
+            PatternField::Case => None,
+        }
+    }
+
+    pub fn changes(&self) -> usize {
+        self.preview.iter().filter(|r| r.old != r.new).count()
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.preview.iter().filter(|r| r.error.is_some()).count()
+    }
+
+    /// Пересчитывает новые имена и конфликты после каждого изменения
+    pub fn update_preview(&mut self) {
+        self.pattern_error = None;
+        let regex = if self.find.is_empty() {
+            None
+        } else {
+            match Regex::new(self.find.text()) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    self.pattern_error = Some(regex_error_summary(&e));
+                    None
+                }
+            }
+        };
+
+        let mut names: Vec<String> = Vec::with_capacity(self.sources.len());
+        // Счётчик {n} идёт только по переименовываемым элементам
+        let mut index = 0;
+        for (src, &(is_dir, modified)) in self.sources.iter().zip(&self.meta) {
+            let old = src.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if self.pattern_error.is_some() {
+                names.push(old);
+                continue;
+            }
+            let vars = TokenVars { index, modified, name: &old, is_dir };
+            match new_name(&old, regex.as_ref(), self.replace.text(), self.extension.text(), self.case, &vars) {
+                Ok(Some(name)) => {
+                    index += 1;
+                    names.push(name);
+                }
+                Ok(None) => names.push(old),
+                Err(e) => {
+                    self.pattern_error = Some(e);
+                    names.push(old);
+                }
+            }
+        }
+
+        // Недопустимые имена не превращаем в пути — иначе "" стало бы каталогом
+        let pairs: Vec<(PathBuf, PathBuf)> = self
+            .sources
+            .iter()
+            .zip(&names)
+            .map(|(src, name)| {
+                let dst = if name_error(name).is_some() { src.clone() } else { src.with_file_name(name) };
+                (src.clone(), dst)
+            })
+            .collect();
+        let conflicts = rename_conflicts(&pairs);
+
+        self.preview = self
+            .sources
+            .iter()
+            .zip(names)
+            .zip(conflicts)
+            .map(|((src, new), conflict)| PreviewRow {
+                old: src.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                error: name_error(&new).or(conflict),
+                new,
+            })
+            .collect();
+    }
+
+    /// Изменённые пары для применения
+    fn plan(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.sources
+            .iter()
+            .zip(&self.preview)
+            .map(|(src, row)| (src.clone(), src.with_file_name(&row.new)))
+            .collect()
+    }
+}
+
+/// Суть ошибки regex — полное сообщение многострочное, с указателем на позицию
+fn regex_error_summary(e: &regex::Error) -> String {
+    let text = e.to_string();
+    let last = text.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("invalid regex");
+    last.trim_start_matches("error: ").to_string()
+}
+
+/// Значения для токенов `{n}`, `{date}`, `{name}` … одного файла
+struct TokenVars<'a> {
+    index: usize,
+    modified: Option<SystemTime>,
+    name: &'a str,
+    /// У каталогов нет расширения: `{ext}` пуст, поле Extension не применяется
+    is_dir: bool,
+}
+
+/// Раскрывает токены шаблона:
+/// `{n}` `{n:03}` `{n:03:10}` — счётчик (ширина, старт), `{date}` `{date:%Y%m%d}` `{time}`,
+/// `{name}` `{stem}` `{ext}`; `{{` и `}}` — литеральные скобки.
+fn expand_tokens(template: &str, vars: &TokenVars, escape_dollar: bool) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            // `${1}` / `${name}` — группы regex, их раскроет сам regex
+            '{' if out.ends_with('$') && escape_dollar => {
+                out.push('{');
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            '{' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => token.push(c),
+                        None => return Err(format!("unclosed '{{{}'", token)),
+                    }
+                }
+                let value = token_value(&token, vars)?;
+                // В шаблоне для regex `$` в значениях не должен стать ссылкой на группу
+                out.push_str(&if escape_dollar { value.replace('$', "$$") } else { value });
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn token_value(token: &str, vars: &TokenVars) -> Result<String, String> {
+    let (name, args) = token.split_once(':').unwrap_or((token, ""));
+    let ext_pos = if vars.is_dir { None } else { extension_start(vars.name) };
+    let stem: String = match ext_pos {
+        Some(i) => vars.name.chars().take(i).collect(),
+        None => vars.name.to_string(),
+    };
+    match name {
+        "n" => {
+            let (width, start) = args.split_once(':').unwrap_or((args, "1"));
+            let start: usize = start.parse().map_err(|_| format!("bad counter start in {{{}}}", token))?;
+            let n = start + vars.index;
+            if width.is_empty() {
+                return Ok(n.to_string());
+            }
+            let w: usize = width.parse().map_err(|_| format!("bad counter width in {{{}}}", token))?;
+            Ok(if width.starts_with('0') { format!("{:0w$}", n, w = w) } else { format!("{:w$}", n, w = w) })
+        }
+        "date" | "time" => {
+            let Some(modified) = vars.modified else {
+                return Ok(String::new());
+            };
+            let default = if name == "date" { "%Y-%m-%d" } else { "%H-%M-%S" };
+            Ok(format_time(modified, if args.is_empty() { default } else { args }))
+        }
+        "name" => Ok(vars.name.to_string()),
+        "stem" => Ok(stem),
+        "ext" => Ok(ext_pos.map(|i| vars.name.chars().skip(i + 1).collect()).unwrap_or_default()),
+        _ => Err(format!("unknown token {{{}}}", token)),
+    }
+}
+
+/// Подмножество strftime: %Y %y %m %d %H %M %S %%
+fn format_time(time: SystemTime, format: &str) -> String {
+    let (y, mo, d, h, mi, s) = date_parts(time);
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", y)),
+            Some('y') => out.push_str(&format!("{:02}", y % 100)),
+            Some('m') => out.push_str(&format!("{:02}", mo)),
+            Some('d') => out.push_str(&format!("{:02}", d)),
+            Some('H') => out.push_str(&format!("{:02}", h)),
+            Some('M') => out.push_str(&format!("{:02}", mi)),
+            Some('S') => out.push_str(&format!("{:02}", s)),
+            Some(other) => out.push(other),
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Новое имя: regex-замена в основе имени, токены, регистр, расширение.
+/// `None` — regex не совпал, элемент не переименовывается.
+fn new_name(
+    old: &str,
+    regex: Option<&Regex>,
+    replace: &str,
+    extension: &str,
+    case: CaseTransform,
+    vars: &TokenVars,
+) -> Result<Option<String>, String> {
+    let ext_pos = if vars.is_dir { None } else { extension_start(old) };
+    let (stem, ext): (String, Option<String>) = match ext_pos {
+        Some(i) => (old.chars().take(i).collect(), Some(old.chars().skip(i + 1).collect())),
+        None => (old.to_string(), None),
+    };
+
+    let stem = match regex {
+        Some(re) if !re.is_match(&stem) => return Ok(None),
+        Some(re) => {
+            let template = expand_tokens(replace, vars, true)?;
+            re.replace_all(&stem, template.as_str()).to_string()
+        }
+        // Без поиска шаблон замены задаёт основу целиком ("photo_{n:03}")
+        None if !replace.is_empty() => expand_tokens(replace, vars, false)?,
+        None => stem,
+    };
+
+    // "-" убирает расширение, пустое поле оставляет как было
+    let ext = match extension.trim().trim_start_matches('.') {
+        _ if vars.is_dir => None,
+        "" => ext,
+        "-" => None,
+        new_ext => Some(new_ext.to_string()),
+    };
+
+    let name = match ext {
+        Some(ext) => format!("{}.{}", stem, ext),
+        None => stem,
+    };
+    Ok(Some(case.apply(&name)))
+}
+
+impl App {
+    pub fn start_pattern_rename(&mut self) {
+        let sources = self.bulk_targets();
+        if sources.is_empty() {
+            self.message = "Nothing to rename".to_string();
+            return;
+        }
+        let meta = sources
+            .iter()
+            .map(|p| match self.meta_cache.get(p) {
+                Some(m) => (m.is_dir, m.modified),
+                None => (p.is_dir(), std::fs::metadata(p).and_then(|m| m.modified()).ok()),
+            })
+            .collect();
+        let mut pattern = PatternRename {
+            sources,
+            find: LineEditor::default(),
+            replace: LineEditor::default(),
+            extension: LineEditor::default(),
+            case: CaseTransform::Keep,
+            field: PatternField::Find,
+            preview: Vec::new(),
+            pattern_error: None,
+            scroll: 0,
+            meta,
+        };
+        pattern.update_preview();
+        self.pattern_rename = Some(pattern);
+    }
+
+    fn apply_pattern_rename(&mut self) {
+        let Some(pattern) = self.pattern_rename.as_ref() else {
+            return;
+        };
+        if let Some(err) = &pattern.pattern_error {
+            self.message = format!("Pattern error: {}", err);
+            return;
+        }
+        if pattern.conflicts() > 0 {
+            self.message = format!("{} conflict(s) — fix the pattern first", pattern.conflicts());
+            return;
+        }
+        if pattern.changes() == 0 {
+            self.message = "No names changed".to_string();
+            return;
+        }
+        let plan = pattern.plan();
+        self.pattern_rename = None;
+
+        match check_renames(&plan).and_then(|_| apply_renames(&plan)) {
+            Ok(n) => {
+                let changed = plan.into_iter().filter(|(src, dst)| src != dst).collect::<Vec<_>>();
+                self.tags_moved(&changed);
+                self.push_undo(UndoEntry::Rename(changed));
+                self.selected_indices.clear();
+                self.message = format!("Renamed {} item(s)", n);
+            }
+            Err(e) => self.message = format!("Pattern rename failed, nothing changed: {}", e),
+        }
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    pub fn handle_pattern_rename_input(&mut self, key: KeyEvent) {
+        let Some(pattern) = self.pattern_rename.as_mut() else {
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                self.pattern_rename = None;
+                self.message = "Pattern rename cancelled".to_string();
+            }
+            KeyCode::Enter => self.apply_pattern_rename(),
+            KeyCode::Tab | KeyCode::BackTab => {
+                let order = [PatternField::Find, PatternField::Replace, PatternField::Extension, PatternField::Case];
+                let pos = order.iter().position(|f| *f == pattern.field).unwrap_or(0);
+                let step = if key.code == KeyCode::Tab { 1 } else { order.len() - 1 };
+                pattern.field = order[(pos + step) % order.len()];
+            }
+            KeyCode::Char('t') if ctrl => {
+                pattern.case = pattern.case.next();
+                pattern.update_preview();
+            }
+            KeyCode::PageDown => pattern.scroll = pattern.scroll.saturating_add(10),
+            KeyCode::PageUp => pattern.scroll = pattern.scroll.saturating_sub(10),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if pattern.field == PatternField::Case => {
+                pattern.case = pattern.case.next();
+                pattern.update_preview();
+            }
+            _ => {
+                if let Some(input) = pattern.active_input_mut() {
+                    input.handle_key(key);
+                    pattern.update_preview();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn rename(old: &str, find: &str, replace: &str, ext: &str, case: CaseTransform, index: usize) -> String {
+        let re = (!find.is_empty()).then(|| Regex::new(find).unwrap());
+        // 2024-03-05 14:07:09 UTC
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_647_629);
+        let vars = TokenVars { index, modified: Some(modified), name: old, is_dir: false };
+        new_name(old, re.as_ref(), replace, ext, case, &vars).unwrap().unwrap_or_else(|| old.to_string())
+    }
+
+    #[test]
+    fn regex_groups_counters_and_dates() {
+        assert_eq!(rename("IMG_1234.JPG", r"IMG_(\d+)", "photo_$1", "", CaseTransform::Keep, 0), "photo_1234.JPG");
+        assert_eq!(rename("IMG_1234.JPG", r"IMG_(?<id>\d+)", "${id}_{n}", "jpg", CaseTransform::Keep, 0), "1234_1.jpg");
+        assert_eq!(rename("export (3).png", "", "photo_{n:03}", "", CaseTransform::Keep, 4), "photo_005.png");
+        assert_eq!(rename("a.png", "", "{n:2:0}_{stem}", "", CaseTransform::Keep, 0), " 0_a.png");
+        assert_eq!(rename("scan.tiff", "", "{date}_{stem}", "", CaseTransform::Keep, 0), "2024-03-05_scan.tiff");
+        assert_eq!(rename("scan.tiff", "^", "{date:%Y%m%d}-", "", CaseTransform::Keep, 0), "20240305-scan.tiff");
+        assert_eq!(rename("My Report.TXT", " ", "_", "md", CaseTransform::Lower, 0), "my_report.md");
+        assert_eq!(rename("notes.txt", "", "", "-", CaseTransform::Title, 0), "Notes");
+        assert_eq!(rename("notes.txt", "^IMG", "x", "md", CaseTransform::Keep, 0), "notes.txt");
+    }
+
+    #[test]
+    fn bad_tokens_are_errors() {
+        let vars = TokenVars { index: 0, modified: None, name: "a.txt", is_dir: false };
+        assert!(expand_tokens("{n", &vars, false).is_err());
+        assert!(expand_tokens("{nope}", &vars, false).is_err());
+        assert_eq!(expand_tokens("{{n}}", &vars, false).unwrap(), "{n}");
+    }
+}
@@ -0,0 +1,538 @@
+use crate::app::App;
+use crate::line_edit::LineEditor;
+use crate::ui::date_parts;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Поля окна свойств в порядке Tab
+#[derive(Clone, Copy, PartialEq)]
+pub enum PropField {
+    Mode,
+    Owner,
+    Group,
+    Accessed,
+    Modified,
+    Recursive,
+    ApplyTo,
+}
+
+const FIELDS: &[PropField] = &[
+    PropField::Mode,
+    PropField::Owner,
+    PropField::Group,
+    PropField::Accessed,
+    PropField::Modified,
+    PropField::Recursive,
+    PropField::ApplyTo,
+];
+
+/// К чему применять изменения при рекурсивном обходе
+#[derive(Clone, Copy, PartialEq)]
+pub enum ApplyTo {
+    All,
+    Files,
+    Dirs,
+}
+
+impl ApplyTo {
+    pub fn label(self) -> &'static str {
+        match self {
+            ApplyTo::All => "files and folders",
+            ApplyTo::Files => "files only",
+            ApplyTo::Dirs => "folders only",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            ApplyTo::All => ApplyTo::Files,
+            ApplyTo::Files => ApplyTo::Dirs,
+            ApplyTo::Dirs => ApplyTo::All,
+        }
+    }
+
+    fn matches(self, is_dir: bool) -> bool {
+        match self {
+            ApplyTo::All => true,
+            ApplyTo::Files => !is_dir,
+            ApplyTo::Dirs => is_dir,
+        }
+    }
+}
+
+/// Окно свойств: сведения о выбранном и редактируемые поля
+pub struct Properties {
+    pub targets: Vec<PathBuf>,
+    /// Строки «подпись — значение» для верхней части окна
+    pub info: Vec<(String, String)>,
+    pub mode: LineEditor,
+    pub owner: LineEditor,
+    pub group: LineEditor,
+    pub accessed: LineEditor,
+    pub modified: LineEditor,
+    pub recursive: bool,
+    pub apply_to: ApplyTo,
+    pub field: PropField,
+    /// Ошибка проверки изменённых полей — показывается до Enter
+    pub error: Option<String>,
+    /// Исходные значения полей: применяется только изменённое
+    initial: [String; 5],
+}
+
+impl Properties {
+    pub fn input(&self, field: PropField) -> Option<&LineEditor> {
+        match field {
+            PropField::Mode => Some(&self.mode),
+            PropField::Owner => Some(&self.owner),
+            PropField::Group => Some(&self.group),
+            PropField::Accessed => Some(&self.accessed),
+            PropField::Modified => Some(&self.modified),
+            _ => None,
+        }
+    }
+
+    fn input_mut(&mut self, field: PropField) -> Option<&mut LineEditor> {
+        match field {
+            PropField::Mode => Some(&mut self.mode),
+            PropField::Owner => Some(&mut self.owner),
+            PropField::Group => Some(&mut self.group),
+            PropField::Accessed => Some(&mut self.accessed),
+            PropField::Modified => Some(&mut self.modified),
+            _ => None,
+        }
+    }
+
+    fn changed(&self, field: PropField) -> Option<&str> {
+        let idx = FIELDS.iter().position(|f| *f == field)?;
+        let text = self.input(field)?.text().trim();
+        (text != self.initial.get(idx)?.as_str()).then_some(text)
+    }
+
+    /// Разбирает изменённые поля; ошибка — первая найденная
+    fn changes(&self) -> Result<Changes, String> {
+        let mode = match self.changed(PropField::Mode) {
+            Some(text) => Some(parse_mode(text)?),
+            None => None,
+        };
+        let uid = match self.changed(PropField::Owner) {
+            Some(text) => Some(lookup_id("/etc/passwd", text).ok_or(format!("Unknown user '{}'", text))?),
+            None => None,
+        };
+        let gid = match self.changed(PropField::Group) {
+            Some(text) => Some(lookup_id("/etc/group", text).ok_or(format!("Unknown group '{}'", text))?),
+            None => None,
+        };
+        let accessed = match self.changed(PropField::Accessed) {
+            Some(text) => Some(parse_datetime(text).ok_or(format!("Bad time '{}' (YYYY-MM-DD HH:MM:SS or now)", text))?),
+            None => None,
+        };
+        let modified = match self.changed(PropField::Modified) {
+            Some(text) => Some(parse_datetime(text).ok_or(format!("Bad time '{}' (YYYY-MM-DD HH:MM:SS or now)", text))?),
+            None => None,
+        };
+        Ok(Changes { mode, uid, gid, accessed, modified })
+    }
+
+    fn validate(&mut self) {
+        self.error = self.changes().err();
+    }
+}
+
+struct Changes {
+    mode: Option<ModeChange>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.mode.is_none() && self.uid.is_none() && self.gid.is_none() && self.accessed.is_none() && self.modified.is_none()
+    }
+}
+
+/// Изменение прав: точное восьмеричное или символьное как у chmod (`u+x,go-w`)
+#[derive(Debug, PartialEq)]
+pub enum ModeChange {
+    Octal(u32),
+    Symbolic(Vec<SymbolicClause>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SymbolicClause {
+    /// Маска затронутых классов (u/g/o), уже в битах rwx каждого класса
+    who: u32,
+    op: char,
+    perms: String,
+}
+
+pub fn parse_mode(text: &str) -> Result<ModeChange, String> {
+    if !text.is_empty() && text.chars().all(|c| c.is_digit(8)) {
+        let mode = u32::from_str_radix(text, 8).map_err(|e| e.to_string())?;
+        if mode > 0o7777 {
+            return Err(format!("Mode {} is out of range", text));
+        }
+        return Ok(ModeChange::Octal(mode));
+    }
+    let mut clauses = Vec::new();
+    for part in text.split(',') {
+        let op_pos = part
+            .find(['+', '-', '='])
+            .ok_or(format!("Bad mode '{}' (use 755 or u+x,go-w)", part))?;
+        let (who_str, rest) = part.split_at(op_pos);
+        let mut who = 0;
+        for c in who_str.chars() {
+            who |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return Err(format!("Bad class '{}' in '{}'", c, part)),
+            };
+        }
+        if who == 0 {
+            who = 0o7777;
+        }
+        let op = rest.chars().next().unwrap_or('+');
+        let perms = &rest[1..];
+        if let Some(c) = perms.chars().find(|c| !"rwxXst".contains(*c)) {
+            return Err(format!("Bad permission '{}' in '{}'", c, part));
+        }
+        clauses.push(SymbolicClause { who, op, perms: perms.to_string() });
+    }
+    Ok(ModeChange::Symbolic(clauses))
+}
+
+/// Новые права для файла с текущими `mode`
+pub fn apply_mode(change: &ModeChange, mode: u32, is_dir: bool) -> u32 {
+    match change {
+        ModeChange::Octal(m) => *m,
+        ModeChange::Symbolic(clauses) => {
+            let mut mode = mode & 0o7777;
+            for clause in clauses {
+                let mut bits = 0;
+                for c in clause.perms.chars() {
+                    bits |= match c {
+                        'r' => 0o444,
+                        'w' => 0o222,
+                        'x' => 0o111,
+                        // X — исполнение только каталогам и уже исполняемым файлам
+                        'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                        's' => 0o6000,
+                        't' => 0o1000,
+                        _ => 0,
+                    };
+                }
+                let bits = bits & clause.who;
+                mode = match clause.op {
+                    '+' => mode | bits,
+                    '-' => mode & !bits,
+                    _ => (mode & !(clause.who & 0o7777)) | bits,
+                };
+            }
+            mode
+        }
+    }
+}
+
+/// `rwxr-xr-x` с учётом setuid/setgid/sticky
+pub fn mode_string(mode: u32) -> String {
+    let mut out = String::with_capacity(9);
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 2 != 0 { 'w' } else { '-' });
+        let exec = bits & 1 != 0;
+        out.push(match (mode & special != 0, exec) {
+            (true, true) => special_char,
+            (true, false) => special_char.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Имя пользователя/группы по id из /etc/passwd или /etc/group
+pub fn name_for_id(db: &str, id: u32) -> Option<String> {
+    let content = fs::read_to_string(db).ok()?;
+    content.lines().find_map(|line| {
+        let mut parts = line.split(':');
+        let name = parts.next()?;
+        let id_field = parts.nth(1)?;
+        (id_field.parse::<u32>().ok()? == id).then(|| name.to_string())
+    })
+}
+
+/// Id по имени или числу
+fn lookup_id(db: &str, name: &str) -> Option<u32> {
+    if let Ok(id) = name.parse::<u32>() {
+        return Some(id);
+    }
+    let content = fs::read_to_string(db).ok()?;
+    content.lines().find_map(|line| {
+        let mut parts = line.split(':');
+        (parts.next()? == name).then_some(())?;
+        parts.nth(1)?.parse().ok()
+    })
+}
+
+pub fn format_datetime(time: SystemTime) -> String {
+    let (y, mo, d, h, mi, s) = date_parts(time);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, mo, d, h, mi, s)
+}
+
+/// Обратное к `format_datetime` (UTC): `YYYY-MM-DD[ HH:MM[:SS]]` или `now`
+pub fn parse_datetime(text: &str) -> Option<SystemTime> {
+    if text.eq_ignore_ascii_case("now") {
+        return Some(SystemTime::now());
+    }
+    let (date, time) = text.split_once([' ', 'T']).unwrap_or((text, "00:00:00"));
+    let mut d = date.split('-').map(|p| p.parse::<i64>());
+    let (y, m, day) = (d.next()?.ok()?, d.next()?.ok()?, d.next()?.ok()?);
+    let mut t = time.split(':').map(|p| p.parse::<u64>());
+    let (h, mi, s) = (t.next()?.ok()?, t.next().unwrap_or(Ok(0)).ok()?, t.next().unwrap_or(Ok(0)).ok()?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&day) || h > 23 || mi > 59 || s > 60 {
+        return None;
+    }
+    // Дни от 1970-01-01 (алгоритм Хиннанта days_from_civil)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    if days < 0 {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs(days as u64 * 86400 + h * 3600 + mi * 60 + s))
+}
+
+fn time_from(secs: i64, nanos: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::new(secs as u64, nanos as u32)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+/// Рекурсивный список путей без перехода по ссылкам
+fn walk(path: &Path, out: &mut Vec<PathBuf>) {
+    out.push(path.to_path_buf());
+    let is_real_dir = fs::symlink_metadata(path).is_ok_and(|m| m.is_dir());
+    if is_real_dir && let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            walk(&entry.path(), out);
+        }
+    }
+}
+
+impl App {
+    pub fn open_properties(&mut self) {
+        let mut indices: Vec<usize> = self.selected_indices.iter().copied().collect();
+        indices.sort_unstable();
+        let targets: Vec<PathBuf> = if indices.is_empty() {
+            self.state.selected().and_then(|i| self.filtered_items.get(i)).cloned().into_iter().collect()
+        } else {
+            indices.iter().filter_map(|&i| self.filtered_items.get(i).cloned()).collect()
+        };
+        let Some(first) = targets.first() else {
+            return;
+        };
+        let meta = match fs::symlink_metadata(first) {
+            Ok(m) => m,
+            Err(e) => {
+                self.message = format!("Properties: {}", e);
+                return;
+            }
+        };
+
+        let mode = meta.mode() & 0o7777;
+        let owner = name_for_id("/etc/passwd", meta.uid()).unwrap_or_else(|| meta.uid().to_string());
+        let group = name_for_id("/etc/group", meta.gid()).unwrap_or_else(|| meta.gid().to_string());
+        let kind = if meta.file_type().is_symlink() {
+            "symlink"
+        } else if meta.is_dir() {
+            "directory"
+        } else {
+            "file"
+        };
+        let time_or_dash = |t: Option<SystemTime>| t.map(format_datetime).unwrap_or_else(|| "—".to_string());
+
+        let mut info = Vec::new();
+        if targets.len() > 1 {
+            info.push(("Items".to_string(), format!("{} selected (values below are from the first)", targets.len())));
+        }
+        info.extend([
+            ("Path".to_string(), first.display().to_string()),
+            ("Type".to_string(), kind.to_string()),
+            ("Size".to_string(), format!("{} bytes", meta.len())),
+            ("Mode".to_string(), format!("{}  ({:04o})", mode_string(mode), mode)),
+            ("Owner".to_string(), format!("{} ({})", owner, meta.uid())),
+            ("Group".to_string(), format!("{} ({})", group, meta.gid())),
+            ("Inode".to_string(), format!("{} on device {}", meta.ino(), meta.dev())),
+            ("Links".to_string(), meta.nlink().to_string()),
+            ("Created".to_string(), time_or_dash(meta.created().ok())),
+            ("Accessed".to_string(), format_datetime(time_from(meta.atime(), meta.atime_nsec()))),
+            ("Modified".to_string(), format_datetime(time_from(meta.mtime(), meta.mtime_nsec()))),
+            ("Changed".to_string(), format_datetime(time_from(meta.ctime(), meta.ctime_nsec()))),
+        ]);
+
+        let initial = [
+            format!("{:o}", mode),
+            owner,
+            group,
+            format_datetime(time_from(meta.atime(), meta.atime_nsec())),
+            format_datetime(time_from(meta.mtime(), meta.mtime_nsec())),
+        ];
+        let editor = |text: &str| {
+            let mut ed = LineEditor::default();
+            ed.set(text);
+            ed
+        };
+        self.properties = Some(Properties {
+            targets,
+            info,
+            mode: editor(&initial[0]),
+            owner: editor(&initial[1]),
+            group: editor(&initial[2]),
+            accessed: editor(&initial[3]),
+            modified: editor(&initial[4]),
+            recursive: false,
+            apply_to: ApplyTo::All,
+            field: PropField::Mode,
+            error: None,
+            initial,
+        });
+    }
+
+    fn apply_properties(&mut self) {
+        let Some(props) = self.properties.as_ref() else {
+            return;
+        };
+        let changes = match props.changes() {
+            Ok(c) if c.is_empty() => {
+                self.message = "Properties: nothing changed".to_string();
+                self.properties = None;
+                return;
+            }
+            Ok(c) => c,
+            Err(e) => {
+                self.message = format!("Properties: {}", e);
+                return;
+            }
+        };
+
+        let mut paths = Vec::new();
+        for target in &props.targets {
+            if props.recursive {
+                walk(target, &mut paths);
+            } else {
+                paths.push(target.clone());
+            }
+        }
+        let apply_to = props.apply_to;
+
+        let mut changed = 0;
+        let mut errors: Vec<String> = Vec::new();
+        for path in &paths {
+            let Ok(meta) = fs::symlink_metadata(path) else {
+                continue;
+            };
+            if !apply_to.matches(meta.is_dir()) {
+                continue;
+            }
+            let is_link = meta.file_type().is_symlink();
+            let mut result = Ok(());
+            // chmod и время у ссылки меняли бы цель — ссылки пропускаем
+            if let Some(mode) = &changes.mode
+                && !is_link
+            {
+                let new_mode = apply_mode(mode, meta.mode(), meta.is_dir());
+                result = result.and_then(|_| fs::set_permissions(path, fs::Permissions::from_mode(new_mode)));
+            }
+            if changes.uid.is_some() || changes.gid.is_some() {
+                result = result.and_then(|_| std::os::unix::fs::lchown(path, changes.uid, changes.gid));
+            }
+            if (changes.accessed.is_some() || changes.modified.is_some()) && !is_link {
+                let mut times = fs::FileTimes::new();
+                if let Some(t) = changes.accessed {
+                    times = times.set_accessed(t);
+                }
+                if let Some(t) = changes.modified {
+                    times = times.set_modified(t);
+                }
+                result = result.and_then(|_| fs::File::open(path)?.set_times(times));
+            }
+            match result {
+                Ok(_) => changed += 1,
+                Err(e) => errors.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e)),
+            }
+        }
+
+        self.message = match errors.first() {
+            Some(err) => format!("Properties: changed {}, {} error(s): {}", changed, errors.len(), err),
+            None => format!("Properties: changed {} item(s)", changed),
+        };
+        self.properties = None;
+        self.refresh_items();
+        self.update_preview();
+    }
+
+    pub fn handle_properties_input(&mut self, key: KeyEvent) {
+        let Some(props) = self.properties.as_mut() else {
+            return;
+        };
+        let pos = FIELDS.iter().position(|f| *f == props.field).unwrap_or(0);
+        match key.code {
+            KeyCode::Esc => self.properties = None,
+            KeyCode::Enter => self.apply_properties(),
+            KeyCode::Tab | KeyCode::Down => props.field = FIELDS[(pos + 1) % FIELDS.len()],
+            KeyCode::BackTab | KeyCode::Up => props.field = FIELDS[(pos + FIELDS.len() - 1) % FIELDS.len()],
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if props.field == PropField::Recursive => {
+                props.recursive = !props.recursive;
+            }
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if props.field == PropField::ApplyTo => {
+                props.apply_to = props.apply_to.next();
+            }
+            _ => {
+                let field = props.field;
+                if let Some(input) = props.input_mut(field) {
+                    input.handle_key(key);
+                    props.validate();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes() {
+        assert_eq!(mode_string(0o755), "rwxr-xr-x");
+        assert_eq!(mode_string(0o4755), "rwsr-xr-x");
+        assert_eq!(mode_string(0o1777), "rwxrwxrwt");
+        assert_eq!(apply_mode(&parse_mode("644").unwrap(), 0o755, false), 0o644);
+        assert_eq!(apply_mode(&parse_mode("u+x,go-w").unwrap(), 0o666, false), 0o744);
+        assert_eq!(apply_mode(&parse_mode("a+X").unwrap(), 0o644, true), 0o755);
+        assert_eq!(apply_mode(&parse_mode("a+X").unwrap(), 0o644, false), 0o644);
+        assert_eq!(apply_mode(&parse_mode("o=r").unwrap(), 0o777, false), 0o774);
+        assert!(parse_mode("u+q").is_err());
+        assert!(parse_mode("999").is_err());
+    }
+
+    #[test]
+    fn datetimes_round_trip() {
+        let t = parse_datetime("2024-03-05 14:07:09").unwrap();
+        assert_eq!(format_datetime(t), "2024-03-05 14:07:09");
+        assert_eq!(format_datetime(parse_datetime("2000-02-29").unwrap()), "2000-02-29 00:00:00");
+        assert!(parse_datetime("2024-13-01").is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::app::{App, InputMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Составные расширения, которые выделяются целиком
+const COMPOUND_EXTENSIONS: &[&str] = &[".tar.gz", ".tar.bz2", ".tar.xz", ".tar.zst"];
+
+/// Часть имени в строке переименования
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NamePart {
+    Stem,
+    Extension,
+    Whole,
+}
+
+/// Позиция (в символах) точки перед расширением.
+/// Ведущая точка у скрытых файлов расширением не считается: `.bashrc` — без расширения.
+pub fn extension_start(name: &str) -> Option<usize> {
+    let lower = name.to_lowercase();
+    let byte = COMPOUND_EXTENSIONS
+        .iter()
+        .find(|ext| lower.ends_with(*ext) && lower.len() > ext.len())
+        .map(|ext| name.len() - ext.len())
+        .or_else(|| name.rfind('.').filter(|&i| i > 0 && i + 1 < name.len()))?;
+    Some(name[..byte].chars().count())
+}
+
+/// Диапазон символов для части имени
+pub fn part_range(name: &str, part: NamePart, is_dir: bool) -> (usize, usize) {
+    let len = name.chars().count();
+    let ext = if is_dir { None } else { extension_start(name) };
+    match (part, ext) {
+        (NamePart::Stem, Some(i)) => (0, i),
+        (NamePart::Extension, Some(i)) => (i + 1, len),
+        _ => (0, len),
+    }
+}
+
+/// Проверяет новое имя для `original` (в том же каталоге). `None` — имя годится.
+pub fn validate_name(name: &str, original: &Path) -> Option<String> {
+    if let Some(err) = name_error(name) {
+        return Some(err);
+    }
+    let dir = original.parent()?;
+    let target = dir.join(name);
+    if target == original {
+        return None;
+    }
+    if fs::symlink_metadata(&target).is_ok() && !same_file(&target, original) {
+        return Some(format!("'{}' already exists", name));
+    }
+    None
+}
+
+/// Смена регистра на нечувствительной ФС указывает на тот же файл
+fn same_file(a: &Path, b: &Path) -> bool {
+    fs::canonicalize(a).ok() == fs::canonicalize(b).ok()
+}
+
+/// Ошибки самого имени, без обращения к диску
+pub fn name_error(name: &str) -> Option<String> {
+    if name.trim().is_empty() {
+        return Some("Name is empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Some(format!("'{}' is reserved", name));
+    }
+    let illegal: &[char] = if cfg!(windows) {
+        &['/', '\\', '<', '>', ':', '"', '|', '?', '*', '\0']
+    } else {
+        &['/', '\0']
+    };
+    if let Some(c) = name.chars().find(|c| illegal.contains(c) || c.is_control()) {
+        return Some(match c {
+            '\0' => "Illegal character NUL".to_string(),
+            c if c.is_control() => format!("Illegal control character U+{:04X}", c as u32),
+            c => format!("Illegal character '{}'", c),
+        });
+    }
+    None
+}
+
+/// Проверяет пакет переименований `(старый, новый)` до применения:
+/// имена, повторяющиеся цели и занятые пути, которые пакет не освобождает.
+pub fn check_renames(pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    for ((src, _), conflict) in pairs.iter().zip(rename_conflicts(pairs)) {
+        if let Some(err) = conflict {
+            return Err(format!("{}: {}", src.file_name().unwrap_or_default().to_string_lossy(), err));
+        }
+    }
+    Ok(())
+}
+
+/// Проблема каждой пары пакета (`None` — пара в порядке)
+pub fn rename_conflicts(pairs: &[(PathBuf, PathBuf)]) -> Vec<Option<String>> {
+    let sources: HashSet<&PathBuf> = pairs.iter().map(|(src, _)| src).collect();
+    let mut counts: HashMap<&PathBuf, usize> = HashMap::new();
+    for (_, dst) in pairs {
+        *counts.entry(dst).or_default() += 1;
+    }
+    pairs
+        .iter()
+        .map(|(src, dst)| {
+            let name = dst.file_name().unwrap_or_default().to_string_lossy();
+            if let Some(err) = name_error(&name) {
+                Some(err)
+            } else if counts[dst] > 1 {
+                Some(format!("several items would be named '{}'", name))
+            } else if src != dst && !sources.contains(dst) && fs::symlink_metadata(dst).is_ok() && !same_file(src, dst) {
+                Some(format!("'{}' already exists", name))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Индексы переименований, чья цель — другой источник пакета (цепочки и обмены a↔b)
+pub fn chained(pairs: &[(PathBuf, PathBuf)]) -> HashSet<usize> {
+    let sources: HashSet<&PathBuf> = pairs.iter().filter(|(s, d)| s != d).map(|(src, _)| src).collect();
+    pairs
+        .iter()
+        .enumerate()
+        .filter(|(_, (src, dst))| src != dst && sources.contains(dst))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Применяет пакет в две фазы через временные имена, поэтому обмены и циклы
+/// не затирают друг друга. При ошибке всё уже сделанное откатывается.
+pub fn apply_renames(pairs: &[(PathBuf, PathBuf)]) -> Result<usize, String> {
+    let pairs: Vec<&(PathBuf, PathBuf)> = pairs.iter().filter(|(src, dst)| src != dst).collect();
+    let temp_of = |i: usize, src: &Path| {
+        src.with_file_name(format!(".ffm-rename-{}-{}", std::process::id(), i))
+    };
+
+    for (i, (src, _)) in pairs.iter().enumerate() {
+        if let Err(e) = fs::rename(src, temp_of(i, src)) {
+            for (j, (src, _)) in pairs.iter().enumerate().take(i) {
+                let _ = fs::rename(temp_of(j, src), src);
+            }
+            return Err(format!("{}: {}", src.display(), e));
+        }
+    }
+
+    for (i, (src, dst)) in pairs.iter().enumerate() {
+        let res = if fs::symlink_metadata(dst).is_ok() {
+            Err(format!("{}: target already exists", dst.display()))
+        } else {
+            fs::rename(temp_of(i, src), dst).map_err(|e| format!("{}: {}", dst.display(), e))
+        };
+        if let Err(e) = res {
+            for (j, (src, dst)) in pairs.iter().enumerate() {
+                let from = if j < i { dst.clone() } else { temp_of(j, src) };
+                let _ = fs::rename(from, src);
+            }
+            return Err(e);
+        }
+    }
+    Ok(pairs.len())
+}
+
+impl App {
+    /// Путь, который сейчас переименовывается (выделенный элемент)
+    fn rename_source(&self) -> Option<&Path> {
+        self.state
+            .selected()
+            .and_then(|i| self.filtered_items.get(i))
+            .map(|p| p.as_path())
+    }
+
+    /// Ошибка для текущего текста в строке переименования
+    pub fn rename_error(&self) -> Option<String> {
+        let source = self.rename_source()?;
+        let name = self.rename_input.text();
+        validate_name(name, source).or_else(|| {
+            // validate_name смотрит только на локальный диск
+            let fs = self.fs_for(source);
+            let target = source.with_file_name(name);
+            (!fs.is_local() && target != source && fs.exists(&target)).then(|| format!("'{}' already exists", name))
+        })
+    }
+
+    /// Выделяет основу, расширение или всё имя — ввод заменит выделенное
+    pub fn select_rename_part(&mut self, part: NamePart) {
+        let is_dir = self.rename_source().is_some_and(|p| p.is_dir());
+        let (start, end) = part_range(self.rename_input.text(), part, is_dir);
+        self.rename_input.select_chars(start, end);
+    }
+
+    /// F2 по кругу: основа → расширение → всё имя
+    fn cycle_rename_part(&mut self) {
+        let is_dir = self.rename_source().is_some_and(|p| p.is_dir());
+        let name = self.rename_input.text();
+        let current = self.rename_input.selection_chars();
+        let stem = part_range(name, NamePart::Stem, is_dir);
+        let ext = part_range(name, NamePart::Extension, is_dir);
+        let next = if current == Some(stem) && ext != stem {
+            NamePart::Extension
+        } else if current == Some(ext) && ext != stem {
+            NamePart::Whole
+        } else {
+            NamePart::Stem
+        };
+        self.select_rename_part(next);
+    }
+
+    pub fn handle_renaming_input(&mut self, key: KeyEvent) {
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(err) = self.rename_error() {
+                    self.message = err;
+                    return;
+                }
+                self.rename_input.push_history();
+                self.rename_item();
+            }
+            KeyCode::Esc => {
+                self.input_mode = InputMode::Normal;
+                self.rename_input.clear();
+                self.message = "Rename cancelled.".to_string();
+            }
+            KeyCode::F(2) => self.cycle_rename_part(),
+            KeyCode::Char('s') if alt => self.select_rename_part(NamePart::Stem),
+            KeyCode::Char('e') if alt => self.select_rename_part(NamePart::Extension),
+            KeyCode::Char('a') if alt => self.select_rename_part(NamePart::Whole),
+            _ => {
+                self.rename_input.handle_key(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_positions() {
+        assert_eq!(extension_start("report.txt"), Some(6));
+        assert_eq!(extension_start("backup.tar.gz"), Some(6));
+        assert_eq!(extension_start(".bashrc"), None);
+        assert_eq!(extension_start("Makefile"), None);
+        assert_eq!(extension_start("trailing."), None);
+        assert_eq!(part_range("отчёт.md", NamePart::Extension, false), (6, 8));
+        assert_eq!(part_range("src.d", NamePart::Stem, true), (0, 5));
+    }
+
+    #[test]
+    fn swaps_names_through_temporaries() {
+        let dir = std::env::temp_dir().join(format!("ffm_rename_swap_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::write(&a, "A").unwrap();
+        fs::write(&b, "B").unwrap();
+        let pairs = vec![(a.clone(), b.clone()), (b.clone(), a.clone())];
+        assert!(check_renames(&pairs).is_ok());
+        assert_eq!(chained(&pairs).len(), 2);
+        assert_eq!(apply_renames(&pairs), Ok(2));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "B");
+        assert!(check_renames(&[(a.clone(), b.clone())]).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_names() {
+        let original = std::env::temp_dir().join("ffm_rename_test_missing.txt");
+        assert_eq!(validate_name("  ", &original).as_deref(), Some("Name is empty"));
+        assert_eq!(validate_name("a/b", &original).as_deref(), Some("Illegal character '/'"));
+        assert!(validate_name("..", &original).is_some());
+        assert!(validate_name("fine name.txt", &original).is_none());
+    }
+}
@@ -0,0 +1,906 @@
+//! `sftp://user@host:port/path` — папки на серверах по SSH. Клиент SFTP v3 поверх `ssh -s sftp`:
+//! вход через ssh-agent, ключи и `~/.ssh/config`, как у обычного ssh. Соединение с хостом одно
+//! и живёт, пока открыт ffm.
+
+use crate::app::{App, Focus};
+use crate::vfs::{FileSystem, Metadata, WriteMode};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+pub const SCHEME: &str = "sftp://";
+
+/// Команды, которым нужны локальные файлы
+pub const LOCAL_ONLY_COMMANDS: &[&str] = &[
+    "bulkrename", "patternrename", "undo", "link", "follow", "properties", "xattr", "tag", "untag", "edit", "compress",
+    "extract", "checksum", "cleanup", "dupes", "du", "dirsize",
+];
+
+// Пакеты SFTP v3 (draft-ietf-secsh-filexfer-02)
+const FXP_INIT: u8 = 1;
+const FXP_VERSION: u8 = 2;
+const FXP_OPEN: u8 = 3;
+const FXP_CLOSE: u8 = 4;
+const FXP_READ: u8 = 5;
+const FXP_WRITE: u8 = 6;
+const FXP_LSTAT: u8 = 7;
+const FXP_FSTAT: u8 = 8;
+const FXP_OPENDIR: u8 = 11;
+const FXP_READDIR: u8 = 12;
+const FXP_REMOVE: u8 = 13;
+const FXP_MKDIR: u8 = 14;
+const FXP_RMDIR: u8 = 15;
+const FXP_REALPATH: u8 = 16;
+const FXP_STAT: u8 = 17;
+const FXP_RENAME: u8 = 18;
+const FXP_READLINK: u8 = 19;
+const FXP_STATUS: u8 = 101;
+const FXP_HANDLE: u8 = 102;
+const FXP_DATA: u8 = 103;
+const FXP_NAME: u8 = 104;
+const FXP_ATTRS: u8 = 105;
+
+const FX_OK: u32 = 0;
+const FX_EOF: u32 = 1;
+const FX_NO_SUCH_FILE: u32 = 2;
+const FX_PERMISSION_DENIED: u32 = 3;
+const FX_FAILURE: u32 = 4;
+
+const ATTR_SIZE: u32 = 0x1;
+const ATTR_UIDGID: u32 = 0x2;
+const ATTR_PERMISSIONS: u32 = 0x4;
+const ATTR_ACMODTIME: u32 = 0x8;
+const ATTR_EXTENDED: u32 = 0x8000_0000;
+
+const FXF_READ: u32 = 0x1;
+const FXF_WRITE: u32 = 0x2;
+const FXF_APPEND: u32 = 0x4;
+const FXF_CREAT: u32 = 0x8;
+const FXF_TRUNC: u32 = 0x10;
+const FXF_EXCL: u32 = 0x20;
+
+/// Столько просим за один READ/WRITE; OpenSSH больше и не отдаёт
+const CHUNK: usize = 32 * 1024;
+/// Защита от мусора вместо пакета (например, приветствия из .bashrc)
+const MAX_PACKET: usize = 256 * 1024;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str().is_some_and(|s| s.starts_with(SCHEME))
+}
+
+/// `sftp://user@host:port/path` → (`user@host:port`, `/path`); путь пустой, если не указан
+pub fn parse_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix(SCHEME)?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    let (user, host) = authority.rsplit_once('@').map_or(("", authority), |(user, host)| (user, host));
+    // `-` в начале ssh принял бы за свою опцию (`-oProxyCommand=…`)
+    let option_like = user.starts_with('-') || host.trim_start_matches('[').starts_with('-');
+    (!host.is_empty() && !option_like).then(|| (authority.to_string(), path.to_string()))
+}
+
+/// Убирает `.` и `..` из пути на сервере; ссылки потом раскрывает REALPATH
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Аргументы ssh для `user@host:port`
+fn ssh_args(authority: &str, identity: &str) -> Vec<String> {
+    let mut args: Vec<String> = ["BatchMode=yes", "ConnectTimeout=10", "ServerAliveInterval=15", "LogLevel=ERROR"]
+        .iter()
+        .flat_map(|o| ["-o".to_string(), o.to_string()])
+        .collect();
+    if !identity.is_empty() {
+        args.extend(["-i".to_string(), crate::commands::expand_path(Path::new("."), identity).to_string_lossy().to_string()]);
+    }
+    let (user, host) = match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    // [::1]:2222 или host:2222
+    let (host, port) = match host.strip_prefix('[').and_then(|h| h.split_once(']')) {
+        Some((host, port)) => (host, port.strip_prefix(':')),
+        None => match host.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host, None),
+        },
+    };
+    if let Some(port) = port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(user) = user {
+        args.extend(["-l".to_string(), user.to_string()]);
+    }
+    args.extend(["-s".to_string(), "--".to_string(), host.to_string(), "sftp".to_string()]);
+    args
+}
+
+#[derive(Default, Debug)]
+struct Attrs {
+    size: Option<u64>,
+    perms: Option<u32>,
+    mtime: Option<u32>,
+}
+
+impl Attrs {
+    fn is_dir(&self) -> bool {
+        self.perms.is_some_and(|p| p & S_IFMT == S_IFDIR)
+    }
+
+    fn is_link(&self) -> bool {
+        self.perms.is_some_and(|p| p & S_IFMT == S_IFLNK)
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            is_dir: self.is_dir(),
+            // Размер каталога на сервере — это длина самого каталога
+            size: if self.is_dir() { None } else { self.size },
+            modified: self.mtime.map(|t| UNIX_EPOCH + Duration::from_secs(t as u64)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Тело запроса
+#[derive(Default)]
+struct Body(Vec<u8>);
+
+impl Body {
+    fn u32(mut self, v: u32) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    fn u64(mut self, v: u64) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    fn bytes(self, v: &[u8]) -> Self {
+        let mut body = self.u32(v.len() as u32);
+        body.0.extend_from_slice(v);
+        body
+    }
+
+    fn str(self, v: &str) -> Self {
+        self.bytes(v.as_bytes())
+    }
+}
+
+/// Разбор ответа
+struct Fields<'a>(&'a [u8]);
+
+impl Fields<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        if self.0.len() < n {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated SFTP packet"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let n = self.u32()? as usize;
+        Ok(self.take(n)?.to_vec())
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.bytes()?).to_string())
+    }
+
+    fn attrs(&mut self) -> io::Result<Attrs> {
+        let flags = self.u32()?;
+        let mut attrs = Attrs::default();
+        if flags & ATTR_SIZE != 0 {
+            attrs.size = Some(self.u64()?);
+        }
+        if flags & ATTR_UIDGID != 0 {
+            self.take(8)?;
+        }
+        if flags & ATTR_PERMISSIONS != 0 {
+            attrs.perms = Some(self.u32()?);
+        }
+        if flags & ATTR_ACMODTIME != 0 {
+            self.u32()?;
+            attrs.mtime = Some(self.u32()?);
+        }
+        if flags & ATTR_EXTENDED != 0 {
+            for _ in 0..self.u32()? {
+                self.bytes()?;
+                self.bytes()?;
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+/// Ошибка из пакета STATUS
+fn status_error(data: &[u8]) -> io::Error {
+    let mut f = Fields(data);
+    let code = f.u32().unwrap_or(FX_FAILURE);
+    let message = f.string().unwrap_or_default();
+    let kind = match code {
+        FX_NO_SUCH_FILE => io::ErrorKind::NotFound,
+        FX_PERMISSION_DENIED => io::ErrorKind::PermissionDenied,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, if message.is_empty() { format!("SFTP error {}", code) } else { message })
+}
+
+fn unexpected(kind: u8) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("unexpected SFTP reply {}", kind))
+}
+
+struct Channel {
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    next_id: u32,
+}
+
+impl Channel {
+    fn send(&mut self, kind: u8, body: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&(body.len() as u32 + 1).to_be_bytes())?;
+        self.writer.write_all(&[kind])?;
+        self.writer.write_all(body)?;
+        self.writer.flush()
+    }
+
+    fn receive(&mut self) -> io::Result<(u8, Vec<u8>)> {
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_PACKET {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an SFTP stream (shell startup output?)"));
+        }
+        let mut packet = vec![0u8; len];
+        self.reader.read_exact(&mut packet)?;
+        let kind = packet.remove(0);
+        Ok((kind, packet))
+    }
+}
+
+/// Соединение с одним сервером; запросы идут по очереди
+pub struct Session {
+    channel: Mutex<Channel>,
+    child: Mutex<Option<Child>>,
+    broken: AtomicBool,
+}
+
+impl Session {
+    /// Запускает `ssh -s sftp`; ssh не спрашивает пароль (BatchMode), а сразу падает
+    pub fn connect(authority: &str, identity: &str) -> io::Result<Session> {
+        let mut child = Command::new("ssh")
+            .args(ssh_args(authority, identity))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("ssh: {}", e)))?;
+        let (stdin, stdout) = (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+        // stderr читаем всё время, чтобы ssh не встал на полном канале; последняя строка — причина отказа
+        let stderr = child.stderr.take().unwrap();
+        let reason = thread::spawn(move || {
+            let mut text = String::new();
+            let _ = BufReader::new(stderr).read_to_string(&mut text);
+            text.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or_default().trim().to_string()
+        });
+        match Session::over(Box::new(BufReader::new(stdout)), Box::new(stdin)) {
+            Ok(session) => {
+                *session.child.lock().unwrap() = Some(child);
+                Ok(session)
+            }
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let reason = reason.join().unwrap_or_default();
+                Err(if reason.is_empty() { e } else { io::Error::other(reason) })
+            }
+        }
+    }
+
+    /// Рукопожатие по готовому каналу: ssh или сервер в том же процессе (тесты)
+    pub fn over(reader: Box<dyn Read + Send>, writer: Box<dyn Write + Send>) -> io::Result<Session> {
+        let mut channel = Channel { reader, writer, next_id: 0 };
+        channel.send(FXP_INIT, &Body::default().u32(3).0)?;
+        match channel.receive()? {
+            (FXP_VERSION, _) => {}
+            (kind, _) => return Err(unexpected(kind)),
+        }
+        Ok(Session { channel: Mutex::new(channel), child: Mutex::new(None), broken: AtomicBool::new(false) })
+    }
+
+    /// Соединение цело: ssh не завершился и канал не рвался
+    pub fn alive(&self) -> bool {
+        !self.broken.load(Ordering::Relaxed)
+            && !self.child.lock().unwrap().as_mut().is_some_and(|c| c.try_wait().is_ok_and(|s| s.is_some()))
+    }
+
+    fn request(&self, kind: u8, body: Body) -> io::Result<(u8, Vec<u8>)> {
+        let mut channel = self.channel.lock().unwrap();
+        channel.next_id = channel.next_id.wrapping_add(1);
+        let id = channel.next_id;
+        let mut packet = id.to_be_bytes().to_vec();
+        packet.extend_from_slice(&body.0);
+        let reply = channel.send(kind, &packet).and_then(|_| channel.receive());
+        let (kind, data) = reply.map_err(|e| {
+            self.broken.store(true, Ordering::Relaxed);
+            io::Error::new(io::ErrorKind::BrokenPipe, format!("connection lost: {}", e))
+        })?;
+        let mut f = Fields(&data);
+        if f.u32()? != id {
+            self.broken.store(true, Ordering::Relaxed);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "SFTP reply out of order"));
+        }
+        Ok((kind, data[4..].to_vec()))
+    }
+
+    fn status(&self, kind: u8, body: Body) -> io::Result<()> {
+        match self.request(kind, body)? {
+            (FXP_STATUS, data) if Fields(&data).u32()? == FX_OK => Ok(()),
+            (FXP_STATUS, data) => Err(status_error(&data)),
+            (kind, _) => Err(unexpected(kind)),
+        }
+    }
+
+    fn handle(&self, kind: u8, body: Body) -> io::Result<Vec<u8>> {
+        match self.request(kind, body)? {
+            (FXP_HANDLE, data) => Fields(&data).bytes(),
+            (FXP_STATUS, data) => Err(status_error(&data)),
+            (kind, _) => Err(unexpected(kind)),
+        }
+    }
+
+    fn attrs(&self, kind: u8, body: Body) -> io::Result<Attrs> {
+        match self.request(kind, body)? {
+            (FXP_ATTRS, data) => Fields(&data).attrs(),
+            (FXP_STATUS, data) => Err(status_error(&data)),
+            (kind, _) => Err(unexpected(kind)),
+        }
+    }
+
+    /// Ответ NAME: имена с атрибутами; `None` — конец списка
+    fn names(&self, kind: u8, body: Body) -> io::Result<Option<Vec<(String, Attrs)>>> {
+        match self.request(kind, body)? {
+            (FXP_NAME, data) => {
+                let mut f = Fields(&data);
+                let mut names = Vec::new();
+                for _ in 0..f.u32()? {
+                    let name = f.string()?;
+                    f.string()?;
+                    names.push((name, f.attrs()?));
+                }
+                Ok(Some(names))
+            }
+            (FXP_STATUS, data) if Fields(&data).u32()? == FX_EOF => Ok(None),
+            (FXP_STATUS, data) => Err(status_error(&data)),
+            (kind, _) => Err(unexpected(kind)),
+        }
+    }
+
+    /// Первое имя из ответа NAME (REALPATH, READLINK)
+    fn name(&self, kind: u8, path: &str) -> io::Result<String> {
+        let names = self.names(kind, Body::default().str(path))?.unwrap_or_default();
+        names.into_iter().next().map(|(name, _)| name).ok_or_else(|| unexpected(FXP_NAME))
+    }
+
+    fn close(&self, handle: &[u8]) -> io::Result<()> {
+        self.status(FXP_CLOSE, Body::default().bytes(handle))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<(String, Attrs)>> {
+        let handle = self.handle(FXP_OPENDIR, Body::default().str(path))?;
+        let mut entries = Vec::new();
+        let listed = loop {
+            match self.names(FXP_READDIR, Body::default().bytes(&handle)) {
+                Ok(Some(names)) => entries.extend(names.into_iter().filter(|(n, _)| n != "." && n != "..")),
+                Ok(None) => break Ok(entries),
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = self.close(&handle);
+        listed
+    }
+
+    pub fn realpath(&self, path: &str) -> io::Result<String> {
+        self.name(FXP_REALPATH, path)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.get_mut().unwrap().as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Открытый удалённый файл: чтение или запись кусками
+struct RemoteFile {
+    session: Arc<Session>,
+    handle: Vec<u8>,
+    offset: u64,
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(CHUNK) as u32;
+        let body = Body::default().bytes(&self.handle).u64(self.offset).u32(len);
+        match self.session.request(FXP_READ, body)? {
+            (FXP_DATA, data) => {
+                let data = Fields(&data).bytes()?;
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                self.offset += n as u64;
+                Ok(n)
+            }
+            (FXP_STATUS, data) if Fields(&data).u32()? == FX_EOF => Ok(0),
+            (FXP_STATUS, data) => Err(status_error(&data)),
+            (kind, _) => Err(unexpected(kind)),
+        }
+    }
+}
+
+impl Write for RemoteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(CHUNK);
+        self.session.status(FXP_WRITE, Body::default().bytes(&self.handle).u64(self.offset).bytes(&buf[..n]))?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RemoteFile {
+    fn drop(&mut self) {
+        let _ = self.session.close(&self.handle);
+    }
+}
+
+/// Сервер как провайдер: пути — `sftp://user@host/abs/path`
+pub struct SftpFs {
+    pub root: PathBuf,
+    session: Arc<Session>,
+}
+
+impl SftpFs {
+    pub fn new(authority: &str, session: Session) -> Self {
+        SftpFs { root: PathBuf::from(format!("{}{}", SCHEME, authority)), session: Arc::new(session) }
+    }
+
+    /// Путь на сервере
+    fn remote(&self, path: &Path) -> io::Result<String> {
+        let rel = path.strip_prefix(&self.root).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "not on this server"))?;
+        let parts: Vec<_> = rel.iter().map(|c| c.to_string_lossy()).collect();
+        Ok(format!("/{}", parts.join("/")))
+    }
+
+    /// Путь в списке для пути на сервере
+    pub fn local(&self, remote: &str) -> PathBuf {
+        match remote.trim_matches('/') {
+            "" => self.root.clone(),
+            rel => self.root.join(rel),
+        }
+    }
+
+    pub fn alive(&self) -> bool {
+        self.session.alive()
+    }
+
+    /// Сведения по атрибутам lstat: ссылку раскрываем отдельными запросами
+    fn resolve(&self, remote: &str, lstat: Attrs) -> Metadata {
+        if !lstat.is_link() {
+            return lstat.metadata();
+        }
+        let link_target = self.session.name(FXP_READLINK, remote).ok().map(PathBuf::from);
+        match self.session.attrs(FXP_STAT, Body::default().str(remote)) {
+            Ok(target) => Metadata { link_target, ..target.metadata() },
+            Err(_) => Metadata { link_target, broken: true, ..lstat.metadata() },
+        }
+    }
+
+    fn open(&self, remote: &str, flags: u32) -> io::Result<RemoteFile> {
+        let handle = self.session.handle(FXP_OPEN, Body::default().str(remote).u32(flags).u32(0))?;
+        Ok(RemoteFile { session: self.session.clone(), handle, offset: 0 })
+    }
+}
+
+impl FileSystem for SftpFs {
+    /// `sftp://user@host`: одно соединение на сервер
+    fn id(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self.session.read_dir(&self.remote(dir)?)?.into_iter().map(|(name, _)| dir.join(name)).collect())
+    }
+
+    /// Атрибуты приходят вместе со списком — без запроса на каждый файл
+    fn entries(&self, dir: &Path) -> io::Result<Vec<(PathBuf, Option<Metadata>)>> {
+        let remote = self.remote(dir)?;
+        let listed = self.session.read_dir(&remote)?;
+        Ok(listed
+            .into_iter()
+            .map(|(name, attrs)| {
+                let meta = self.resolve(&format!("{}/{}", remote.trim_end_matches('/'), name), attrs);
+                (dir.join(name), Some(meta))
+            })
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let remote = self.remote(path)?;
+        let lstat = self.session.attrs(FXP_LSTAT, Body::default().str(&remote))?;
+        Ok(self.resolve(&remote, lstat))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(self.open(&self.remote(path)?, FXF_READ)?))
+    }
+
+    fn write(&self, path: &Path, mode: WriteMode) -> io::Result<Box<dyn Write>> {
+        let remote = self.remote(path)?;
+        let flags = FXF_WRITE
+            | FXF_CREAT
+            | match mode {
+                WriteMode::Truncate => FXF_TRUNC,
+                WriteMode::CreateNew => FXF_EXCL,
+                WriteMode::Append => FXF_APPEND,
+            };
+        let mut file = self.open(&remote, flags)?;
+        if mode == WriteMode::Append {
+            file.offset = self.session.attrs(FXP_FSTAT, Body::default().bytes(&file.handle))?.size.unwrap_or(0);
+        }
+        Ok(Box::new(file))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.session.status(FXP_RENAME, Body::default().str(&self.remote(from)?).str(&self.remote(to)?))
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let remote = self.remote(path)?;
+        let attrs = self.session.attrs(FXP_LSTAT, Body::default().str(&remote))?;
+        if !attrs.is_dir() {
+            return self.session.status(FXP_REMOVE, Body::default().str(&remote));
+        }
+        for child in self.list(path)? {
+            self.remove(&child)?;
+        }
+        self.session.status(FXP_RMDIR, Body::default().str(&remote))
+    }
+
+    fn mkdir(&self, path: &Path, parents: bool) -> io::Result<()> {
+        if parents {
+            match self.metadata(path) {
+                Ok(meta) if meta.is_dir => return Ok(()),
+                Ok(_) => return Err(io::Error::from(io::ErrorKind::AlreadyExists)),
+                Err(_) => {}
+            }
+            if let Some(parent) = path.parent().filter(|p| p.starts_with(&self.root)) {
+                self.mkdir(parent, true)?;
+            }
+        }
+        self.session.status(FXP_MKDIR, Body::default().str(&self.remote(path)?).u32(0))
+    }
+}
+
+impl App {
+    /// Сервер, на котором лежит `path`
+    pub fn remote_at(&self, path: &Path) -> Option<&SftpFs> {
+        self.remotes.iter().find(|r| path.starts_with(&r.root))
+    }
+
+    /// Отказ для команд, которым нужны локальные файлы, пока открыт сервер
+    pub fn remote_local_only(&mut self, command: &str) -> bool {
+        let blocked =
+            self.focus == Focus::FileList && LOCAL_ONLY_COMMANDS.contains(&command) && self.remote_at(&self.current_dir).is_some();
+        if blocked {
+            self.message = format!("'{}' works on local files only — copy them over with y and p", command);
+        }
+        blocked
+    }
+
+    /// Корень сервера: выше подниматься некуда
+    pub fn at_remote_root(&self) -> bool {
+        self.remotes.iter().any(|r| r.root == self.current_dir)
+    }
+
+    /// Переход по `sftp://`-адресу: соединение с хостом открывается один раз и переиспользуется
+    pub fn open_remote(&mut self, url: &str) {
+        let Some((authority, path)) = parse_url(url) else {
+            self.message = format!("Bad address: {} (expected sftp://user@host/path)", url);
+            return;
+        };
+        // Оборванное соединение открываем заново
+        self.remotes.retain(|r| r.alive());
+        let root = PathBuf::from(format!("{}{}", SCHEME, authority));
+        let index = match self.remotes.iter().position(|r| r.root == root) {
+            Some(i) => i,
+            None => match Session::connect(&authority, &self.config.ssh_identity) {
+                Ok(session) => {
+                    self.remotes.push(SftpFs::new(&authority, session));
+                    self.remotes.len() - 1
+                }
+                Err(e) => {
+                    self.message = format!("Cannot connect to {}: {}", authority, e);
+                    return;
+                }
+            },
+        };
+        let remote = &self.remotes[index];
+        // Пустой путь — домашняя папка; `..` и ссылки раскрывает сервер
+        let resolved = remote
+            .session
+            .realpath(&if path.is_empty() { ".".to_string() } else { normalize(&path) })
+            .and_then(|real| Ok((remote.local(&real), remote.metadata(&remote.local(&real))?)));
+        match resolved {
+            Ok((target, meta)) if meta.is_dir => {
+                self.message = format!("{}{}", SCHEME, authority);
+                self.current_dir = target;
+                self.focus = Focus::FileList;
+                self.refresh_items();
+            }
+            Ok((target, _)) => self.reveal_path(&target),
+            Err(e) => self.message = format!("{}{}{}: {}", SCHEME, authority, path, e),
+        }
+    }
+}
+
+/// SFTP-сервер в том же процессе поверх папки на диске — для тестов без sshd
+#[cfg(test)]
+pub mod server {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::MetadataExt;
+
+    fn attrs(meta: &fs::Metadata) -> Body {
+        #[cfg(unix)]
+        let (perms, mtime) = (meta.mode(), meta.mtime() as u32);
+        #[cfg(not(unix))]
+        let (perms, mtime) = (if meta.is_dir() { S_IFDIR | 0o755 } else { 0o100644 }, 0);
+        Body::default().u32(ATTR_SIZE | ATTR_PERMISSIONS | ATTR_ACMODTIME).u64(meta.len()).u32(perms).u32(mtime).u32(mtime)
+    }
+
+    fn status(code: u32, message: &str) -> (u8, Body) {
+        (FXP_STATUS, Body::default().u32(code).str(message).str(""))
+    }
+
+    fn io_status(e: io::Error) -> (u8, Body) {
+        let code = match e.kind() {
+            io::ErrorKind::NotFound => FX_NO_SUCH_FILE,
+            io::ErrorKind::PermissionDenied => FX_PERMISSION_DENIED,
+            _ => FX_FAILURE,
+        };
+        status(code, &e.to_string())
+    }
+
+    enum Open {
+        File(fs::File),
+        Dir(Option<Vec<(String, fs::Metadata)>>),
+    }
+
+    /// Запускает сервер в потоке и возвращает сессию к нему; пути сервера — внутри `root`
+    pub fn start(root: &Path) -> Session {
+        let (client_read, server_write) = io::pipe().unwrap();
+        let (server_read, client_write) = io::pipe().unwrap();
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let mut channel = Channel { reader: Box::new(server_read), writer: Box::new(server_write), next_id: 0 };
+            let mut handles: HashMap<u32, Open> = HashMap::new();
+            let mut next = 0u32;
+            while let Ok((kind, data)) = channel.receive() {
+                if kind == FXP_INIT {
+                    let _ = channel.send(FXP_VERSION, &Body::default().u32(3).0);
+                    continue;
+                }
+                let mut f = Fields(&data);
+                let id = f.u32().unwrap();
+                let local = |p: String| root.join(p.trim_start_matches('/'));
+                let (reply, body) = serve(kind, &mut f, &local, &mut handles, &mut next).unwrap_or_else(io_status);
+                let mut packet = Body::default().u32(id);
+                packet.0.extend_from_slice(&body.0);
+                if channel.send(reply, &packet.0).is_err() {
+                    break;
+                }
+            }
+        });
+        Session::over(Box::new(client_read), Box::new(client_write)).unwrap()
+    }
+
+    fn serve(
+        kind: u8,
+        f: &mut Fields,
+        local: &dyn Fn(String) -> PathBuf,
+        handles: &mut HashMap<u32, Open>,
+        next: &mut u32,
+    ) -> io::Result<(u8, Body)> {
+        let mut handle_of = |open: Open| {
+            *next += 1;
+            handles.insert(*next, open);
+            (FXP_HANDLE, Body::default().bytes(&next.to_be_bytes()))
+        };
+        Ok(match kind {
+            FXP_OPEN => {
+                let path = local(f.string()?);
+                let flags = f.u32()?;
+                let file = fs::OpenOptions::new()
+                    .read(flags & FXF_READ != 0)
+                    .write(flags & FXF_WRITE != 0)
+                    .append(flags & FXF_APPEND != 0)
+                    .create(flags & FXF_CREAT != 0 && flags & FXF_EXCL == 0)
+                    .create_new(flags & FXF_EXCL != 0)
+                    .truncate(flags & FXF_TRUNC != 0)
+                    .open(path)?;
+                handle_of(Open::File(file))
+            }
+            FXP_OPENDIR => {
+                let entries = fs::read_dir(local(f.string()?))?
+                    .flatten()
+                    .filter_map(|e| Some((e.file_name().to_string_lossy().to_string(), e.path().symlink_metadata().ok()?)))
+                    .collect();
+                handle_of(Open::Dir(Some(entries)))
+            }
+            FXP_CLOSE | FXP_READ | FXP_WRITE | FXP_READDIR | FXP_FSTAT => {
+                let handle = u32::from_be_bytes(f.bytes()?.try_into().unwrap_or_default());
+                if kind == FXP_CLOSE {
+                    handles.remove(&handle);
+                    return Ok(status(FX_OK, ""));
+                }
+                let open = handles.get_mut(&handle).ok_or_else(|| io::Error::other("bad handle"))?;
+                match (kind, open) {
+                    (FXP_READ, Open::File(file)) => {
+                        use std::io::{Seek, SeekFrom};
+                        file.seek(SeekFrom::Start(f.u64()?))?;
+                        let mut buf = vec![0u8; f.u32()? as usize];
+                        let n = file.read(&mut buf)?;
+                        if n == 0 { status(FX_EOF, "") } else { (FXP_DATA, Body::default().bytes(&buf[..n])) }
+                    }
+                    (FXP_WRITE, Open::File(file)) => {
+                        use std::io::{Seek, SeekFrom};
+                        file.seek(SeekFrom::Start(f.u64()?))?;
+                        file.write_all(&f.bytes()?)?;
+                        status(FX_OK, "")
+                    }
+                    (FXP_FSTAT, Open::File(file)) => (FXP_ATTRS, attrs(&file.metadata()?)),
+                    (FXP_READDIR, Open::Dir(entries)) => match entries.take() {
+                        Some(entries) => {
+                            let mut body = Body::default().u32(entries.len() as u32);
+                            for (name, meta) in entries {
+                                body = body.str(&name).str(&name);
+                                body.0.extend_from_slice(&attrs(&meta).0);
+                            }
+                            (FXP_NAME, body)
+                        }
+                        None => status(FX_EOF, ""),
+                    },
+                    _ => return Err(io::Error::other("bad handle")),
+                }
+            }
+            FXP_STAT => (FXP_ATTRS, attrs(&fs::metadata(local(f.string()?))?)),
+            FXP_LSTAT => (FXP_ATTRS, attrs(&fs::symlink_metadata(local(f.string()?))?)),
+            FXP_REMOVE => fs::remove_file(local(f.string()?)).map(|_| status(FX_OK, ""))?,
+            FXP_RMDIR => fs::remove_dir(local(f.string()?)).map(|_| status(FX_OK, ""))?,
+            FXP_MKDIR => fs::create_dir(local(f.string()?)).map(|_| status(FX_OK, ""))?,
+            FXP_RENAME => {
+                let (from, to) = (local(f.string()?), local(f.string()?));
+                if to.exists() {
+                    return Ok(status(FX_FAILURE, "Failure"));
+                }
+                fs::rename(from, to).map(|_| status(FX_OK, ""))?
+            }
+            FXP_READLINK => {
+                let target = fs::read_link(local(f.string()?))?;
+                (FXP_NAME, Body::default().u32(1).str(&target.to_string_lossy()).str("").u32(0))
+            }
+            FXP_REALPATH => {
+                let path = f.string()?;
+                // Домашняя папка тестового сервера — его корень
+                let real = if path == "." { "/".to_string() } else { path };
+                fs::metadata(local(real.clone()))?;
+                (FXP_NAME, Body::default().u32(1).str(&real).str("").u32(0))
+            }
+            _ => status(8, "unsupported"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{self, LocalFs};
+    use std::fs;
+
+    #[test]
+    fn parses_addresses() {
+        assert_eq!(parse_url("sftp://dev@build:2222/srv/app"), Some(("dev@build:2222".to_string(), "/srv/app".to_string())));
+        assert_eq!(parse_url("sftp://build"), Some(("build".to_string(), String::new())));
+        assert_eq!(parse_url("sftp:///x"), None);
+        assert_eq!(parse_url("/tmp"), None);
+        assert_eq!(normalize("/srv/app/logs/.././/x/"), "/srv/app/x");
+        assert_eq!(normalize("/.."), "/");
+        let args = ssh_args("dev@[::1]:2222", "");
+        assert_eq!(&args[args.len() - 8..], ["-p", "2222", "-l", "dev", "-s", "--", "::1", "sftp"]);
+        assert_eq!(parse_url("sftp://-oProxyCommand=touch%20x/"), None);
+        assert_eq!(parse_url("sftp://-oProxyCommand=x@build/"), None);
+        assert_eq!(parse_url("sftp://dev@[-oProxyCommand=x]/"), None);
+    }
+
+    #[test]
+    fn works_against_in_process_server() {
+        let dir = std::env::temp_dir().join(format!("ffm_sftp_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("served/docs")).unwrap();
+        fs::write(dir.join("served/docs/a.txt"), "alpha").unwrap();
+        fs::create_dir_all(dir.join("local")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("docs/a.txt", dir.join("served/link")).unwrap();
+
+        let remote = SftpFs::new("dev@build", server::start(&dir.join("served")));
+        let root = PathBuf::from("sftp://dev@build");
+        assert_eq!(remote.local("/"), root);
+        let mut listed = remote.entries(&root).unwrap();
+        listed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(listed[0].0, root.join("docs"));
+        assert!(listed[0].1.as_ref().unwrap().is_dir);
+        #[cfg(unix)]
+        assert_eq!(listed[1].1.as_ref().unwrap().link_target, Some(PathBuf::from("docs/a.txt")));
+
+        let mut text = String::new();
+        remote.read(&root.join("docs/a.txt")).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "alpha");
+
+        // Выгрузка и загрузка — тем же копированием, что и при вставке
+        vfs::copy_recursive(&remote, &root.join("docs"), &LocalFs, &dir.join("local/docs"), false).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("local/docs/a.txt")).unwrap(), "alpha");
+        let big = "x".repeat(CHUNK * 2 + 5);
+        fs::write(dir.join("local/big.txt"), &big).unwrap();
+        remote.mkdir(&root.join("up/deep"), true).unwrap();
+        vfs::copy_recursive(&LocalFs, &dir.join("local/big.txt"), &remote, &root.join("up/big.txt"), false).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("served/up/big.txt")).unwrap(), big);
+
+        remote.rename(&root.join("up/big.txt"), &root.join("up/deep/moved.txt")).unwrap();
+        assert!(remote.exists(&root.join("up/deep/moved.txt")));
+        assert!(remote.write(&root.join("up/deep/moved.txt"), WriteMode::CreateNew).is_err());
+        remote.remove(&root.join("up")).unwrap();
+        assert!(!dir.join("served/up").exists());
+        assert_eq!(remote.metadata(&root.join("nope")).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(remote.alive());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,796 @@
+//! `:share` — раздача текущей папки (или выбранного) по HTTP: список со ссылками, скачивание,
+//! по желанию загрузка (PUT) и пароль (Basic). По умолчанию слушает только localhost, в сеть —
+//! с `lan`. Сервер живёт в фоне до `:share stop`, адрес виден в строке состояния и QR-кодом в окне.
+
+use crate::app::App;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Как часто цикл приёма проверяет флаг остановки
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+/// Сколько ждать медленного клиента
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// Заголовки длиннее — не HTTP, а мусор
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+/// Одновременных соединений; остальным — 503, а не новый поток на каждого
+const MAX_CONNECTIONS: usize = 32;
+const TEXT: &str = "text/plain; charset=utf-8";
+
+/// Счётчики, которые сервер обновляет из своих потоков
+#[derive(Default)]
+pub struct ShareStats {
+    pub requests: AtomicU64,
+    pub sent: AtomicU64,
+    pub uploads: AtomicU64,
+    /// Открытые сейчас соединения
+    pub connections: AtomicUsize,
+    /// Последний принятый файл: имя и адрес отправителя
+    pub last_upload: Mutex<String>,
+}
+
+/// Запущенная раздача; при удалении сервер останавливается
+pub struct ShareJob {
+    pub url: String,
+    /// Что раздаётся: путь папки или «N selected items»
+    pub label: String,
+    pub upload: bool,
+    pub user: Option<String>,
+    /// Модули QR-кода адреса; `None`, если адрес не влез
+    pub qr: Option<Vec<Vec<bool>>>,
+    pub stats: Arc<ShareStats>,
+    /// Папка, в которую падают загрузки корня; её список обновляем после загрузки
+    dir: Option<PathBuf>,
+    seen_uploads: u64,
+    stop: Arc<AtomicBool>,
+    accept: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for ShareJob {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Ждём цикл приёма, чтобы порт освободился сразу и `:share` можно было повторить
+        if let Some(accept) = self.accept.take() {
+            let _ = accept.join();
+        }
+    }
+}
+
+/// Ввод пароля после `:share … <user>`: в командной строке он остался бы в истории и макросах
+pub struct SharePrompt {
+    pub user: String,
+    pub password: String,
+    port: u16,
+    upload: bool,
+    lan: bool,
+}
+
+/// Снимает счётчик соединения, когда поток обслуживания заканчивается
+struct ConnectionGuard(Arc<ShareStats>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Сравнение за время, не зависящее от того, где строки расходятся
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    std::hint::black_box(a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y))) == 0
+}
+
+/// Корень раздачи: папка целиком или выбранные элементы (тогда корень — их список)
+enum Root {
+    Dir(PathBuf),
+    Picks(Vec<PathBuf>),
+}
+
+enum Target {
+    Picks,
+    /// Настоящий путь (без ссылок) и корень, за который он не выходит
+    Path(PathBuf, PathBuf),
+    Missing,
+}
+
+impl Root {
+    fn resolve(&self, parts: &[String]) -> Target {
+        let (base, rest) = match (self, parts.split_first()) {
+            (Root::Dir(dir), _) => (dir, parts),
+            (Root::Picks(_), None) => return Target::Picks,
+            (Root::Picks(picks), Some((first, rest))) => {
+                match picks.iter().find(|p| p.file_name().is_some_and(|n| n.to_string_lossy() == first.as_str())) {
+                    Some(pick) => (pick, rest),
+                    None => return Target::Missing,
+                }
+            }
+        };
+        let Ok(base) = fs::canonicalize(base) else {
+            return Target::Missing;
+        };
+        match contained(&base, &rest.iter().fold(base.clone(), |p, part| p.join(part))) {
+            Some(path) => Target::Path(path, base),
+            None => Target::Missing,
+        }
+    }
+}
+
+/// Путь после разрешения ссылок, если он внутри `base`; ссылка наружу раздачи — как отсутствующий файл
+fn contained(base: &Path, path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok().filter(|real| real.starts_with(base))
+}
+
+struct Server {
+    root: Root,
+    upload: bool,
+    /// Ожидаемое значение `Authorization`
+    auth: Option<String>,
+    stop: Arc<AtomicBool>,
+    stats: Arc<ShareStats>,
+}
+
+struct Request {
+    method: String,
+    /// Путь как пришёл, без запроса после `?`
+    raw_path: String,
+    /// Раскодированные части пути
+    parts: Vec<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Запускает сервер; порт занимается сразу, чтобы ошибка пришла к пользователю, а не в поток.
+/// `lan` — слушать все интерфейсы, иначе только localhost.
+/// QR-код адреса (уровень коррекции M): `true` — тёмный модуль; `None`, если больше версии 10 —
+/// 57×57 модулей ещё помещаются в терминал
+fn qr_modules(url: &str) -> Option<Vec<Vec<bool>>> {
+    let code = qrcode::QrCode::with_error_correction_level(url, qrcode::EcLevel::M).ok()?;
+    let width = code.width();
+    if width > 57 {
+        return None;
+    }
+    let colors = code.to_colors();
+    Some(colors.chunks(width).map(|row| row.iter().map(|&c| c == qrcode::Color::Dark).collect()).collect())
+}
+
+fn start(root: Root, label: String, port: u16, upload: bool, lan: bool, creds: Option<(&str, &str)>) -> io::Result<ShareJob> {
+    let host = if lan { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+    let listener = TcpListener::bind((host, port))?;
+    listener.set_nonblocking(true)?;
+    let port = listener.local_addr()?.port();
+    let url = format!("http://{}:{}/", if lan { lan_ip() } else { IpAddr::V4(Ipv4Addr::LOCALHOST) }, port);
+    let dir = match &root {
+        Root::Dir(dir) => Some(dir.clone()),
+        Root::Picks(_) => None,
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let stats = Arc::new(ShareStats::default());
+    let auth = creds.map(|(user, pass)| format!("Basic {}", crate::system_clipboard::base64(format!("{}:{}", user, pass).as_bytes())));
+    let server = Arc::new(Server { root, upload, auth, stop: stop.clone(), stats: stats.clone() });
+    let accept = thread::spawn(move || {
+        while !server.stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((mut stream, _)) => {
+                    if server.stats.connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                        server.stats.connections.fetch_sub(1, Ordering::Relaxed);
+                        // Ответ крошечный и влезает в буфер сокета — цикл приёма не ждёт клиента
+                        let _ = stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                        continue;
+                    }
+                    let guard = ConnectionGuard(server.stats.clone());
+                    let server = server.clone();
+                    thread::spawn(move || {
+                        let _guard = guard;
+                        let _ = serve(&server, stream);
+                    });
+                }
+                Err(_) => thread::sleep(ACCEPT_POLL),
+            }
+        }
+    });
+    Ok(ShareJob {
+        qr: qr_modules(&url),
+        url,
+        label,
+        upload,
+        user: creds.map(|(user, _)| user.to_string()),
+        stats,
+        dir,
+        seen_uploads: 0,
+        stop,
+        accept: Some(accept),
+    })
+}
+
+/// Адрес в локальной сети: интерфейс, через который ушёл бы пакет наружу (UDP ничего не шлёт)
+fn lan_ip() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn serve(server: &Server, stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let peer = stream.peer_addr().map(|a| a.ip().to_string()).unwrap_or_default();
+    let mut out = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let Some(req) = read_request(&mut reader)? else {
+        return respond(&mut out, "400 Bad Request", TEXT, b"Bad request\n", &[], false);
+    };
+    server.stats.requests.fetch_add(1, Ordering::Relaxed);
+    if let Some(expected) = &server.auth
+        && !constant_time_eq(req.header("authorization").unwrap_or_default().as_bytes(), expected.as_bytes())
+    {
+        return respond(&mut out, "401 Unauthorized", TEXT, b"Password required\n", &[("WWW-Authenticate", "Basic realm=\"ffm\"")], false);
+    }
+    match req.method.as_str() {
+        "GET" => serve_get(server, &req, &mut out, false),
+        "HEAD" => serve_get(server, &req, &mut out, true),
+        "PUT" if server.upload => serve_put(server, &req, &mut reader, &mut out, &peer),
+        _ => {
+            let allow = if server.upload { "GET, HEAD, PUT" } else { "GET, HEAD" };
+            respond(&mut out, "405 Method Not Allowed", TEXT, b"Method not allowed\n", &[("Allow", allow)], false)
+        }
+    }
+}
+
+/// Строка запроса и заголовки; `None` — не HTTP или путь с `..`
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut total = 0;
+    let mut line = String::new();
+    let mut next_line = |line: &mut String| -> io::Result<bool> {
+        line.clear();
+        let n = reader.by_ref().take((MAX_HEADER_BYTES - total) as u64).read_line(line)?;
+        total += n;
+        Ok(n > 0 && line.ends_with('\n'))
+    };
+    if !next_line(&mut line)? {
+        return Ok(None);
+    }
+    let mut words = line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Ok(None);
+    };
+    let method = method.to_string();
+    let raw_path = target.split(['?', '#']).next().unwrap_or_default().to_string();
+    let Some(decoded) = percent_decode(&raw_path) else {
+        return Ok(None);
+    };
+    let parts: Vec<String> = decoded.split('/').filter(|p| !p.is_empty()).map(String::from).collect();
+    if !raw_path.starts_with('/') || parts.iter().any(|p| p == "." || p == ".." || p.contains('\\')) {
+        return Ok(None);
+    }
+    let mut headers = Vec::new();
+    loop {
+        if !next_line(&mut line)? {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(Some(Request { method, raw_path, parts, headers }))
+}
+
+fn respond(out: &mut TcpStream, status: &str, kind: &str, body: &[u8], extra: &[(&str, &str)], head: bool) -> io::Result<()> {
+    let mut response = format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, kind, body.len());
+    for (name, value) in extra {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    out.write_all(response.as_bytes())?;
+    if !head {
+        out.write_all(body)?;
+    }
+    out.flush()
+}
+
+fn serve_get(server: &Server, req: &Request, out: &mut TcpStream, head: bool) -> io::Result<()> {
+    let (path, base) = match server.root.resolve(&req.parts) {
+        Target::Path(path, base) => (path, base),
+        Target::Missing => return respond(out, "404 Not Found", TEXT, b"Not found\n", &[], head),
+        Target::Picks => {
+            let Root::Picks(picks) = &server.root else {
+                return Ok(());
+            };
+            let entries = picks.iter().filter_map(|p| entry(p, p.file_name()?.to_string_lossy().to_string())).collect();
+            let page = index_page(&req.parts, entries, false);
+            return respond(out, "200 OK", "text/html; charset=utf-8", page.as_bytes(), &[], head);
+        }
+    };
+    let Ok(meta) = fs::metadata(&path) else {
+        return respond(out, "404 Not Found", TEXT, b"Not found\n", &[], head);
+    };
+    if meta.is_dir() {
+        if !req.raw_path.ends_with('/') {
+            let location = format!("{}/", req.raw_path);
+            return respond(out, "301 Moved Permanently", TEXT, b"", &[("Location", &location)], head);
+        }
+        let listing = match fs::read_dir(&path) {
+            Ok(listing) => listing,
+            Err(e) => return respond(out, "403 Forbidden", TEXT, format!("{}\n", e).as_bytes(), &[], head),
+        };
+        let entries = listing
+            .flatten()
+            .filter(|e| contained(&base, &e.path()).is_some())
+            .filter_map(|e| entry(&e.path(), e.file_name().to_string_lossy().to_string()))
+            .collect();
+        let page = index_page(&req.parts, entries, server.upload);
+        return respond(out, "200 OK", "text/html; charset=utf-8", page.as_bytes(), &[], head);
+    }
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) => return respond(out, "403 Forbidden", TEXT, format!("{}\n", e).as_bytes(), &[], head),
+    };
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        content_type(&path),
+        meta.len()
+    );
+    out.write_all(header.as_bytes())?;
+    if head {
+        return out.flush();
+    }
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        if server.stop.load(Ordering::Relaxed) {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return out.flush();
+        }
+        out.write_all(&buf[..n])?;
+        server.stats.sent.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+/// `PUT /папка/имя` — новый файл; существующие не перезаписываются
+fn serve_put(server: &Server, req: &Request, reader: &mut BufReader<TcpStream>, out: &mut TcpStream, peer: &str) -> io::Result<()> {
+    let Some((name, dir_parts)) = req.parts.split_last() else {
+        return respond(out, "400 Bad Request", TEXT, b"File name missing\n", &[], false);
+    };
+    let dir = match server.root.resolve(dir_parts) {
+        Target::Path(dir, _) if dir.is_dir() => dir,
+        _ => return respond(out, "404 Not Found", TEXT, b"No such folder\n", &[], false),
+    };
+    let Some(len) = req.header("content-length").and_then(|v| v.parse::<u64>().ok()) else {
+        return respond(out, "411 Length Required", TEXT, b"Content-Length required\n", &[], false);
+    };
+    let target = dir.join(name);
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&target) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return respond(out, "409 Conflict", TEXT, format!("'{}' already exists\n", name).as_bytes(), &[], false);
+        }
+        Err(e) => return respond(out, "403 Forbidden", TEXT, format!("{}\n", e).as_bytes(), &[], false),
+    };
+    if req.header("expect").is_some_and(|v| v.eq_ignore_ascii_case("100-continue")) {
+        out.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+    let copied = io::copy(&mut reader.by_ref().take(len), &mut file);
+    drop(file);
+    if copied.as_ref().ok() != Some(&len) {
+        // Оборванная загрузка не должна выглядеть готовым файлом
+        let _ = fs::remove_file(&target);
+        return respond(out, "400 Bad Request", TEXT, b"Upload interrupted\n", &[], false);
+    }
+    if let Ok(mut last) = server.stats.last_upload.lock() {
+        *last = format!("{} from {}", req.parts.join("/"), peer);
+    }
+    server.stats.uploads.fetch_add(1, Ordering::Relaxed);
+    respond(out, "201 Created", TEXT, b"Uploaded\n", &[], false)
+}
+
+fn entry(path: &Path, name: String) -> Option<Entry> {
+    let meta = fs::metadata(path).ok()?;
+    Some(Entry { name, is_dir: meta.is_dir(), size: meta.len(), modified: meta.modified().ok() })
+}
+
+fn index_page(parts: &[String], mut entries: Vec<Entry>, upload: bool) -> String {
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    let title = html_escape(&format!("/{}", parts.iter().map(|p| format!("{}/", p)).collect::<String>()));
+    let mut page = format!(
+        "<!doctype html><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width\"><title>{0}</title>\n\
+         <style>body{{font-family:sans-serif;margin:1.5em}}td{{padding:.2em 1.5em .2em 0}}a{{text-decoration:none}}</style>\n\
+         <h2>{0}</h2>\n<table>\n",
+        title
+    );
+    if !parts.is_empty() {
+        page.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for e in &entries {
+        let slash = if e.is_dir { "/" } else { "" };
+        let (size, download) = if e.is_dir { (String::new(), "") } else { (crate::ui::format_size(e.size), " download") };
+        let date = e.modified.map_or(String::new(), |time| {
+            let (y, m, d, h, min, _) = crate::ui::date_parts(time);
+            format!("{:04}-{:02}-{:02} {:02}:{:02}", y, m, d, h, min)
+        });
+        page.push_str(&format!(
+            "<tr><td><a href=\"{}{}\"{}>{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            percent_encode(&e.name),
+            slash,
+            download,
+            html_escape(&e.name),
+            slash,
+            size,
+            date
+        ));
+    }
+    page.push_str("</table>\n");
+    if upload {
+        page.push_str(
+            "<p><input type=\"file\" id=\"f\" multiple> <button onclick=\"up()\">Upload</button> <span id=\"s\"></span>\n\
+             <script>async function up(){const s=document.getElementById('s');\
+             for(const f of document.getElementById('f').files){s.textContent='Uploading '+f.name+'…';\
+             const r=await fetch(encodeURIComponent(f.name),{method:'PUT',body:f});\
+             if(!r.ok){s.textContent=f.name+': '+await r.text();return}}location.reload()}</script>\n",
+        );
+    }
+    page
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "toml" | "log" | "csv" | "py" | "sh" | "c" | "h" | "yaml" | "yml" => TEXT,
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+impl App {
+    /// `share [порт] [upload] [lan] [user]`, `share stop`; без аргументов при запущенной раздаче — окно с QR.
+    /// С `user` сначала спрашивается пароль.
+    pub fn share_command(&mut self, args: &[String]) {
+        if args.first().map(String::as_str) == Some("stop") {
+            return self.stop_share();
+        }
+        if let Some(job) = self.share.as_ref() {
+            if args.is_empty() {
+                self.share_popup = true;
+            } else {
+                self.message = format!("Already sharing at {} (`:share stop` first)", job.url);
+            }
+            return;
+        }
+        let (mut port, mut upload, mut lan, mut user) = (self.config.share_port, false, false, None);
+        for arg in args {
+            match arg.as_str() {
+                "upload" => upload = true,
+                "lan" => lan = true,
+                a if a.contains(':') => {
+                    self.message = "Give only the user name; the password is asked for separately".to_string();
+                    return;
+                }
+                a => match a.parse() {
+                    Ok(p) => port = p,
+                    Err(_) if user.is_none() && !a.starts_with(|c: char| c.is_ascii_digit()) => user = Some(a.to_string()),
+                    Err(_) => {
+                        self.message = format!("Usage: share [port] [upload] [lan] [user] | share stop (bad argument '{}')", a);
+                        return;
+                    }
+                },
+            }
+        }
+        if !self.fs_in(&self.current_dir).is_local() {
+            self.message = "Only local folders can be shared".to_string();
+            return;
+        }
+        match user {
+            Some(user) => self.share_prompt = Some(SharePrompt { user, password: String::new(), port, upload, lan }),
+            None => self.start_share(port, upload, lan, None),
+        }
+    }
+
+    fn start_share(&mut self, port: u16, upload: bool, lan: bool, creds: Option<(&str, &str)>) {
+        let mut indices: Vec<usize> = self.selected_indices.iter().copied().collect();
+        indices.sort_unstable();
+        let picks: Vec<PathBuf> = indices.iter().filter_map(|&i| self.filtered_items.get(i).cloned()).collect();
+        let (root, label) = if picks.is_empty() {
+            (Root::Dir(self.current_dir.clone()), self.current_dir.display().to_string())
+        } else {
+            (Root::Picks(picks.clone()), format!("{} selected item(s)", picks.len()))
+        };
+        match start(root, label, port, upload, lan, creds) {
+            Ok(job) => {
+                let scope = if lan { "" } else { " (this computer only; `:share lan` for the network)" };
+                self.message = format!("Sharing {} at {}{}", job.label, job.url, scope);
+                self.share = Some(job);
+                self.share_popup = true;
+            }
+            Err(e) => self.message = format!("Cannot share on port {}: {}", port, e),
+        }
+    }
+
+    /// Ввод пароля: символы не показываются и не попадают ни в историю, ни в запись макроса
+    pub fn handle_share_prompt_input(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.share_prompt.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.share_prompt = None;
+                self.message = "Share cancelled".to_string();
+            }
+            KeyCode::Enter if prompt.password.is_empty() => self.message = "Password is empty".to_string(),
+            KeyCode::Enter => {
+                if let Some(p) = self.share_prompt.take() {
+                    self.start_share(p.port, p.upload, p.lan, Some((&p.user, &p.password)));
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.password.pop();
+            }
+            KeyCode::Char(c) => prompt.password.push(c),
+            _ => {}
+        }
+    }
+
+    pub fn stop_share(&mut self) {
+        self.share_popup = false;
+        self.message = match self.share.take() {
+            Some(job) => format!("Stopped sharing {} ({} request(s))", job.url, job.stats.requests.load(Ordering::Relaxed)),
+            None => "Nothing is being shared".to_string(),
+        };
+    }
+
+    pub fn handle_share_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('s') => self.stop_share(),
+            KeyCode::Char('y') => {
+                if let Some(job) = self.share.as_ref() {
+                    self.message = match crate::system_clipboard::copy_text(&job.url) {
+                        Ok(via) => format!("Copied {} ({})", job.url, via),
+                        Err(e) => format!("Copy failed: {}", e),
+                    };
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.share_popup = false,
+            _ => {}
+        }
+    }
+
+    /// Сообщает о принятых файлах и обновляет список (вызывается из `tick`)
+    pub fn poll_share(&mut self) {
+        let Some(job) = self.share.as_mut() else {
+            return;
+        };
+        let uploads = job.stats.uploads.load(Ordering::Relaxed);
+        if uploads == job.seen_uploads {
+            return;
+        }
+        job.seen_uploads = uploads;
+        let last = job.stats.last_upload.lock().map(|l| l.clone()).unwrap_or_default();
+        self.message = format!("Received {}", last);
+        if job.dir.is_none() || job.dir.as_ref() == Some(&self.current_dir) {
+            self.refresh_items();
+            self.update_preview();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetch(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_index_downloads_and_uploads() {
+        let dir = std::env::temp_dir().join(format!("ffm_share_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub dir")).unwrap();
+        fs::write(dir.join("a&b.txt"), "hello").unwrap();
+
+        let job = start(Root::Dir(dir.clone()), String::new(), 0, true, false, Some(("me", "secret"))).unwrap();
+        assert!(job.url.starts_with("http://127.0.0.1:"));
+        let port: u16 = job.url.trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+        assert_eq!(job.user.as_deref(), Some("me"));
+        let wrong = format!("Authorization: Basic {}\r\n", crate::system_clipboard::base64(b"me:secreT"));
+        assert!(fetch(port, &format!("GET / HTTP/1.1\r\n{}\r\n", wrong)).starts_with("HTTP/1.1 401"));
+        let auth = format!("Authorization: Basic {}\r\n", crate::system_clipboard::base64(b"me:secret"));
+        assert!(fetch(port, "GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401"));
+
+        let index = fetch(port, &format!("GET / HTTP/1.1\r\n{}\r\n", auth));
+        assert!(index.starts_with("HTTP/1.1 200"));
+        assert!(index.contains("<a href=\"a%26b.txt\" download>a&amp;b.txt</a>"));
+        assert!(index.contains("<a href=\"sub%20dir/\">sub dir/</a>"));
+        assert!(fetch(port, &format!("GET /sub%20dir HTTP/1.1\r\n{}\r\n", auth)).contains("Location: /sub%20dir/"));
+        assert!(fetch(port, &format!("GET /a%26b.txt HTTP/1.1\r\n{}\r\n", auth)).ends_with("\r\n\r\nhello"));
+        assert!(fetch(port, &format!("GET /../etc/passwd HTTP/1.1\r\n{}\r\n", auth)).starts_with("HTTP/1.1 400"));
+
+        let put = |name: &str| fetch(port, &format!("PUT /sub%20dir/{} HTTP/1.1\r\n{}Content-Length: 3\r\n\r\nnew", name, auth));
+        assert!(put("n.txt").starts_with("HTTP/1.1 201"));
+        assert!(put("n.txt").starts_with("HTTP/1.1 409"));
+        assert_eq!(fs::read_to_string(dir.join("sub dir/n.txt")).unwrap(), "new");
+        assert_eq!(job.stats.uploads.load(Ordering::Relaxed), 1);
+
+        drop(job);
+        assert!(TcpStream::connect((Ipv4Addr::LOCALHOST, port)).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn encodes_the_address_as_qr() {
+        let qr = qr_modules("http://192.168.1.20:8000/").unwrap();
+        assert_eq!(qr.len(), 25); // 25 байт — версия 2
+        assert!(qr.iter().all(|row| row.len() == 25));
+        // Поисковый узор: тёмная рамка 7×7 со светлым кольцом и тёмным центром 3×3
+        assert!(qr[0][..7].iter().all(|&m| m) && !qr[1][1] && qr[3][3]);
+        assert_eq!(qr_modules(&"x".repeat(200)).unwrap().len(), 57);
+        assert!(qr_modules(&"x".repeat(300)).is_none());
+    }
+
+    #[test]
+    fn compares_in_constant_time() {
+        assert!(constant_time_eq(b"Basic abc", b"Basic abc"));
+        assert!(!constant_time_eq(b"Basic abc", b"Basic abd"));
+        assert!(!constant_time_eq(b"Basic ab", b"Basic abc"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn caps_connections() {
+        let dir = std::env::temp_dir().join(format!("ffm_share_cap_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let job = start(Root::Dir(dir.clone()), String::new(), 0, false, false, None).unwrap();
+        let port: u16 = job.url.trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+        // Молчащие клиенты занимают все места
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS).map(|_| TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap()).collect();
+        let started = std::time::Instant::now();
+        while job.stats.connections.load(Ordering::Relaxed) < MAX_CONNECTIONS && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        // Ответ уходит сразу при приёме, ещё до запроса
+        let mut refused = String::new();
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap().read_to_string(&mut refused).unwrap();
+        assert!(refused.starts_with("HTTP/1.1 503"), "{}", refused);
+        drop(idle);
+        let started = std::time::Instant::now();
+        while job.stats.connections.load(Ordering::Relaxed) > 0 && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(fetch(port, "GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200"));
+        drop(job);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn asks_for_the_password_separately() {
+        let dir = std::env::temp_dir().join(format!("ffm_share_prompt_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut app = App::with_config(crate::config::Config::default(), dir.clone());
+        app.refresh_items();
+        app.share_command(&["0".to_string(), "alice:pw".to_string()]);
+        assert!(app.share.is_none() && app.share_prompt.is_none());
+
+        app.share_command(&["0".to_string(), "alice".to_string()]);
+        assert_eq!(app.share_prompt.as_ref().map(|p| p.user.as_str()), Some("alice"));
+        let key = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        app.handle_key(key(KeyCode::Enter));
+        assert_eq!(app.message, "Password is empty");
+        for c in "s3cretx".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Backspace));
+        app.handle_key(key(KeyCode::Enter));
+        assert!(app.share_prompt.is_none());
+        let job = app.share.as_ref().unwrap();
+        assert_eq!(job.user.as_deref(), Some("alice"));
+        let port: u16 = job.url.trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+        let auth = crate::system_clipboard::base64(b"alice:s3cret");
+        assert!(fetch(port, &format!("GET / HTTP/1.1\r\nAuthorization: Basic {}\r\n\r\n", auth)).starts_with("HTTP/1.1 200"));
+        app.stop_share();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_do_not_leave_the_share() {
+        let base = std::env::temp_dir().join(format!("ffm_share_links_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let (dir, outside) = (base.join("shared"), base.join("outside"));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret.txt"), "secret").unwrap();
+        fs::write(dir.join("sub/in.txt"), "inside").unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), dir.join("secret.txt")).unwrap();
+        std::os::unix::fs::symlink("sub/in.txt", dir.join("in.txt")).unwrap();
+
+        let job = start(Root::Dir(dir.clone()), String::new(), 0, true, false, None).unwrap();
+        let port: u16 = job.url.trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+        let index = fetch(port, "GET / HTTP/1.1\r\n\r\n");
+        assert!(!index.contains("link/") && !index.contains("secret.txt"));
+        assert!(fetch(port, "GET /link/secret.txt HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+        assert!(fetch(port, "GET /secret.txt HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+        // Ссылки внутри раздачи работают
+        assert!(fetch(port, "GET /in.txt HTTP/1.1\r\n\r\n").ends_with("inside"));
+        let put = fetch(port, "PUT /link/planted.txt HTTP/1.1\r\nContent-Length: 3\r\n\r\nbad");
+        assert!(put.starts_with("HTTP/1.1 404"));
+        assert!(!outside.join("planted.txt").exists());
+
+        drop(job);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
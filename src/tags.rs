@@ -0,0 +1,471 @@
+use crate::app::{App, Focus};
+use ratatui::style::Color;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Атрибут, который читают другие программы (KDE, tmsu и т.п.): теги через запятую
+#[cfg(unix)]
+const XDG_TAGS: &str = "user.xdg.tags";
+
+/// Цвета для тегов без своего цвета в `[tag_colors]`
+const PALETTE: &[Color] = &[
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::Rgb(255, 150, 80),
+    Color::Rgb(150, 200, 120),
+    Color::Rgb(200, 160, 255),
+];
+
+/// Строка панели тегов: заголовок тега или файл под ним
+#[derive(Clone, PartialEq, Debug)]
+pub enum TagRow {
+    Tag(String, usize),
+    File(String, PathBuf),
+}
+
+/// База тегов: путь → теги. Хранится в `<data dir>/tags.txt`, строка `теги<TAB>путь`;
+/// в путях `\\`, TAB и переводы строк экранированы (файлы старого формата без заголовка читаются как есть).
+#[derive(Default)]
+pub struct TagDb {
+    map: BTreeMap<PathBuf, Vec<String>>,
+}
+
+/// Первая строка файла с экранированными путями
+const HEADER: &str = "# ffm tags v2";
+
+fn tags_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "ffm").map(|p| p.data_dir().join("tags.txt"))
+}
+
+/// `\\`, TAB, CR и LF в пути — двухсимвольные пары, чтобы строка базы оставалась одной строкой
+fn escape(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Обратно к `escape`; неизвестная пара остаётся как есть
+fn unescape(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Допустимое имя тега: без разделителей базы и пробелов
+pub fn tag_error(tag: &str) -> Option<String> {
+    if tag.is_empty() {
+        return Some("Tag name is empty".to_string());
+    }
+    tag.chars()
+        .find(|c| *c == ',' || c.is_whitespace() || c.is_control())
+        .map(|c| format!("Illegal character {:?} in tag '{}'", c, tag))
+}
+
+impl TagDb {
+    pub fn load() -> Self {
+        let mut db = TagDb::default();
+        if let Some(path) = tags_path()
+            && let Ok(content) = fs::read_to_string(path)
+        {
+            db.parse(&content);
+        }
+        db
+    }
+
+    fn parse(&mut self, content: &str) {
+        let escaped = content.lines().next() == Some(HEADER);
+        for line in content.lines() {
+            if let Some((tags, path)) = line.split_once('\t') {
+                let tags: Vec<String> = tags.split(',').filter(|t| !t.is_empty()).map(String::from).collect();
+                if !tags.is_empty() {
+                    let path = if escaped { unescape(path) } else { path.to_string() };
+                    self.map.insert(PathBuf::from(path), tags);
+                }
+            }
+        }
+    }
+
+    /// Текст файла и число путей, которые не UTF-8 и потому не записаны
+    fn serialize(&self) -> (String, usize) {
+        let mut out = format!("{}\n", HEADER);
+        let mut skipped = 0;
+        for (path, tags) in &self.map {
+            match path.to_str() {
+                Some(path) => out.push_str(&format!("{}\t{}\n", tags.join(","), escape(path))),
+                None => skipped += 1,
+            }
+        }
+        (out, skipped)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = tags_path().ok_or("No data directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let (content, skipped) = self.serialize();
+        fs::write(&path, content).map_err(|e| e.to_string())?;
+        if skipped > 0 {
+            return Err(format!("{} non-UTF-8 path(s) not saved, their tags last until exit", skipped));
+        }
+        Ok(())
+    }
+
+    pub fn tags_of(&self, path: &Path) -> &[String] {
+        self.map.get(path).map(|t| t.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Пустой `tag` — любой тег
+    pub fn has(&self, path: &Path, tag: &str) -> bool {
+        let tags = self.tags_of(path);
+        if tag.is_empty() { !tags.is_empty() } else { tags.iter().any(|t| t == tag) }
+    }
+
+    fn add(&mut self, path: &Path, tag: &str) -> bool {
+        let tags = self.map.entry(path.to_path_buf()).or_default();
+        if tags.iter().any(|t| t == tag) {
+            return false;
+        }
+        tags.push(tag.to_string());
+        true
+    }
+
+    /// Пустой `tag` снимает все теги
+    fn remove(&mut self, path: &Path, tag: &str) -> bool {
+        let Some(tags) = self.map.get_mut(path) else {
+            return false;
+        };
+        let before = tags.len();
+        tags.retain(|t| !tag.is_empty() && t != tag);
+        let changed = tags.len() != before;
+        if tags.is_empty() {
+            self.map.remove(path);
+        }
+        changed
+    }
+
+    /// Теги следуют за переименованными и перемещёнными путями (и за содержимым каталогов)
+    fn moved(&mut self, pairs: &[(PathBuf, PathBuf)]) -> bool {
+        let mut moved = Vec::new();
+        for (old, new) in pairs {
+            let keys: Vec<PathBuf> = self.map.keys().filter(|p| p.starts_with(old)).cloned().collect();
+            for key in keys {
+                if let (Some(tags), Ok(rest)) = (self.map.remove(&key), key.strip_prefix(old)) {
+                    let target = if rest.as_os_str().is_empty() { new.clone() } else { new.join(rest) };
+                    moved.push((target, tags));
+                }
+            }
+        }
+        let changed = !moved.is_empty();
+        // Вставка после удаления всех старых ключей — обмены a↔b не затирают друг друга
+        self.map.extend(moved);
+        changed
+    }
+
+    /// Все теги с их файлами
+    pub fn by_tag(&self) -> BTreeMap<&str, Vec<&Path>> {
+        let mut out: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+        for (path, tags) in &self.map {
+            for tag in tags {
+                out.entry(tag).or_default().push(path);
+            }
+        }
+        out
+    }
+}
+
+/// Делит запрос на термы `tag:имя` и остальной текст для нечёткого поиска
+pub fn split_query(query: &str) -> (Vec<&str>, String) {
+    let mut tags = Vec::new();
+    let mut rest = Vec::new();
+    for word in query.split_whitespace() {
+        match word.strip_prefix("tag:") {
+            Some(tag) => tags.push(tag),
+            None => rest.push(word),
+        }
+    }
+    (tags, rest.join(" "))
+}
+
+#[cfg(unix)]
+fn write_xattr(path: &Path, tags: &[String]) -> std::io::Result<()> {
+    if tags.is_empty() {
+        match crate::xattr::remove(path, XDG_TAGS) {
+            Err(e) if e.raw_os_error() == Some(libc::ENODATA) => Ok(()),
+            res => res,
+        }
+    } else {
+        crate::xattr::set(path, XDG_TAGS, tags.join(",").as_bytes())
+    }
+}
+
+impl App {
+    /// Цвет тега: из `[tag_colors]`, иначе стабильный цвет из палитры по имени
+    pub fn tag_color(&self, tag: &str) -> Color {
+        if let Some(color) = self.config.tag_colors.get(tag) {
+            return crate::theme::parse_color(color);
+        }
+        let hash = tag.bytes().fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+        PALETTE[hash % PALETTE.len()]
+    }
+
+    /// `:tag` — добавить (`name`, `+name`) или снять (`-name`) теги у выделенного
+    pub fn tag_command(&mut self, args: &[String]) {
+        let paths = self.bulk_targets_or_highlighted();
+        if paths.is_empty() {
+            return;
+        }
+        let mut ops = Vec::new();
+        for arg in args {
+            let (add, name) = match arg.strip_prefix('-') {
+                Some(name) => (false, name),
+                None => (true, arg.strip_prefix('+').unwrap_or(arg)),
+            };
+            if let Some(err) = tag_error(name) {
+                self.message = err;
+                return;
+            }
+            ops.push((add, name));
+        }
+
+        let mut changed = false;
+        for path in &paths {
+            for (add, name) in &ops {
+                changed |= if *add { self.tags.add(path, name) } else { self.tags.remove(path, name) };
+            }
+        }
+        self.message = match self.store_tags(&paths, changed) {
+            Err(e) => format!("Tags: {}", e),
+            Ok(_) if paths.len() == 1 => {
+                let tags = self.tags.tags_of(&paths[0]);
+                if tags.is_empty() { "No tags".to_string() } else { format!("Tags: {}", tags.join(", ")) }
+            }
+            Ok(_) => format!("Tagged {} item(s)", paths.len()),
+        };
+    }
+
+    /// `:untag [name]` — снять тег (или все) с выделенного
+    pub fn untag_command(&mut self, tag: Option<&str>) {
+        let paths = self.bulk_targets_or_highlighted();
+        let mut changed = false;
+        for path in &paths {
+            changed |= self.tags.remove(path, tag.unwrap_or(""));
+        }
+        self.message = match self.store_tags(&paths, changed) {
+            Err(e) => format!("Tags: {}", e),
+            Ok(_) if changed => format!("Untagged {} item(s)", paths.len()),
+            Ok(_) => "Nothing to untag".to_string(),
+        };
+    }
+
+    pub fn bulk_targets_or_highlighted(&self) -> Vec<PathBuf> {
+        if self.focus == Focus::Tags {
+            return self.selected_tag_row().and_then(|row| match row {
+                TagRow::File(_, path) => Some(path),
+                TagRow::Tag(..) => None,
+            }).into_iter().collect();
+        }
+        let mut indices: Vec<usize> = self.selected_indices.iter().copied().collect();
+        indices.sort_unstable();
+        if indices.is_empty() {
+            self.state.selected().and_then(|i| self.filtered_items.get(i)).cloned().into_iter().collect()
+        } else {
+            indices.iter().filter_map(|&i| self.filtered_items.get(i).cloned()).collect()
+        }
+    }
+
+    /// Сохраняет базу и, если включено, дублирует теги в xattr
+    fn store_tags(&mut self, paths: &[PathBuf], changed: bool) -> Result<(), String> {
+        if !changed {
+            return Ok(());
+        }
+        self.tags.save()?;
+        #[cfg(unix)]
+        if self.config.tags_xattr {
+            for path in paths {
+                write_xattr(path, self.tags.tags_of(path)).map_err(|e| format!("xattr on {}: {}", path.display(), e))?;
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = paths;
+        self.clamp_tag_selection();
+        Ok(())
+    }
+
+    /// Вызывается после переименований и перемещений внутри ffm
+    pub fn tags_moved(&mut self, pairs: &[(PathBuf, PathBuf)]) {
+        if self.tags.moved(pairs)
+            && let Err(e) = self.tags.save()
+        {
+            self.message = format!("Tags: {}", e);
+        }
+    }
+
+    // --- ПАНЕЛЬ ТЕГОВ ---
+
+    /// Строки панели: тег, под ним его файлы (если тег развёрнут)
+    pub fn tag_rows(&self) -> Vec<TagRow> {
+        let mut rows = Vec::new();
+        for (tag, paths) in self.tags.by_tag() {
+            rows.push(TagRow::Tag(tag.to_string(), paths.len()));
+            if !self.collapsed_tags.contains(tag) {
+                rows.extend(paths.into_iter().map(|p| TagRow::File(tag.to_string(), p.to_path_buf())));
+            }
+        }
+        rows
+    }
+
+    fn selected_tag_row(&self) -> Option<TagRow> {
+        self.tag_rows().into_iter().nth(self.tags_state.selected()?)
+    }
+
+    fn clamp_tag_selection(&mut self) {
+        let len = self.tag_rows().len();
+        self.tags_state.select(match self.tags_state.selected() {
+            _ if len == 0 => None,
+            Some(i) => Some(i.min(len - 1)),
+            None => Some(0),
+        });
+    }
+
+    pub fn next_tag_row(&mut self) {
+        let len = self.tag_rows().len();
+        if len > 0 {
+            self.tags_state.select(Some(self.tags_state.selected().map_or(0, |i| (i + 1) % len)));
+        }
+    }
+
+    pub fn previous_tag_row(&mut self) {
+        let len = self.tag_rows().len();
+        if len > 0 {
+            self.tags_state.select(Some(self.tags_state.selected().map_or(0, |i| (i + len - 1) % len)));
+        }
+    }
+
+    /// Enter: на теге — свернуть/развернуть, на файле — перейти к нему
+    pub fn open_tag_row(&mut self) {
+        match self.selected_tag_row() {
+            Some(TagRow::Tag(tag, _)) if !self.collapsed_tags.remove(&tag) => {
+                self.collapsed_tags.insert(tag);
+            }
+            Some(TagRow::Tag(..)) => {}
+            Some(TagRow::File(_, path)) if fs::symlink_metadata(&path).is_ok() => self.reveal_path(&path),
+            Some(TagRow::File(_, path)) => self.message = format!("Missing: {}", path.display()),
+            None => {}
+        }
+    }
+
+    /// Delete в панели: снять тег с файла, на заголовке — удалить тег у всех файлов
+    pub fn remove_tag_row(&mut self) {
+        let (paths, tag) = match self.selected_tag_row() {
+            Some(TagRow::File(tag, path)) => (vec![path], tag),
+            Some(TagRow::Tag(tag, _)) => {
+                let paths = self.tags.by_tag().get(tag.as_str()).map(|p| p.iter().map(|p| p.to_path_buf()).collect()).unwrap_or_default();
+                (paths, tag)
+            }
+            None => return,
+        };
+        for path in &paths {
+            self.tags.remove(path, &tag);
+        }
+        self.message = match self.store_tags(&paths, true) {
+            Err(e) => format!("Tags: {}", e),
+            Ok(_) => format!("Removed tag '{}' from {} item(s)", tag, paths.len()),
+        };
+    }
+
+    /// Имена всех тегов — для автодополнения
+    pub fn tag_names(&self) -> Vec<String> {
+        self.tags.by_tag().keys().map(|t| t.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn db_round_trip_and_moves() {
+        let mut db = TagDb::default();
+        db.parse("review,todo\t/a/b.txt\ndone\t/a/dir\ndone\t/a/dir/inner.rs\n");
+        assert!(db.has(Path::new("/a/b.txt"), "todo"));
+        assert!(db.has(Path::new("/a/dir"), ""));
+        assert!(!db.has(Path::new("/a/c"), ""));
+        assert_eq!(db.by_tag()["done"].len(), 2);
+
+        db.moved(&[(PathBuf::from("/a/dir"), PathBuf::from("/b/dir2")), (PathBuf::from("/a/b.txt"), PathBuf::from("/a/dir"))]);
+        assert_eq!(db.tags_of(Path::new("/b/dir2/inner.rs")), ["done"]);
+        assert_eq!(db.tags_of(Path::new("/a/dir")), ["review", "todo"]);
+
+        assert!(db.remove(Path::new("/a/dir"), "review"));
+        assert!(db.remove(Path::new("/a/dir"), ""));
+        assert!(db.tags_of(Path::new("/a/dir")).is_empty());
+        assert_eq!(db.serialize().0, "# ffm tags v2\ndone\t/b/dir2\ndone\t/b/dir2/inner.rs\n");
+    }
+
+    #[test]
+    fn odd_paths_survive_save() {
+        let mut db = TagDb::default();
+        for name in ["/a/line\nbreak", "/a/tab\there", "/a/back\\slash\\n", "/a/cr\r"] {
+            db.add(Path::new(name), "x");
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            db.add(Path::new(std::ffi::OsStr::from_bytes(b"/a/bad\xff")), "x");
+        }
+        let (content, skipped) = db.serialize();
+        assert_eq!(skipped, if cfg!(unix) { 1 } else { 0 });
+        assert_eq!(content.lines().count(), 5);
+
+        let mut back = TagDb::default();
+        back.parse(&content);
+        assert_eq!(back.map.len(), 4);
+        for path in back.map.keys() {
+            assert!(db.has(path, "x"), "{:?}", path);
+        }
+
+        // Старый файл без заголовка: обратные слэши — часть пути
+        let mut old = TagDb::default();
+        old.parse("x\tC:\\new\\tmp\n");
+        assert!(old.has(Path::new("C:\\new\\tmp"), "x"));
+    }
+
+    #[test]
+    fn splits_tag_terms() {
+        assert_eq!(split_query("tag:review main"), (vec!["review"], "main".to_string()));
+        assert_eq!(split_query("tag: tag:done"), (vec!["", "done"], String::new()));
+        assert!(tag_error("a,b").is_some());
+    }
+}
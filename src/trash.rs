@@ -0,0 +1,168 @@
+//! Перемещение в корзину: freedesktop.org Trash на Linux/BSD, `~/.Trash` на macOS.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Кладёт файл или каталог в корзину, возвращает новое место
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let home = directories::BaseDirs::new()
+        .map(|d| d.data_dir().join("Trash"))
+        .ok_or_else(|| io::Error::other("no home directory"))?;
+    match move_into(&home, &path, &path, false) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            // Другой диск: корзина `$topdir/.Trash-$uid`, путь в .trashinfo — относительно topdir
+            let top = mount_top(&path)?;
+            let trash_dir = top.join(format!(".Trash-{}", unsafe { libc::getuid() }));
+            let relative = path.strip_prefix(&top).unwrap_or(&path).to_path_buf();
+            move_into(&trash_dir, &path, &relative, true)
+        }
+        other => other,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let home = directories::BaseDirs::new().ok_or_else(|| io::Error::other("no home directory"))?;
+    let dir = home.home_dir().join(".Trash");
+    let name = path.file_name().ok_or_else(|| io::Error::other("no file name"))?;
+    let dest = dir.join(name);
+    let dest = if dest.exists() { crate::app::find_available_name(&dest) } else { dest };
+    fs::rename(path, &dest)?;
+    Ok(dest)
+}
+
+#[cfg(not(unix))]
+pub fn trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "trash is not supported on this platform"))
+}
+
+/// Папка корзины должна быть настоящей папкой текущего пользователя; `$topdir/.Trash-$uid`
+/// ещё и с правами 0700 — иначе её могли подменить (например, ссылкой на чужом общем диске)
+#[cfg(all(unix, not(target_os = "macos")))]
+fn check_trash_dir(dir: &Path, private: bool) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = fs::symlink_metadata(dir)?;
+    if !meta.file_type().is_dir() || meta.uid() != unsafe { libc::getuid() } || (private && meta.mode() & 0o777 != 0o700) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a private trash folder", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Резервирует имя через `info/<name>.trashinfo` (create_new), потом переносит файл.
+/// `private` — корзина на другом диске, `$topdir/.Trash-$uid`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn move_into(trash_dir: &Path, path: &Path, recorded: &Path, private: bool) -> io::Result<PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::DirBuilderExt;
+    let files = trash_dir.join("files");
+    let info = trash_dir.join("info");
+    for dir in [trash_dir, files.as_path(), info.as_path()] {
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+            _ => {}
+        }
+        check_trash_dir(dir, private && dir == trash_dir)?;
+    }
+    let name = path.file_name().ok_or_else(|| io::Error::other("no file name"))?.to_string_lossy().to_string();
+    let (y, mo, d, h, mi, s) = crate::ui::date_parts(std::time::SystemTime::now());
+    let body = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={:04}-{:02}-{:02}T{:02}:{:02}:{:02}\n",
+        percent_encode(recorded),
+        y, mo, d, h, mi, s
+    );
+    for n in 1..10000 {
+        let candidate = if n == 1 { name.clone() } else { format!("{}.{}", name, n) };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let dest = files.join(&candidate);
+        let res = file.write_all(body.as_bytes()).and_then(|_| fs::rename(path, &dest));
+        return match res {
+            Ok(_) => Ok(dest),
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(e)
+            }
+        };
+    }
+    Err(io::Error::other("trash is full of files with this name"))
+}
+
+/// Корень файловой системы, на которой лежит `path`
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_top(path: &Path) -> io::Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+    let dev = fs::symlink_metadata(path)?.dev();
+    let mut top = path.parent().unwrap_or(path).to_path_buf();
+    while let Some(parent) = top.parent() {
+        if fs::metadata(parent).map(|m| m.dev()).ok() != Some(dev) {
+            break;
+        }
+        top = parent.to_path_buf();
+    }
+    Ok(top)
+}
+
+/// Экранирование пути для .trashinfo: всё, кроме безопасных символов и `/`
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn percent_encode(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.to_string_lossy().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_trashinfo_paths() {
+        assert_eq!(percent_encode(Path::new("/home/u/my file%.txt")), "/home/u/my%20file%25.txt");
+        assert_eq!(percent_encode(Path::new("/tmp/ж")), "/tmp/%D0%B6");
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn refuses_planted_trash_folders() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("ffm_trash_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("elsewhere")).unwrap();
+        let victim = dir.join("victim.txt");
+        fs::write(&victim, "x").unwrap();
+
+        // Ссылка вместо папки корзины
+        let planted = dir.join(".Trash-link");
+        std::os::unix::fs::symlink(dir.join("elsewhere"), &planted).unwrap();
+        assert!(move_into(&planted, &victim, Path::new("victim.txt"), true).is_err());
+        assert!(fs::read_dir(dir.join("elsewhere")).unwrap().next().is_none());
+
+        // Папка, открытая другим
+        let open = dir.join(".Trash-open");
+        fs::create_dir(&open).unwrap();
+        fs::set_permissions(&open, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(move_into(&open, &victim, Path::new("victim.txt"), true).is_err());
+        assert!(victim.exists());
+
+        let good = dir.join(".Trash-good");
+        let moved = move_into(&good, &victim, Path::new("victim.txt"), true).unwrap();
+        assert_eq!(moved, good.join("files/victim.txt"));
+        assert!(good.join("info/victim.txt.trashinfo").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,9 @@
-use crate::app::{App, ClipboardOp, Focus, InputMode, SortMode};
+use crate::app::{App, ClipboardOp, Focus, InputMode, SortMode, fuzzy_match};
 use crate::icons::{get_icon, get_icon_color};
+use crate::commands::describe;
+use crate::keymap::{Resolution, display_key, key_label};
+use crate::line_edit::LineEditor;
+use crate::tags::TagRow;
 use crate::theme::parse_color;
 use ratatui::{
     Frame,
@@ -8,6 +12,7 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
+use std::fs;
 use std::time::UNIX_EPOCH;
 
 pub fn render(f: &mut Frame, app: &mut App) {
@@ -57,12 +62,13 @@ pub fn render(f: &mut Frame, app: &mut App) {
         ])
         .split(vertical_chunks[0]);
 
-    // Левая панель: Избранное сверху, Диски снизу
+    // Левая панель: Избранное сверху, Теги посередине, Диски снизу
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
+            Constraint::Percentage(35),
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
         ])
         .split(main_chunks[0]);
 
@@ -75,7 +81,7 @@ pub fn render(f: &mut Frame, app: &mut App) {
                 .file_name()
                 .unwrap_or(path.as_os_str())
                 .to_string_lossy();
-            let icon = if path.is_dir() { "󰉒" } else { "󰈔" };
+            let icon = if path.is_dir() || crate::sftp::is_remote(path) { "󰉒" } else { "󰈔" };
             ListItem::new(format!("{} {}", icon, name))
                 .style(Style::default().fg(Color::Rgb(210, 180, 255)).bg(bg_color))
         })
@@ -112,6 +118,61 @@ pub fn render(f: &mut Frame, app: &mut App) {
 
     f.render_stateful_widget(fav_list, left_chunks[0], &mut app.favorites_state);
 
+    // --- ПАНЕЛЬ ТЕГОВ (СЛЕВА ПОСЕРЕДИНЕ) ---
+    let tag_items: Vec<ListItem> = if app.tags.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(
+            format!("No tags — press {} to tag", display_key(&app.config.keys.tag)),
+            Style::default().fg(Color::DarkGray),
+        )))]
+    } else {
+        app.tag_rows()
+            .into_iter()
+            .map(|row| match row {
+                TagRow::Tag(tag, count) => {
+                    let arrow = if app.collapsed_tags.contains(&tag) { "▸" } else { "▾" };
+                    let color = app.tag_color(&tag);
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} ● ", arrow), Style::default().fg(color)),
+                        Span::styled(tag, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                        Span::styled(format!(" ({})", count), Style::default().fg(Color::DarkGray)),
+                    ]))
+                }
+                TagRow::File(_, path) => {
+                    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().to_string();
+                    // Файл мог быть удалён или лежать на отключённом диске
+                    let style = if fs::symlink_metadata(&path).is_ok() {
+                        Style::default().fg(text_color)
+                    } else {
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+                    };
+                    ListItem::new(Line::from(Span::styled(format!("    {}", name), style)))
+                }
+            })
+            .collect()
+    };
+
+    let tags_focused = app.focus == Focus::Tags;
+    let tag_list = List::new(tag_items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(" ● Tags ")
+                .border_style(if tags_focused {
+                    Style::default().fg(active_border_color).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(inactive_border_color)
+                })
+                .style(Style::default().bg(bg_color)),
+        )
+        .highlight_style(if tags_focused {
+            Style::default().bg(sel_bg).fg(sel_fg).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        });
+
+    f.render_stateful_widget(tag_list, left_chunks[1], &mut app.tags_state);
+
     // --- 2. ПАНЕЛЬ ДИСКОВ (СЛЕВА СНИЗУ) ---
     fn format_bytes(bytes: u64) -> String {
         const U: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -160,11 +221,12 @@ pub fn render(f: &mut Frame, app: &mut App) {
         .highlight_style(drive_highlight_style)
         .highlight_symbol(theme.highlight_symbol.as_str());
 
-    f.render_stateful_widget(drive_list, left_chunks[1], &mut app.drive_state);
+    f.render_stateful_widget(drive_list, left_chunks[2], &mut app.drive_state);
 
     // --- 3. ПАНЕЛЬ ФАЙЛОВ (ЦЕНТР) ---
     let panel_width = main_chunks[1].width as usize;
-    let name_max_width = panel_width.saturating_sub(26);
+    // Рамка, иконка, размер, дата и символ выделения; длинные `name -> target` обрезаются
+    let name_max_width = panel_width.saturating_sub(26 + theme.highlight_symbol.chars().count());
 
     let file_items: Vec<ListItem> = app
         .filtered_items
@@ -200,35 +262,71 @@ pub fn render(f: &mut Frame, app: &mut App) {
                 match app.clipboard.as_ref().map(|(_, op)| op) {
                     Some(ClipboardOp::Copy) => "[C]",
                     Some(ClipboardOp::Cut) => "[X]",
-                    None => "",
+                    Some(ClipboardOp::Extract) | None => "",
                 }
             } else {
                 ""
             };
 
             let (size_str, date_str) = if is_dir {
-                (String::new(), String::new())
+                // Размер папки — когда посчитан, спиннер — пока считается
+                let size = match meta {
+                    Some(m) if m.size_known => format_size(m.size),
+                    _ if app.dir_sizer.pending.contains(path) => spinner_frame().to_string(),
+                    _ => String::new(),
+                };
+                (size, String::new())
             } else if let Some(m) = meta {
                 (format_size(m.size), format_date(m.modified))
             } else {
                 (String::new(), String::new())
             };
 
-            let name_display = if name.len() > name_max_width {
-                let truncated: String = name.chars().take(name_max_width.saturating_sub(1)).collect();
+            // Ссылки: `name -> target`, битые — красным
+            let link_target = meta.and_then(|m| m.link_target.as_ref());
+            let broken = meta.is_some_and(|m| m.broken);
+            let full_name = match link_target {
+                Some(target) => format!("{} -> {}", name, target.display()),
+                None => name.to_string(),
+            };
+            // Цветные точки тегов после имени
+            let tags = app.tags.tags_of(path);
+            let name_width = name_max_width.saturating_sub(if tags.is_empty() { 0 } else { tags.len() + 1 });
+            let name_display = if full_name.chars().count() > name_width {
+                let truncated: String = full_name.chars().take(name_width.saturating_sub(1)).collect();
                 format!("{}…", truncated)
             } else {
-                name.to_string()
+                full_name
+            };
+            let (link_name, link_rest) = match name_display.find(" -> ") {
+                Some(pos) if link_target.is_some() => (name_display[..pos].to_string(), name_display[pos..].to_string()),
+                _ => (name_display.clone(), String::new()),
             };
+            let name_style = if broken {
+                Style::default().fg(Color::LightRed).add_modifier(Modifier::CROSSED_OUT)
+            } else if link_target.is_some() {
+                Style::default().fg(text_color).add_modifier(Modifier::ITALIC)
+            } else {
+                Style::default().fg(text_color)
+            };
+            let target_style = if broken { Style::default().fg(Color::LightRed) } else { Style::default().fg(Color::DarkGray) };
 
-            let line = Line::from(vec![
+            let mut spans = vec![
                 Span::styled(sel_mark, Style::default().fg(text_color)),
-                Span::styled(icon, Style::default().fg(icon_color)),
-                Span::styled(
-                    format!(" {} {} {:>8} {}", name_display, clipboard_mark, size_str, date_str),
-                    Style::default().fg(text_color),
-                ),
-            ]);
+                Span::styled(icon, Style::default().fg(if broken { Color::LightRed } else { icon_color })),
+                Span::raw(" "),
+                Span::styled(link_name, name_style),
+                Span::styled(link_rest, target_style),
+            ];
+            if !tags.is_empty() {
+                spans.push(Span::raw(" "));
+                spans.extend(tags.iter().map(|tag| Span::styled("●", Style::default().fg(app.tag_color(tag)))));
+            }
+            spans.push(Span::styled(
+                format!(" {} {:>8} {}", clipboard_mark, size_str, date_str),
+                Style::default().fg(text_color),
+            ));
+            let line = Line::from(spans);
             ListItem::new(line).style(Style::default().bg(bg_color))
         })
         .collect();
@@ -254,16 +352,20 @@ pub fn render(f: &mut Frame, app: &mut App) {
     let sel_info = if sel_count > 0 { format!(" ({})", sel_count) } else { String::new() };
 
     let list_title = if app.input_mode == InputMode::Search {
-        format!(" Search: {} ", app.search_query)
+        let width = (main_chunks[1].width as usize).saturating_sub(14);
+        let mut spans = vec![Span::raw(" Search: ")];
+        spans.extend(input_spans(&app.search_query, width, Style::default()));
+        spans.push(Span::raw(" "));
+        Line::from(spans)
     } else {
-        let path_str = app.current_dir.to_string_lossy();
+        let path_str = app.archive_title().unwrap_or_else(|| app.current_dir.to_string_lossy().to_string());
         let path_str = if path_str.len() > 50 {
             let start = path_str.len().saturating_sub(47);
             format!("…{}", &path_str[start..])
         } else {
             path_str.to_string()
         };
-        format!(" {}{} ", path_str, sel_info)
+        Line::from(format!(" {}{} ", path_str, sel_info))
     };
 
     let file_list = List::new(file_items)
@@ -314,6 +416,11 @@ pub fn render(f: &mut Frame, app: &mut App) {
                     format!(" │ 󰆐 Cut: {} items", n)
                 }
             }
+            Some((_, ClipboardOp::Extract)) | None => String::new(),
+        };
+
+        let share_hint = match &app.share {
+            Some(job) => format!(" │ 󰒍 Sharing {}", job.url),
             None => String::new(),
         };
 
@@ -327,30 +434,24 @@ pub fn render(f: &mut Frame, app: &mut App) {
             SortMode::Size => "Size",
             SortMode::Date => "Date",
         };
+        let rec_hint = match &app.macro_recording {
+            Some((reg, _)) => format!(" │ ● REC @{}", reg),
+            None => String::new(),
+        };
         let mode_text = match app.input_mode {
             InputMode::Normal => match app.focus {
                 Focus::FileList => format!(" FILES [{}]", sort_label),
                 Focus::DriveList => " DRIVES".to_string(),
                 Focus::Favorites => "★ FAVORITES".to_string(),
+                Focus::Tags => " TAGS".to_string(),
             },
             InputMode::Editing => " EDITING".to_string(),
             InputMode::Search => " SEARCH".to_string(),
             InputMode::Renaming => " RENAME".to_string(),
+            InputMode::Command => " COMMAND".to_string(),
         };
 
-        let keys_hint = match app.input_mode {
-            InputMode::Normal => match app.focus {
-                Focus::FileList => format!(
-                    "hjkl Nav │ Space Sel │ s Sort │ a New │ r Ren │ D Del │ {} Edit │ y Copy │ x Cut │ p Paste │ f Fav │ / Search │ ? Help │ Ctrl+B Bar",
-                    app.config.keys.edit
-                ),
-                Focus::DriveList => "jk Nav │ Enter Open │ Tab Switch │ ? Help │ Ctrl+B Bar".to_string(),
-                Focus::Favorites => "jk Nav │ Enter Open │ D Remove │ Tab Switch │ ? Help │ Ctrl+B Bar".to_string(),
-            },
-            InputMode::Editing => "Enter Save │ Esc Cancel".to_string(),
-            InputMode::Search => "Enter Confirm │ Esc Cancel │ ↑↓ Navigate".to_string(),
-            InputMode::Renaming => "Enter Confirm │ Esc Cancel".to_string(),
-        };
+        let keys_hint = status_hints(app);
 
         let msg = if app.message.is_empty() {
             String::new()
@@ -359,8 +460,8 @@ pub fn render(f: &mut Frame, app: &mut App) {
         };
 
         let status_text = format!(
-            " {} │ {}{}{}{} ",
-            mode_text, keys_hint, clipboard_hint, update_hint, msg
+            " {}{}{} │ {}{}{}{} ",
+            mode_text, rec_hint, share_hint, keys_hint, clipboard_hint, update_hint, msg
         );
         let footer = Paragraph::new(status_text)
             .style(Style::default().fg(text_color).bg(bg_color))
@@ -388,23 +489,67 @@ pub fn render(f: &mut Frame, app: &mut App) {
         f.render_widget(para, bar_rect);
     }
 
+    // Ширина текста внутри cmd_bar (рамка + поля)
+    let bar_width = (area.width as usize).saturating_sub(4);
+    let input_style = Style::default().fg(Color::Yellow);
+
     // Создание файла/папки
     if let InputMode::Editing = app.input_mode {
-        cmd_bar(f, area, " New  (end with / for folder) ", vec![
-            Line::from(Span::styled(app.input_buffer.clone(), Style::default().fg(Color::Yellow))),
+        let title = match app.create_template_name() {
+            Some(name) => format!(" New from template: {}  (Tab next) ", name),
+            None => " New  (end with / for folder, a, b for several, x_{1..3}) ".to_string(),
+        };
+        cmd_bar(f, area, &title, vec![
+            Line::from(input_spans(&app.create_input, bar_width, input_style)),
         ], text_color, bg_color);
     }
 
     // Переименование
     if let InputMode::Renaming = app.input_mode {
-        cmd_bar(f, area, " Rename ", vec![
-            Line::from(Span::styled(app.input_buffer.clone(), Style::default().fg(Color::Yellow))),
-        ], text_color, bg_color);
+        // Ошибка проверки имени показывается сразу, до Enter
+        let error = app.rename_error();
+        let width = if error.is_some() { bar_width * 2 / 3 } else { bar_width };
+        let mut spans = input_spans(&app.rename_input, width, input_style);
+        if let Some(err) = error {
+            spans.push(Span::styled(format!("   ✗ {}", err), Style::default().fg(Color::LightRed)));
+        }
+        cmd_bar(f, area, " Rename  (F2 stem/ext/all) ", vec![Line::from(spans)], text_color, bg_color);
+    }
+
+    // Командная строка
+    if let InputMode::Command = app.input_mode {
+        let mut spans = vec![Span::styled(":", Style::default().fg(sel_bg))];
+        // При подсказке справа оставляем строке ввода половину ширины
+        let hinted = app.completion.is_some() || crate::commands::spec_for(app.command_input.text()).is_some();
+        let width = if hinted { bar_width / 2 } else { bar_width.saturating_sub(1) };
+        spans.extend(input_spans(&app.command_input, width, input_style));
+        if let Some(c) = &app.completion {
+            let list: Vec<String> = c
+                .candidates
+                .iter()
+                .enumerate()
+                .map(|(i, s)| if i == c.index { format!("[{}]", s.trim_end()) } else { s.trim_end().to_string() })
+                .collect();
+            spans.push(Span::styled(format!("   {}", list.join("  ")), Style::default().fg(Color::DarkGray)));
+        } else if let Some(spec) = crate::commands::spec_for(app.command_input.text()) {
+            spans.push(Span::styled(
+                format!("   {} — {}", spec.usage, spec.description),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        cmd_bar(f, area, " Command ", vec![Line::from(spans)], text_color, bg_color);
+    }
+
+    // Очистка — до строки подтверждения, которая рисуется поверх
+    if app.cleanup.is_some() {
+        render_cleanup(f, area, app, bg_color, text_color, sel_bg, sel_fg);
     }
 
     // Подтверждение удаления
     if app.confirm_delete {
-        let msg = if !app.selected_indices.is_empty() {
+        let msg = if !app.pending_trash.is_empty() {
+            format!("Move {} item(s) to trash? (y/N)", app.pending_trash.len())
+        } else if !app.selected_indices.is_empty() {
             format!("Delete {} item(s)? (y/N)", app.selected_indices.len())
         } else if let Some(ref p) = app.pending_delete {
             format!("Delete '{}'? (y/N)", p.file_name().unwrap_or_default().to_string_lossy())
@@ -425,18 +570,634 @@ pub fn render(f: &mut Frame, app: &mut App) {
 
     // Попап помощи
     if app.show_help {
-        render_help_popup(f, area, bg_color, text_color, sel_bg, app.help_scroll);
+        render_help_popup(f, area, app, bg_color, text_color, sel_bg);
     }
+
+    // Which-key: продолжения набранного префикса
+    if app.which_key_visible() {
+        render_which_key(f, vertical_chunks[0], app);
+    }
+
+    // Подтверждение массового переименования
+    if app.bulk_rename.as_ref().is_some_and(|b| b.awaiting_confirm()) {
+        render_bulk_rename(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Переименование по шаблону
+    if app.pattern_rename.is_some() {
+        render_pattern_rename(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Свойства
+    #[cfg(unix)]
+    if app.properties.is_some() {
+        render_properties(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Расширенные атрибуты
+    #[cfg(unix)]
+    if app.xattrs.is_some() {
+        render_xattrs(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Анализ места
+    if app.disk_usage.is_some() {
+        render_disk_usage(f, area, app, bg_color, text_color, sel_bg, sel_fg);
+    }
+
+    // Дубликаты
+    if app.dupes.is_some() {
+        render_dupes(f, area, app, bg_color, text_color, sel_bg, sel_fg);
+    }
+
+    // Контрольные суммы
+    if app.checksums.is_some() {
+        render_checksums(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Раздача по HTTP
+    if app.share_popup {
+        render_share(f, area, app, bg_color, text_color, sel_bg);
+    }
+    if app.share_prompt.is_some() {
+        render_share_prompt(f, area, app, bg_color, text_color, sel_bg);
+    }
+
+    // Палитра команд
+    if app.palette.is_some() {
+        render_palette(f, area, app, bg_color, text_color, sel_bg, sel_fg);
+    }
+}
+
+/// Видимая часть поля ввода с курсором (инверсная ячейка) и выделением
+fn input_spans(input: &LineEditor, width: usize, style: Style) -> Vec<Span<'static>> {
+    let (visible, col) = input.view(width);
+    let offset = input.cursor_chars() - col;
+    let selected = input
+        .selection_chars()
+        .map(|(s, e)| (s.saturating_sub(offset), e.saturating_sub(offset)));
+    let cursor_style = style.add_modifier(Modifier::REVERSED);
+    let selection_style = style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD).bg(Color::DarkGray);
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut chars: Vec<char> = visible.chars().collect();
+    if col >= chars.len() {
+        chars.push(' ');
+    }
+    // Склеиваем соседние символы одного стиля
+    for (i, c) in chars.into_iter().enumerate() {
+        let s = if i == col {
+            cursor_style
+        } else if selected.is_some_and(|(start, end)| i >= start && i < end) {
+            selection_style
+        } else {
+            style
+        };
+        match spans.last_mut() {
+            Some(last) if last.style == s => last.content.to_mut().push(c),
+            _ => spans.push(Span::styled(c.to_string(), s)),
+        }
+    }
+    spans
 }
 
-fn render_help_popup(
+fn render_which_key(f: &mut Frame, area: Rect, app: &App) {
+    let Resolution::Pending(_, next) = app.keymap.resolve(&app.pending_keys) else {
+        return;
+    };
+    let theme = &app.config.theme;
+    let bg_color = parse_color(&theme.background);
+    let text_color = parse_color(&theme.text);
+    let accent = parse_color(&theme.selected_bg);
+    let key_color = parse_color(&theme.directory);
+
+    let lines: Vec<Line> = next
+        .iter()
+        .map(|(key, command)| {
+            let desc = match command {
+                Some(c) => describe(c),
+                None => "+prefix".to_string(),
+            };
+            Line::from(vec![
+                Span::styled(format!(" {:>5} ", display_key(key)), Style::default().fg(key_color).add_modifier(Modifier::BOLD)),
+                Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+                Span::styled(desc, Style::default().fg(text_color)),
+            ])
+        })
+        .collect();
+
+    let width = lines
+        .iter()
+        .map(|l| l.width() as u16 + 3)
+        .max()
+        .unwrap_or(20)
+        .max(24)
+        .min(area.width.saturating_sub(2));
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.right().saturating_sub(width + 1),
+        y: area.bottom().saturating_sub(height),
+        width,
+        height,
+    };
+
+    let typed: Vec<String> = app.pending_keys.iter().map(key_label).collect();
+    f.render_widget(Clear, popup);
+    let para = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" {} … ", typed.join(" ")))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+            .style(Style::default().bg(bg_color)),
+    );
+    f.render_widget(para, popup);
+}
+
+fn render_palette(
     f: &mut Frame,
     area: Rect,
+    app: &mut App,
     bg_color: Color,
     text_color: Color,
     accent: Color,
-    scroll: u16,
+    accent_fg: Color,
 ) {
+    let Some(palette) = app.palette.as_mut() else {
+        return;
+    };
+    let popup_area = centered_rect(60, 60, area);
+    f.render_widget(Clear, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(popup_area);
+
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(accent)),
+        Span::styled(palette.query.clone(), Style::default().fg(Color::Yellow)),
+    ]))
+    .block(
+        Block::default()
+            .title(" Command Palette ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+            .style(Style::default().bg(bg_color)),
+    );
+    f.render_widget(input, chunks[0]);
+
+    let width = chunks[1].width.saturating_sub(4) as usize;
+    let items: Vec<ListItem> = palette
+        .filtered
+        .iter()
+        .filter_map(|&i| palette.entries.get(i))
+        .map(|e| {
+            let keys = e.keys.join(" / ");
+            let desc_width = width.saturating_sub(21 + keys.chars().count() + 2);
+            let desc: String = if e.description.chars().count() > desc_width {
+                let truncated: String = e.description.chars().take(desc_width.saturating_sub(1)).collect();
+                format!("{}…", truncated)
+            } else {
+                e.description.clone()
+            };
+            let pad = width.saturating_sub(21 + desc.chars().count() + keys.chars().count());
+            Line::from(vec![
+                Span::styled(format!("{:<20} ", e.command), Style::default().fg(text_color).add_modifier(Modifier::BOLD)),
+                Span::styled(desc, Style::default().fg(text_color)),
+                Span::raw(" ".repeat(pad)),
+                Span::styled(keys, Style::default().fg(Color::Yellow)),
+            ])
+        })
+        .map(ListItem::new)
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(" {} actions ", palette.filtered.len()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(accent))
+                .style(Style::default().bg(bg_color)),
+        )
+        .highlight_style(Style::default().bg(accent).fg(accent_fg).add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(list, chunks[1], &mut palette.state);
+}
+
+/// Подсказки статус-бара из активной раскладки, по фокусу и режиму
+fn status_hints(app: &App) -> String {
+    let keymap = &app.keymap;
+    let hint = |command: &str, label: &str| keymap.key_for(command).map(|k| format!("{} {}", k, label));
+
+    // "hjkl Nav", если все четыре клавиши однобуквенные
+    let nav = |with_lr: bool| -> Option<String> {
+        let commands: &[&str] = if with_lr { &["back", "down", "up", "open"] } else { &["down", "up"] };
+        let keys: Vec<String> = commands.iter().filter_map(|c| keymap.key_for(c)).collect();
+        if keys.len() == commands.len() && keys.iter().all(|k| k.chars().count() == 1) {
+            Some(format!("{} Nav", keys.concat()))
+        } else {
+            Some("↑↓ Nav".to_string())
+        }
+    };
+
+    let parts: Vec<Option<String>> = match app.input_mode {
+        InputMode::Normal => match app.focus {
+            Focus::FileList => vec![
+                nav(true),
+                hint("select", "Sel"),
+                hint("sort", "Sort"),
+                hint("create", "New"),
+                hint("rename", "Ren"),
+                hint("delete", "Del"),
+                hint("edit", "Edit"),
+                hint("copy", "Copy"),
+                hint("cut", "Cut"),
+                hint("paste", "Paste"),
+                hint("fav", "Fav"),
+                hint("search", "Search"),
+                hint("command", "Cmd"),
+                hint("help", "Help"),
+                hint("set statusbar!", "Bar"),
+            ],
+            Focus::DriveList => vec![
+                nav(false),
+                hint("open", "Open"),
+                hint("focus next", "Switch"),
+                hint("help", "Help"),
+                hint("set statusbar!", "Bar"),
+            ],
+            Focus::Tags => vec![
+                nav(false),
+                hint("open", "Go/Fold"),
+                hint("delete", "Untag"),
+                hint("focus next", "Switch"),
+                hint("help", "Help"),
+                hint("set statusbar!", "Bar"),
+            ],
+            Focus::Favorites => vec![
+                nav(false),
+                hint("open", "Open"),
+                hint("delete", "Remove"),
+                hint("focus next", "Switch"),
+                hint("help", "Help"),
+                hint("set statusbar!", "Bar"),
+            ],
+        },
+        InputMode::Editing => vec![
+            Some("Enter Save".to_string()),
+            Some("Tab Template".to_string()),
+            Some("Esc Cancel".to_string()),
+        ],
+        InputMode::Search => vec![
+            Some(format!("Enter/{} Confirm", display_key(&app.config.keys.submit))),
+            Some(format!("{} Cancel", display_key(&app.config.keys.cancel))),
+            Some("↑↓ Navigate".to_string()),
+        ],
+        InputMode::Renaming => vec![
+            Some("Enter Confirm".to_string()),
+            Some("F2 Stem/Ext/All".to_string()),
+            Some("Esc Cancel".to_string()),
+        ],
+        InputMode::Command => vec![
+            Some("Enter Run".to_string()),
+            Some("Tab Complete".to_string()),
+            Some("↑↓ History".to_string()),
+            Some("Esc Cancel".to_string()),
+        ],
+    };
+    parts.into_iter().flatten().collect::<Vec<_>>().join(" │ ")
+}
+
+fn render_bulk_rename(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    let Some(bulk) = app.bulk_rename.as_ref() else {
+        return;
+    };
+    let popup_area = centered_rect(80, 70, area);
+    f.render_widget(Clear, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let changes: Vec<_> = bulk.changes().collect();
+    let old_width = changes
+        .iter()
+        .map(|(_, (src, _))| src.file_name().unwrap_or_default().to_string_lossy().chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(popup_area.width as usize / 2);
+
+    let mut lines: Vec<Line> = changes
+        .iter()
+        .map(|(i, (src, dst))| {
+            let old = src.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let new = dst.file_name().unwrap_or_default().to_string_lossy().to_string();
+            // ↻ — имя занято другим элементом пакета (обмен/цепочка)
+            let marker = if bulk.chained.contains(i) { " ↻" } else { "" };
+            Line::from(vec![
+                Span::styled(format!("  {:<width$}", old, width = old_width), Style::default().fg(Color::LightRed)),
+                Span::styled("  →  ", dim),
+                Span::styled(new, Style::default().fg(Color::LightGreen)),
+                Span::styled(marker, Style::default().fg(Color::Yellow)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  y/Enter Apply │ e Edit again │ ↑↓ Scroll │ n/Esc Cancel   (↻ swap/chain)",
+        dim,
+    )));
+
+    let unchanged = bulk.plan.len() - changes.len();
+    let para = Paragraph::new(lines)
+        .style(Style::default().fg(text_color))
+        .block(
+            Block::default()
+                .title(format!(" Rename {} item(s)? ({} unchanged) ", changes.len(), unchanged))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+                .style(Style::default().bg(bg_color)),
+        )
+        .scroll((bulk.scroll, 0));
+    f.render_widget(para, popup_area);
+}
+
+fn render_pattern_rename(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    use crate::pattern_rename::PatternField;
+    let Some(pattern) = app.pattern_rename.as_ref() else {
+        return;
+    };
+    let popup_area = centered_rect(80, 80, area);
+    f.render_widget(Clear, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(1)])
+        .split(popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let label_style = |field: PatternField| {
+        if pattern.field == field {
+            Style::default().fg(accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(text_color)
+        }
+    };
+    let input_width = (chunks[0].width as usize).saturating_sub(16);
+    let field_line = |field: PatternField, label: &str, input: &LineEditor, hint: &str| {
+        let mut spans = vec![Span::styled(format!(" {:<10} ", label), label_style(field))];
+        if pattern.field == field {
+            spans.extend(input_spans(input, input_width, Style::default().fg(Color::Yellow)));
+        } else if input.is_empty() {
+            spans.push(Span::styled(hint.to_string(), dim));
+        } else {
+            spans.push(Span::styled(input.text().to_string(), Style::default().fg(Color::Yellow)));
+        }
+        Line::from(spans)
+    };
+
+    let mut lines = vec![
+        field_line(PatternField::Find, "Find", &pattern.find, "regex on the name without extension (empty: whole name)"),
+        field_line(PatternField::Replace, "Replace", &pattern.replace, "$1 ${name}  {n:03} {n:03:0}  {date} {date:%Y%m%d} {time}  {stem} {ext}"),
+        field_line(PatternField::Extension, "Extension", &pattern.extension, "new extension, - to remove (empty: keep)"),
+        Line::from(vec![
+            Span::styled(format!(" {:<10} ", "Case"), label_style(PatternField::Case)),
+            Span::styled(pattern.case.label(), Style::default().fg(Color::Yellow)),
+            Span::styled("   Ctrl+T to cycle", dim),
+        ]),
+    ];
+    lines.push(match &pattern.pattern_error {
+        Some(err) => Line::from(Span::styled(format!(" ✗ {}", err), Style::default().fg(Color::LightRed))),
+        None => Line::from(Span::styled(" Tab Next field │ Enter Apply │ PgUp/PgDn Scroll │ Esc Cancel", dim)),
+    });
+
+    let title = format!(
+        " Pattern rename: {} item(s), {} change(s), {} conflict(s) ",
+        pattern.preview.len(),
+        pattern.changes(),
+        pattern.conflicts()
+    );
+    let form = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+            .style(Style::default().bg(bg_color)),
+    );
+    f.render_widget(form, chunks[0]);
+
+    let old_width = pattern
+        .preview
+        .iter()
+        .map(|r| r.old.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(chunks[1].width as usize / 3);
+    let rows: Vec<Line> = pattern
+        .preview
+        .iter()
+        .map(|row| {
+            let (new_style, arrow) = if row.error.is_some() {
+                (Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD), "  ✗  ")
+            } else if row.old == row.new {
+                (dim, "  =  ")
+            } else {
+                (Style::default().fg(Color::LightGreen), "  →  ")
+            };
+            let mut spans = vec![
+                Span::styled(format!(" {:<width$}", row.old, width = old_width), Style::default().fg(text_color)),
+                Span::styled(arrow, dim),
+                Span::styled(row.new.clone(), new_style),
+            ];
+            if let Some(err) = &row.error {
+                spans.push(Span::styled(format!("   {}", err), Style::default().fg(Color::LightRed)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    let table = Paragraph::new(rows)
+        .block(
+            Block::default()
+                .title(" Preview ")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(accent))
+                .style(Style::default().bg(bg_color)),
+        )
+        .scroll((pattern.scroll, 0));
+    f.render_widget(table, chunks[1]);
+}
+
+#[cfg(unix)]
+fn render_xattrs(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    use crate::xattr::display_value;
+    let Some(panel) = app.xattrs.as_ref() else {
+        return;
+    };
+    let popup_area = centered_rect(75, 80, area);
+    f.render_widget(Clear, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(panel.acl.len() as u16 + 2), Constraint::Length(4)])
+        .split(popup_area);
+    let dim = Style::default().fg(Color::DarkGray);
+    let value_style = Style::default().fg(Color::Yellow);
+    let block = |title: String, bold: bool| {
+        let border = if bold { Style::default().fg(accent).add_modifier(Modifier::BOLD) } else { Style::default().fg(accent) };
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(border)
+            .style(Style::default().bg(bg_color))
+    };
+
+    // Атрибуты: имя и значение, длинные значения обрезаются
+    let name_width = panel.entries.iter().map(|(n, _)| n.chars().count()).max().unwrap_or(0).min(chunks[0].width as usize / 3);
+    let value_width = (chunks[0].width as usize).saturating_sub(name_width + 8);
+    let mut lines: Vec<Line> = panel
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let mut shown = display_value(value, panel.hex).replace('\n', "⏎");
+            if shown.chars().count() > value_width {
+                shown = shown.chars().take(value_width.saturating_sub(1)).collect::<String>() + "…";
+            }
+            let row_style = if i == panel.selected { Style::default().bg(Color::DarkGray) } else { Style::default() };
+            Line::from(vec![
+                Span::styled(format!(" {:<width$}  ", name, width = name_width), row_style.fg(text_color)),
+                Span::styled(shown, row_style.fg(Color::Yellow)),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(" No extended attributes — press a to add one", dim)));
+    }
+    let selected = panel.selected as u16;
+    let visible = chunks[0].height.saturating_sub(2);
+    let scroll = selected.saturating_sub(visible.saturating_sub(1));
+    let name = panel.path.file_name().unwrap_or_default().to_string_lossy();
+    let mode = if panel.hex { "hex" } else { "text" };
+    let list = Paragraph::new(lines)
+        .block(block(format!(" Xattrs: {} ({}, {}) ", name, panel.entries.len(), mode), true))
+        .scroll((scroll, 0));
+    f.render_widget(list, chunks[0]);
+
+    let acl: Vec<Line> = panel
+        .acl
+        .iter()
+        .map(|l| Line::from(Span::styled(format!(" {}", l), Style::default().fg(text_color))))
+        .collect();
+    f.render_widget(Paragraph::new(acl).block(block(" ACL ".to_string(), false)), chunks[1]);
+
+    // Нижняя строка: форма редактирования, подтверждение или подсказки
+    let input_width = (chunks[2].width as usize).saturating_sub(12);
+    let footer: Vec<Line> = if let Some(edit) = &panel.edit {
+        let field = |label: &str, input: &LineEditor, active: bool| {
+            let label_style = if active { Style::default().fg(accent).add_modifier(Modifier::BOLD) } else { Style::default().fg(text_color) };
+            let mut spans = vec![Span::styled(format!(" {:<7} ", label), label_style)];
+            if active {
+                spans.extend(input_spans(input, input_width, value_style));
+            } else {
+                spans.push(Span::styled(input.text().to_string(), value_style));
+            }
+            Line::from(spans)
+        };
+        vec![field("Name", &edit.name, !edit.on_value), field("Value", &edit.value, edit.on_value)]
+    } else if panel.confirm_remove {
+        let name = panel.entries.get(panel.selected).map(|(n, _)| n.as_str()).unwrap_or("");
+        vec![Line::from(Span::styled(format!(" Remove {}? (y/N)", name), Style::default().fg(Color::LightRed)))]
+    } else {
+        vec![
+            Line::from(Span::styled(" a Add │ e/Enter Edit │ d Remove │ x Text/hex │ Esc Close", dim)),
+            Line::from(Span::styled(" Values starting with 0x are written as hex bytes", dim)),
+        ]
+    };
+    let title = if panel.edit.is_some() { " Edit (Tab switch field, Enter save, Esc cancel) " } else { "" };
+    f.render_widget(Paragraph::new(footer).block(block(title.to_string(), panel.edit.is_some())), chunks[2]);
+}
+
+#[cfg(unix)]
+fn render_properties(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    use crate::properties::PropField;
+    let Some(props) = app.properties.as_ref() else {
+        return;
+    };
+    let popup_area = centered_rect(70, 80, area);
+    f.render_widget(Clear, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let value_style = Style::default().fg(Color::Yellow);
+    let label_style = |field: PropField| {
+        if props.field == field {
+            Style::default().fg(accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(text_color)
+        }
+    };
+
+    let mut lines: Vec<Line> = props
+        .info
+        .iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!(" {:<10} ", label), dim),
+                Span::styled(value.clone(), Style::default().fg(text_color)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+
+    let input_width = (popup_area.width as usize).saturating_sub(16);
+    for (field, label, hint) in [
+        (PropField::Mode, "Mode", "755, u+x,go-w, a+X"),
+        (PropField::Owner, "Owner", "user name or uid"),
+        (PropField::Group, "Group", "group name or gid"),
+        (PropField::Accessed, "Accessed", "YYYY-MM-DD HH:MM:SS (UTC) or now"),
+        (PropField::Modified, "Modified", "YYYY-MM-DD HH:MM:SS (UTC) or now"),
+    ] {
+        let Some(input) = props.input(field) else {
+            continue;
+        };
+        let mut spans = vec![Span::styled(format!(" {:<10} ", label), label_style(field))];
+        if props.field == field {
+            spans.extend(input_spans(input, input_width, value_style));
+            spans.push(Span::styled(format!("  {}", hint), dim));
+        } else {
+            spans.push(Span::styled(input.text().to_string(), value_style));
+        }
+        lines.push(Line::from(spans));
+    }
+    let check = if props.recursive { "[x]" } else { "[ ]" };
+    lines.push(Line::from(vec![
+        Span::styled(format!(" {:<10} ", "Recursive"), label_style(PropField::Recursive)),
+        Span::styled(check, value_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(format!(" {:<10} ", "Apply to"), label_style(PropField::ApplyTo)),
+        Span::styled(props.apply_to.label(), value_style),
+    ]));
+    lines.push(Line::from(""));
+    lines.push(match &props.error {
+        Some(err) => Line::from(Span::styled(format!(" ✗ {}", err), Style::default().fg(Color::LightRed))),
+        None => Line::from(Span::styled(" Tab Next field │ Space Toggle │ Enter Apply changes │ Esc Close", dim)),
+    });
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Properties ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+            .style(Style::default().bg(bg_color)),
+    );
+    f.render_widget(popup, popup_area);
+}
+
+fn render_help_popup(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
     let popup_area = centered_rect(72, 85, area);
     f.render_widget(Clear, popup_area);
 
@@ -449,59 +1210,47 @@ fn render_help_popup(
     let desc_style = Style::default().fg(text_color);
     let dim = Style::default().fg(Color::DarkGray);
 
-    fn row<'a>(key: &'a str, desc: &'a str, key_style: Style, desc_style: Style) -> Line<'a> {
-        Line::from(vec![
-            Span::raw("  "),
-            Span::styled(format!("{:<18}", key), key_style),
-            Span::styled(desc, desc_style),
-        ])
+    let query = app.help_query.as_str();
+    let mut lines: Vec<Line> = Vec::new();
+    if !query.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  Filter: ", dim),
+            Span::styled(query.to_string(), Style::default().fg(Color::Yellow)),
+        ]));
+        lines.push(Line::from(""));
     }
 
-    let lines: Vec<Line> = vec![
-        Line::from(Span::styled("  Navigation", header)),
-        Line::from(Span::styled("  ──────────────────────────────────────────", dim)),
-        row("j / ↓",         "Move down",                               key_style, desc_style),
-        row("k / ↑",         "Move up",                                 key_style, desc_style),
-        row("l / → / Enter", "Open directory",                          key_style, desc_style),
-        row("h / ← / Bksp",  "Go to parent directory",                  key_style, desc_style),
-        row("← Shift",       "Shrink center panel",                     key_style, desc_style),
-        row("→ Shift",       "Expand center panel",                     key_style, desc_style),
-        Line::from(""),
-        Line::from(Span::styled("  File Operations", header)),
-        Line::from(Span::styled("  ──────────────────────────────────────────", dim)),
-        row("a",             "Create new file/folder (/ = folder)",     key_style, desc_style),
-        row("r",             "Rename selected item",                    key_style, desc_style),
-        row("D",             "Delete selected item (with confirm)",     key_style, desc_style),
-        row("Space",         "Toggle selection",                        key_style, desc_style),
-        row("s",             "Cycle sort: Name / Size / Date",          key_style, desc_style),
-        row("e",             "Open in $EDITOR",                        key_style, desc_style),
-        row("y",             "Copy to clipboard",                       key_style, desc_style),
-        row("x",             "Cut (move) to clipboard",                 key_style, desc_style),
-        row("p",             "Paste clipboard here",                    key_style, desc_style),
-        Line::from(""),
-        Line::from(Span::styled("  Favorites", header)),
-        Line::from(Span::styled("  ──────────────────────────────────────────", dim)),
-        row("f",             "Add selected to Favorites",               key_style, desc_style),
-        row("D  (Fav panel)","Remove from Favorites",                   key_style, desc_style),
-        row("Enter (Fav)",   "Navigate to favorited item",              key_style, desc_style),
-        Line::from(""),
-        Line::from(Span::styled("  Search", header)),
-        Line::from(Span::styled("  ──────────────────────────────────────────", dim)),
-        row("/",             "Start search / filter",                   key_style, desc_style),
-        row("Esc",           "Cancel search",                           key_style, desc_style),
-        Line::from(""),
-        Line::from(Span::styled("  Global", header)),
-        Line::from(Span::styled("  ──────────────────────────────────────────", dim)),
-        row("Tab",           "Switch focus: Files → Drives → Favorites",key_style, desc_style),
-        row("Ctrl+H",        "Focus Drives panel",                      key_style, desc_style),
-        row("Ctrl+L",        "Focus Files panel",                       key_style, desc_style),
-        row("Ctrl+B",        "Toggle status bar",                       key_style, desc_style),
-        row("F5",            "Hot-reload config",                       key_style, desc_style),
-        row("?",             "Toggle this help popup",                  key_style, desc_style),
-        row("q",             "Quit",                                    key_style, desc_style),
-        Line::from(""),
-        Line::from(Span::styled("  Press ? or Esc to close", dim)),
-    ];
+    let mut matched = false;
+    for (section, rows) in app.keymap.help_sections() {
+        let rows: Vec<&(String, String)> = rows
+            .iter()
+            .filter(|(keys, desc)| fuzzy_match(keys, query) || fuzzy_match(desc, query) || fuzzy_match(&section, query))
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        matched = true;
+        lines.push(Line::from(Span::styled(format!("  {}", section), header)));
+        lines.push(Line::from(Span::styled("  ──────────────────────────────────────────", dim)));
+        for (keys, desc) in rows {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(format!("{:<24} ", keys), key_style),
+                Span::styled(desc.clone(), desc_style),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+
+    if !matched {
+        lines.push(Line::from(Span::styled("  Nothing matches", dim)));
+        lines.push(Line::from(""));
+    }
+    let close_key = app.keymap.key_for("help").unwrap_or_else(|| "?".to_string());
+    lines.push(Line::from(Span::styled(
+        format!("  Type to filter │ ↑↓ Scroll │ {} or Esc to close", close_key),
+        dim,
+    )));
 
     let help = Paragraph::new(lines)
         .block(
@@ -512,13 +1261,559 @@ fn render_help_popup(
                 .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
                 .style(Style::default().bg(bg_color)),
         )
-        .scroll((scroll, 0))
+        .scroll((app.help_scroll, 0))
         .wrap(Wrap { trim: false });
 
     f.render_widget(help, popup_area);
 }
 
-fn format_size(size: u64) -> String {
+fn render_checksums(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    use crate::checksum::{Checksums, Outcome};
+    let Some(popup) = app.checksums.as_ref() else {
+        return;
+    };
+    let Ok(progress) = popup.progress.lock() else {
+        return;
+    };
+    let popup_area = centered_rect(80, 70, area);
+    f.render_widget(Clear, popup_area);
+    let dim = Style::default().fg(Color::DarkGray);
+    let inner_width = popup_area.width.saturating_sub(2) as usize;
+
+    // Шапка: прогресс или итог проверки
+    let mut header: Vec<Line> = Vec::new();
+    if !progress.finished {
+        let ratio = if progress.total_bytes == 0 { 0.0 } else { progress.done_bytes as f64 / progress.total_bytes as f64 };
+        let bar_width = inner_width.saturating_sub(30).min(40);
+        let filled = ((bar_width as f64) * ratio.min(1.0)) as usize;
+        header.push(Line::from(vec![
+            Span::styled(format!(" {}", "█".repeat(filled)), Style::default().fg(accent)),
+            Span::styled("░".repeat(bar_width - filled), dim),
+            Span::styled(
+                format!(" {:>3}%  {} / {}", (ratio * 100.0) as u32, format_size(progress.done_bytes), format_size(progress.total_bytes)),
+                Style::default().fg(text_color),
+            ),
+        ]));
+        header.push(Line::from(Span::styled(format!(" {}/{}  {}", progress.results.len() + 1, popup.files, progress.current), dim)));
+    } else if popup.sums_file.is_some() {
+        let (ok, failed, missing, errors) = Checksums::counts(&progress.results);
+        let mut spans = vec![
+            Span::styled(format!(" {} OK", ok), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {} FAILED", failed), Style::default().fg(if failed > 0 { Color::LightRed } else { Color::DarkGray })),
+            Span::styled(format!("  {} MISSING", missing), Style::default().fg(if missing > 0 { Color::Yellow } else { Color::DarkGray })),
+        ];
+        if errors > 0 {
+            spans.push(Span::styled(format!("  {} unreadable", errors), Style::default().fg(Color::Magenta)));
+        }
+        header.push(Line::from(spans));
+    } else if popup.skipped_dirs > 0 {
+        header.push(Line::from(Span::styled(format!(" Skipped {} director(ies)", popup.skipped_dirs), dim)));
+    }
+
+    // Список: файл и суммы под ним или строка статуса проверки
+    let rows = Checksums::rows(&progress.results);
+    let selected_row = rows.get(popup.selected).copied();
+    let mut lines: Vec<Line> = Vec::new();
+    let mut selected_line = 0;
+    for (i, result) in progress.results.iter().enumerate() {
+        let row_style = |alg: Option<usize>| {
+            if selected_row == Some((i, alg)) { Style::default().bg(Color::DarkGray) } else { Style::default() }
+        };
+        match &result.outcome {
+            Outcome::Digests(digests) => {
+                lines.push(Line::from(Span::styled(format!(" {}", result.name), Style::default().fg(text_color).add_modifier(Modifier::BOLD))));
+                for (j, (alg, hex)) in digests.iter().enumerate() {
+                    if selected_row == Some((i, Some(j))) {
+                        selected_line = lines.len();
+                    }
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("   {:<8} ", alg.label()), row_style(Some(j)).fg(text_color)),
+                        Span::styled(hex.clone(), row_style(Some(j)).fg(Color::Yellow)),
+                    ]));
+                }
+            }
+            outcome => {
+                if selected_row == Some((i, None)) {
+                    selected_line = lines.len();
+                }
+                let color = match outcome {
+                    Outcome::Ok => Color::Green,
+                    Outcome::Failed => Color::LightRed,
+                    Outcome::Missing => Color::Yellow,
+                    _ => Color::Magenta,
+                };
+                let label = match outcome {
+                    Outcome::Error(_) => "ERROR",
+                    other => other.label(),
+                };
+                let mut spans = vec![
+                    Span::styled(format!(" {:<8}", label), row_style(None).fg(color).add_modifier(Modifier::BOLD)),
+                    Span::styled(result.name.clone(), row_style(None).fg(text_color)),
+                ];
+                if let Outcome::Error(e) = outcome {
+                    spans.push(Span::styled(format!("  {}", e), row_style(None).fg(Color::DarkGray)));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+    }
+    if lines.is_empty() && progress.finished {
+        lines.push(Line::from(Span::styled(" Nothing to show", dim)));
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(header.len() as u16), Constraint::Min(1), Constraint::Length(1)])
+        .split(popup_area.inner(ratatui::layout::Margin { horizontal: 1, vertical: 1 }));
+    let title = match &popup.sums_file {
+        Some(name) => format!(" Verify: {} ", name),
+        None => format!(" Checksums ({} file(s)) ", popup.files),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    f.render_widget(block, popup_area);
+    f.render_widget(Paragraph::new(header), chunks[0]);
+    let visible = chunks[1].height as usize;
+    let scroll = selected_line.saturating_sub(visible.saturating_sub(1));
+    f.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), chunks[1]);
+    let hint = if progress.finished { " j/k Move │ y Copy │ Y Copy all │ Esc Close" } else { " j/k Move │ y Copy │ Esc Cancel" };
+    f.render_widget(Paragraph::new(Line::from(Span::styled(hint, dim))), chunks[2]);
+}
+
+/// Адрес раздачи и QR-код: две строки модулей в одной ячейке (`▀`), белые поля по краям
+fn render_share(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    use std::sync::atomic::Ordering;
+    let Some(job) = app.share.as_ref() else {
+        return;
+    };
+    let dim = Style::default().fg(Color::DarkGray);
+    let stats = &job.stats;
+    let mut info = vec![
+        Line::from(Span::styled(job.url.clone(), Style::default().fg(accent).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(job.label.clone(), Style::default().fg(text_color))),
+        Line::from(Span::styled(
+            format!(
+                "Upload {} │ Password {}",
+                if job.upload { "on" } else { "off" },
+                job.user.as_deref().map_or("off".to_string(), |u| format!("on ({})", u))
+            ),
+            Style::default().fg(text_color),
+        )),
+        Line::from(Span::styled(
+            format!(
+                "{} request(s) │ {} sent │ {} upload(s)",
+                stats.requests.load(Ordering::Relaxed),
+                format_size(stats.sent.load(Ordering::Relaxed)),
+                stats.uploads.load(Ordering::Relaxed)
+            ),
+            dim,
+        )),
+        Line::from(""),
+        Line::from(Span::styled("s Stop │ y Copy URL │ Esc Close", dim)),
+    ];
+    let text_width = info.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+
+    // QR с полем в 2 модуля; не влезает в окно — только текст
+    const QUIET: usize = 2;
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(modules) = &job.qr {
+        let size = modules.len() + 2 * QUIET;
+        let dark = |x: usize, y: usize| {
+            x >= QUIET && y >= QUIET && x < size - QUIET && y < size - QUIET && modules[y - QUIET][x - QUIET]
+        };
+        let rows = size.div_ceil(2);
+        if (rows + info.len() + 3) as u16 <= area.height && size as u16 + 4 <= area.width {
+            for row in 0..rows {
+                let spans: Vec<Span> = (0..size)
+                    .map(|x| {
+                        let color = |d: bool| if d { Color::Black } else { Color::White };
+                        let bottom = 2 * row + 1 < size && dark(x, 2 * row + 1);
+                        Span::styled("▀", Style::default().fg(color(dark(x, 2 * row))).bg(color(bottom)))
+                    })
+                    .collect();
+                lines.push(Line::from(spans));
+            }
+            lines.push(Line::from(""));
+        } else {
+            info.push(Line::from(Span::styled("Enlarge the window to see the QR code", dim)));
+        }
+    }
+    let qr_width = lines.first().map_or(0, |l| l.width()) as u16;
+    lines.extend(info);
+
+    let width = (qr_width.max(text_width) + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup_area = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .title(" Share ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    let paragraph = Paragraph::new(lines).block(block).alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Пароль для раздачи: вместо символов точки
+fn render_share_prompt(f: &mut Frame, area: Rect, app: &App, bg_color: Color, text_color: Color, accent: Color) {
+    let Some(prompt) = app.share_prompt.as_ref() else {
+        return;
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(format!("Password for {}: ", prompt.user), Style::default().fg(text_color)),
+            Span::styled("•".repeat(prompt.password.chars().count()), Style::default().fg(accent)),
+            Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("Enter Start sharing │ Esc Cancel", Style::default().fg(Color::DarkGray))),
+    ];
+    let width = (lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16 + 4).max(40).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup_area = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .title(" Share ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    f.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+/// Анализ места: доли детей текущего каталога полосками
+fn render_disk_usage(f: &mut Frame, area: Rect, app: &mut App, bg_color: Color, text_color: Color, sel_bg: Color, sel_fg: Color) {
+    let theme = &app.config.theme;
+    let dir_color = parse_color(&theme.directory);
+    let file_color = parse_color(&theme.file);
+    let highlight_symbol = theme.highlight_symbol.clone();
+    let Some(view) = app.disk_usage.as_mut() else {
+        return;
+    };
+    let popup_area = centered_rect(90, 85, area);
+    f.render_widget(Clear, popup_area);
+    let dim = Style::default().fg(Color::DarkGray);
+    let finished = view.finished();
+    let cache = view.cache.clone();
+    let Ok(cache) = cache.lock() else {
+        return;
+    };
+    let usage = cache.get(&view.current).filter(|_| finished);
+
+    let title = match usage {
+        Some(u) => format!(" Disk usage: {} — {} in {} file(s) ", view.current.display(), format_size(u.size), u.files),
+        None => format!(" Disk usage: {} ", view.current.display()),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(sel_bg).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let Some(usage) = usage else {
+        let Ok(progress) = view.progress.lock() else {
+            return;
+        };
+        let mut lines = vec![Line::from(Span::styled(
+            format!(" Scanning… {} file(s), {}", progress.files, format_size(progress.bytes)),
+            Style::default().fg(text_color).add_modifier(Modifier::BOLD),
+        ))];
+        lines.push(Line::from(Span::styled(format!(" {}", progress.current), dim)));
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+        f.render_widget(Paragraph::new(Line::from(Span::styled(" Esc Cancel", dim))), chunks[1]);
+        return;
+    };
+
+    const BAR: usize = 20;
+    let name_width = (chunks[0].width as usize).saturating_sub(BAR + 25 + highlight_symbol.chars().count());
+    let items: Vec<ListItem> = if usage.entries.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(" Empty folder", dim)))]
+    } else {
+        usage
+            .entries
+            .iter()
+            .map(|e| {
+                let share = if usage.size == 0 { 0.0 } else { e.size as f64 / usage.size as f64 };
+                let filled = ((share * BAR as f64).round() as usize).min(BAR);
+                let path = view.current.join(&e.name);
+                let (icon, icon_color) = if e.is_dir {
+                    (get_icon(&path, true), dir_color)
+                } else {
+                    (get_icon(&path, false), get_icon_color(&path).unwrap_or(file_color))
+                };
+                let mut name = if e.is_dir { format!("{}/", e.name) } else { e.name.clone() };
+                if name.chars().count() > name_width {
+                    name = name.chars().take(name_width.saturating_sub(1)).collect::<String>() + "…";
+                }
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(" {:>9} ", format_size(e.size)), Style::default().fg(text_color)),
+                    Span::styled(format!("{:>5.1}% ", share * 100.0), dim),
+                    Span::styled("█".repeat(filled), Style::default().fg(sel_bg)),
+                    Span::styled("░".repeat(BAR - filled), dim),
+                    Span::raw(" "),
+                    Span::styled(icon, Style::default().fg(icon_color)),
+                    Span::raw(" "),
+                    Span::styled(name, Style::default().fg(text_color)),
+                ]))
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .style(Style::default().bg(bg_color))
+        .highlight_style(Style::default().bg(sel_bg).fg(sel_fg).add_modifier(Modifier::BOLD))
+        .highlight_symbol(highlight_symbol.as_str());
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    let hint = if view.confirm_delete.is_some() {
+        Span::styled(" Delete? (y/N)", Style::default().fg(Color::LightRed))
+    } else {
+        Span::styled(" Enter/l Open │ h/Backspace Up │ d Delete │ Esc Close", dim)
+    };
+    f.render_widget(Paragraph::new(Line::from(hint)), chunks[1]);
+}
+
+/// Дубликаты: строки как в списке файлов, группы с заголовками
+fn render_dupes(f: &mut Frame, area: Rect, app: &mut App, bg_color: Color, text_color: Color, sel_bg: Color, sel_fg: Color) {
+    use crate::dupes::DupeRow;
+    let theme = &app.config.theme;
+    let file_base_color = parse_color(&theme.file);
+    let highlight_symbol = theme.highlight_symbol.clone();
+    let Some(view) = app.dupes.as_mut() else {
+        return;
+    };
+    let popup_area = centered_rect(90, 85, area);
+    f.render_widget(Clear, popup_area);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let root = view.root.to_string_lossy().to_string();
+    let (marked, marked_bytes) = view.marked_total();
+    let title = if view.loaded {
+        let mut title = format!(" Duplicates in {} — {} group(s), {} reclaimable ", root, view.groups.len(), format_size(view.reclaimable()));
+        if marked > 0 {
+            title.push_str(&format!("│ {} marked, {} ", marked, format_size(marked_bytes)));
+        }
+        title
+    } else {
+        format!(" Duplicates in {} ", root)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(sel_bg).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    // Пока идёт скан — этап и прогресс
+    if !view.loaded {
+        let Ok(progress) = view.progress.lock() else {
+            return;
+        };
+        let mut lines = vec![Line::from(Span::styled(
+            format!(" {} — {} file(s) seen", if progress.phase.is_empty() { "Starting" } else { progress.phase }, progress.files_seen),
+            Style::default().fg(text_color).add_modifier(Modifier::BOLD),
+        ))];
+        if progress.total_bytes > 0 {
+            let ratio = (progress.done_bytes as f64 / progress.total_bytes as f64).min(1.0);
+            let bar_width = (chunks[0].width as usize).saturating_sub(30).min(50);
+            let filled = (bar_width as f64 * ratio) as usize;
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {}", "█".repeat(filled)), Style::default().fg(sel_bg)),
+                Span::styled("░".repeat(bar_width - filled), dim),
+                Span::styled(
+                    format!(" {:>3}%  {} / {}", (ratio * 100.0) as u32, format_size(progress.done_bytes), format_size(progress.total_bytes)),
+                    Style::default().fg(text_color),
+                ),
+            ]));
+        }
+        lines.push(Line::from(Span::styled(format!(" {}", progress.current), dim)));
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+        f.render_widget(Paragraph::new(Line::from(Span::styled(" Esc Cancel", dim))), chunks[1]);
+        return;
+    }
+
+    let name_width = (chunks[0].width as usize).saturating_sub(30 + highlight_symbol.chars().count());
+    let items: Vec<ListItem> = if view.groups.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(" No duplicates found", dim)))]
+    } else {
+        view.rows()
+            .into_iter()
+            .map(|row| match row {
+                DupeRow::Group(g) => {
+                    let group = &view.groups[g];
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} copies × {}", group.files.len(), format_size(group.size)), Style::default().fg(sel_bg).add_modifier(Modifier::BOLD)),
+                        Span::styled(format!("  ({} reclaimable)", format_size(group.reclaimable())), dim),
+                    ]))
+                }
+                DupeRow::File(g, i) => {
+                    let file = &view.groups[g].files[i];
+                    let shown = file.path.strip_prefix(&view.root).unwrap_or(&file.path).to_string_lossy().to_string();
+                    let shown = if shown.chars().count() > name_width {
+                        let tail: String = shown.chars().rev().take(name_width.saturating_sub(1)).collect::<Vec<_>>().into_iter().rev().collect();
+                        format!("…{}", tail)
+                    } else {
+                        shown
+                    };
+                    let is_marked = view.marked.contains(&file.path);
+                    let name_style = if is_marked {
+                        Style::default().fg(Color::LightRed).add_modifier(Modifier::CROSSED_OUT)
+                    } else {
+                        Style::default().fg(text_color)
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(if is_marked { " [x]" } else { " [ ]" }, Style::default().fg(text_color)),
+                        Span::styled(get_icon(&file.path, false), Style::default().fg(get_icon_color(&file.path).unwrap_or(file_base_color))),
+                        Span::raw(" "),
+                        Span::styled(format!("{:<width$}", shown, width = name_width), name_style),
+                        Span::styled(format!(" {:>8} {}", format_size(view.groups[g].size), format_date(file.modified)), Style::default().fg(text_color)),
+                    ]))
+                }
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .style(Style::default().bg(bg_color))
+        .highlight_style(Style::default().bg(sel_bg).fg(sel_fg).add_modifier(Modifier::BOLD))
+        .highlight_symbol(highlight_symbol.as_str());
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    let hint = " Space Mark │ o/n/s Keep oldest/newest/shortest │ u Unmark │ d Trash │ h Hard-link │ Enter Reveal │ Esc Close";
+    f.render_widget(Paragraph::new(Line::from(Span::styled(hint, dim))), chunks[1]);
+}
+
+/// Очистка: категории с заголовками, под ними найденные пути
+fn render_cleanup(f: &mut Frame, area: Rect, app: &mut App, bg_color: Color, text_color: Color, sel_bg: Color, sel_fg: Color) {
+    use crate::cleanup::CleanupRow;
+    let theme = &app.config.theme;
+    let dir_color = parse_color(&theme.directory);
+    let file_base_color = parse_color(&theme.file);
+    let highlight_symbol = theme.highlight_symbol.clone();
+    let Some(view) = app.cleanup.as_mut() else {
+        return;
+    };
+    let popup_area = centered_rect(90, 85, area);
+    f.render_widget(Clear, popup_area);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let root = view.root.to_string_lossy().to_string();
+    let title = if view.loaded {
+        let (marked, marked_bytes) = view.marked_total();
+        format!(" Cleanup {} │ {} marked, {} ", root, marked, format_size(marked_bytes))
+    } else {
+        format!(" Cleanup {} ", root)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(sel_bg).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg_color));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    if !view.loaded {
+        let Ok(progress) = view.progress.lock() else {
+            return;
+        };
+        let lines = vec![
+            Line::from(Span::styled(
+                format!(" Scanning… {} folder(s), {} found", progress.dirs, progress.found),
+                Style::default().fg(text_color).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(Span::styled(format!(" {}", progress.current), dim)),
+        ];
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+        f.render_widget(Paragraph::new(Line::from(Span::styled(" Esc Cancel", dim))), chunks[1]);
+        return;
+    }
+
+    let name_width = (chunks[0].width as usize).saturating_sub(20 + highlight_symbol.chars().count());
+    let items: Vec<ListItem> = if view.groups.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(" Nothing to clean up", dim)))]
+    } else {
+        view.rows()
+            .into_iter()
+            .map(|row| match row {
+                CleanupRow::Group(g) => {
+                    let group = &view.groups[g];
+                    ListItem::new(Line::from(vec![
+                        Span::styled(group.category.label(), Style::default().fg(sel_bg).add_modifier(Modifier::BOLD)),
+                        Span::styled(format!("  {} item(s), {}", group.items.len(), format_size(group.size())), dim),
+                    ]))
+                }
+                CleanupRow::Item(g, i) => {
+                    let item = &view.groups[g].items[i];
+                    let mut shown = item.path.strip_prefix(&view.root).unwrap_or(&item.path).to_string_lossy().to_string();
+                    if item.is_dir {
+                        shown.push('/');
+                    }
+                    let shown = if shown.chars().count() > name_width {
+                        let tail: String = shown.chars().rev().take(name_width.saturating_sub(1)).collect::<Vec<_>>().into_iter().rev().collect();
+                        format!("…{}", tail)
+                    } else {
+                        shown
+                    };
+                    let is_marked = view.marked.contains(&item.path);
+                    let name_style = if is_marked {
+                        Style::default().fg(Color::LightRed).add_modifier(Modifier::CROSSED_OUT)
+                    } else {
+                        Style::default().fg(text_color)
+                    };
+                    let (icon, icon_color) = if item.is_dir {
+                        (get_icon(&item.path, true), dir_color)
+                    } else {
+                        (get_icon(&item.path, false), get_icon_color(&item.path).unwrap_or(file_base_color))
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(if is_marked { " [x]" } else { " [ ]" }, Style::default().fg(text_color)),
+                        Span::styled(icon, Style::default().fg(icon_color)),
+                        Span::raw(" "),
+                        Span::styled(format!("{:<width$}", shown, width = name_width), name_style),
+                        Span::styled(format!(" {:>9}", format_size(item.size)), Style::default().fg(text_color)),
+                    ]))
+                }
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .style(Style::default().bg(bg_color))
+        .highlight_style(Style::default().bg(sel_bg).fg(sel_fg).add_modifier(Modifier::BOLD))
+        .highlight_symbol(highlight_symbol.as_str());
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    let hint = " Space Mark (on a header: whole category) │ a Mark all │ u Unmark │ d Trash marked │ Enter Reveal │ Esc Close";
+    f.render_widget(Paragraph::new(Line::from(Span::styled(hint, dim))), chunks[1]);
+}
+
+fn spinner_frame() -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let ms = std::time::SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    FRAMES[(ms / 100 % FRAMES.len() as u128) as usize]
+}
+
+pub fn format_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut s = size as f64;
     let mut i = 0;
@@ -536,43 +1831,49 @@ fn format_size(size: u64) -> String {
 fn format_date(modified: Option<std::time::SystemTime>) -> String {
     match modified {
         Some(time) => {
-            let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
-            let total_secs = duration.as_secs();
-            let days = total_secs / 86400;
+            let (y, m, d, ..) = date_parts(time);
+            format!("{:04}-{:02}-{:02}", y, m, d)
+        }
+        None => String::new(),
+    }
+}
 
-            let mut y = 1970i64;
-            let mut remaining = days as i64;
+/// Дата и время (UTC): год, месяц, день, часы, минуты, секунды
+pub fn date_parts(time: std::time::SystemTime) -> (i64, i64, i64, u64, u64, u64) {
+    let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let total_secs = duration.as_secs();
+    let days = total_secs / 86400;
+    let secs_of_day = total_secs % 86400;
 
-            loop {
-                let days_in_year = if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 { 366 } else { 365 };
-                if remaining < days_in_year {
-                    break;
-                }
-                remaining -= days_in_year;
-                y += 1;
-            }
+    let mut y = 1970i64;
+    let mut remaining = days as i64;
 
-            let leap = (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
-            let months_days: &[i64] = if leap {
-                &[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-            } else {
-                &[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-            };
+    loop {
+        let days_in_year = if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 { 366 } else { 365 };
+        if remaining < days_in_year {
+            break;
+        }
+        remaining -= days_in_year;
+        y += 1;
+    }
 
-            let mut m = 1i64;
-            for &md in months_days {
-                if remaining < md {
-                    break;
-                }
-                remaining -= md;
-                m += 1;
-            }
+    let leap = (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+    let months_days: &[i64] = if leap {
+        &[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+    } else {
+        &[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+    };
 
-            let d = remaining + 1;
-            format!("{:04}-{:02}-{:02}", y, m, d)
+    let mut m = 1i64;
+    for &md in months_days {
+        if remaining < md {
+            break;
         }
-        None => String::new(),
+        remaining -= md;
+        m += 1;
     }
+
+    (y, m, remaining + 1, secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60)
 }
 
 // Хелпер для центрирования попапов
@@ -0,0 +1,47 @@
+use crate::app::App;
+use crate::rename::{apply_renames, check_renames};
+use std::path::PathBuf;
+
+const UNDO_LIMIT: usize = 50;
+
+/// Отменяемое действие
+pub enum UndoEntry {
+    /// Пакет переименований `(старый, новый)`
+    Rename(Vec<(PathBuf, PathBuf)>),
+}
+
+impl App {
+    pub fn push_undo(&mut self, entry: UndoEntry) {
+        self.undo_stack.push(entry);
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Отменяет последнее действие из стека
+    pub fn undo(&mut self) {
+        let Some(entry) = self.undo_stack.pop() else {
+            self.message = "Nothing to undo".to_string();
+            return;
+        };
+        match entry {
+            UndoEntry::Rename(pairs) => {
+                let reversed: Vec<(PathBuf, PathBuf)> =
+                    pairs.iter().map(|(old, new)| (new.clone(), old.clone())).collect();
+                let res = check_renames(&reversed).and_then(|_| apply_renames(&reversed));
+                match res {
+                    Ok(n) => {
+                        self.tags_moved(&reversed);
+                        self.message = format!("Undone: rename of {} item(s)", n);
+                    }
+                    Err(e) => {
+                        self.message = format!("Undo failed: {}", e);
+                        self.undo_stack.push(UndoEntry::Rename(pairs));
+                    }
+                }
+            }
+        }
+        self.refresh_items();
+        self.update_preview();
+    }
+}
@@ -0,0 +1,431 @@
+//! Файловые провайдеры: список, сведения, чтение, запись, переименование, удаление, папки.
+//! `App` работает с файлами только через них — локальный диск, открытые архивы, sftp-серверы.
+
+use crate::app::App;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Сведения о записи, как их показывает список
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    /// У папок — только если провайдер знает размер поддерева
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    /// Цель символьной ссылки как записана
+    pub link_target: Option<PathBuf>,
+    /// Ссылка указывает в никуда
+    pub broken: bool,
+}
+
+/// Как открывать файл на запись
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WriteMode {
+    /// Создать или обнулить
+    Truncate,
+    /// Только новый файл; существующий — ошибка
+    CreateNew,
+    /// Создать, если нет, и дописывать
+    Append,
+}
+
+pub trait FileSystem: Send + Sync {
+    /// Чьи это файлы: у провайдеров с одним id общие пути, и переименование
+    /// или копия между ними — внутренние
+    fn id(&self) -> String;
+    /// Записи папки полными путями
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+    /// Идёт по ссылкам; у битой ссылки — сведения о ней самой и `broken`
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read>>;
+    fn write(&self, path: &Path, mode: WriteMode) -> io::Result<Box<dyn Write>>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    /// Папки удаляются вместе с содержимым
+    fn remove(&self, path: &Path) -> io::Result<()>;
+    fn mkdir(&self, path: &Path, parents: bool) -> io::Result<()>;
+
+    /// Записи папки со сведениями. Провайдеры, у которых сведения приходят вместе со списком,
+    /// отдают их без запроса на каждую запись.
+    fn entries(&self, dir: &Path) -> io::Result<Vec<(PathBuf, Option<Metadata>)>> {
+        Ok(self.list(dir)?.into_iter().map(|path| (path.clone(), self.metadata(&path).ok())).collect())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Копия файла внутри провайдера
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        io::copy(&mut self.read(from)?, &mut self.write(to, WriteMode::Truncate)?).map(drop)
+    }
+
+    /// Выгрузка записи (папки — целиком) на локальный диск, если провайдер умеет это быстрее,
+    /// чем по одному файлу. `None` — пусть копирует общий код.
+    fn export(&self, _path: &Path, _dest: &Path) -> Option<Result<(), String>> {
+        None
+    }
+
+    /// Локальный диск: xattr и прочее, что есть только у настоящих файлов
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Локальный диск через `std::fs`
+pub struct LocalFs;
+
+impl FileSystem for LocalFs {
+    fn id(&self) -> String {
+        "file://".to_string()
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?.map(|e| e.map(|e| e.path())).collect()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let link_target = fs::read_link(path).ok();
+        let meta = fs::metadata(path);
+        let broken = link_target.is_some() && meta.is_err();
+        let meta = meta.or_else(|_| fs::symlink_metadata(path))?;
+        Ok(Metadata {
+            is_dir: meta.is_dir(),
+            // Длина самого каталога ничего не значит
+            size: if meta.is_dir() { None } else { Some(meta.len()) },
+            modified: meta.modified().ok(),
+            link_target,
+            broken,
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn write(&self, path: &Path, mode: WriteMode) -> io::Result<Box<dyn Write>> {
+        let mut options = fs::OpenOptions::new();
+        match mode {
+            WriteMode::Truncate => options.write(true).create(true).truncate(true),
+            WriteMode::CreateNew => options.write(true).create_new(true),
+            WriteMode::Append => options.create(true).append(true),
+        };
+        Ok(Box::new(options.open(path)?))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        // Ссылку на папку удаляем как ссылку, а не содержимое папки
+        if fs::symlink_metadata(path)?.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }
+    }
+
+    fn mkdir(&self, path: &Path, parents: bool) -> io::Result<()> {
+        if parents { fs::create_dir_all(path) } else { fs::create_dir(path) }
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        // `fs::copy` сохраняет права
+        fs::copy(from, to).map(drop)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+impl App {
+    /// Провайдер, который показывает содержимое папки `dir`: открытый архив, сервер или обычный диск
+    pub fn fs_in(&self, dir: &Path) -> &dyn FileSystem {
+        if let Some((mount, _)) = self.archive_at(dir) {
+            return mount;
+        }
+        match self.remote_at(dir) {
+            Some(remote) => remote,
+            None => self.fs.as_ref(),
+        }
+    }
+
+    /// Провайдер записи `path`; сам файл архива принадлежит папке, где лежит
+    pub fn fs_for(&self, path: &Path) -> &dyn FileSystem {
+        path.parent().map_or(self.fs.as_ref(), |dir| self.fs_in(dir))
+    }
+}
+
+/// Копирует файл или папку целиком, в том числе между провайдерами; ссылки раскрываются
+pub fn copy_recursive(src_fs: &dyn FileSystem, src: &Path, dst_fs: &dyn FileSystem, dest: &Path, xattrs: bool) -> Result<(), String> {
+    if dst_fs.is_local()
+        && let Some(res) = src_fs.export(src, dest)
+    {
+        return res;
+    }
+    let err = |e: io::Error| format!("{}: {}", src.display(), e);
+    if src_fs.metadata(src).map_err(err)?.is_dir {
+        dst_fs.mkdir(dest, true).map_err(|e| e.to_string())?;
+        for child in src_fs.list(src).map_err(err)? {
+            let Some(name) = child.file_name() else { continue };
+            copy_recursive(src_fs, &child, dst_fs, &dest.join(name), xattrs)?;
+        }
+    } else if src_fs.id() == dst_fs.id() {
+        src_fs.copy_file(src, dest).map_err(err)?;
+    } else {
+        let mut out = dst_fs.write(dest, WriteMode::Truncate).map_err(|e| e.to_string())?;
+        io::copy(&mut src_fs.read(src).map_err(err)?, &mut out).map_err(err)?;
+        out.flush().map_err(|e| e.to_string())?;
+    }
+    #[cfg(unix)]
+    if xattrs && src_fs.is_local() && dst_fs.is_local() {
+        crate::xattr::copy_all(src, dest).map_err(|e| format!("xattrs: {}", e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = xattrs;
+    Ok(())
+}
+
+/// Новый файл во временной папке под непредсказуемым именем, доступный только владельцу.
+/// `create_new` не пойдёт по ссылке, подложенной в общий `/tmp` заранее
+pub fn temp_file(prefix: &str, suffix: &str) -> io::Result<(PathBuf, fs::File)> {
+    use std::hash::{BuildHasher, Hasher};
+    for _ in 0..16 {
+        // RandomState засевается случайно для каждого процесса
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_nanos()));
+        let path = std::env::temp_dir().join(format!("{}{:016x}{}", prefix, hasher.finish(), suffix));
+        let mut options = fs::OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "no free temporary file name"))
+}
+
+/// Дерево в памяти для тестов
+#[cfg(test)]
+pub mod memory {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// `None` — папка
+    type Tree = BTreeMap<PathBuf, Option<Vec<u8>>>;
+
+    #[derive(Clone)]
+    pub struct MemFs {
+        tree: Arc<Mutex<Tree>>,
+    }
+
+    impl Default for MemFs {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl MemFs {
+        /// Пустое дерево с корнем `/`
+        pub fn new() -> Self {
+            let mut tree = Tree::new();
+            tree.insert(PathBuf::from("/"), None);
+            MemFs { tree: Arc::new(Mutex::new(tree)) }
+        }
+
+        pub fn add_file(&self, path: &str, data: &str) {
+            let path = Path::new(path);
+            self.mkdir(path.parent().unwrap(), true).unwrap();
+            self.tree.lock().unwrap().insert(path.to_path_buf(), Some(data.as_bytes().to_vec()));
+        }
+
+        pub fn contents(&self, path: &str) -> Option<String> {
+            let tree = self.tree.lock().unwrap();
+            tree.get(Path::new(path))?.as_ref().map(|d| String::from_utf8_lossy(d).to_string())
+        }
+
+        fn node(&self, path: &Path) -> io::Result<Option<Vec<u8>>> {
+            self.tree.lock().unwrap().get(path).cloned().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+
+        fn parent_is_dir(&self, path: &Path) -> io::Result<()> {
+            match path.parent().map(|p| self.node(p)) {
+                Some(Ok(None)) => Ok(()),
+                Some(Ok(Some(_))) => Err(io::Error::other("parent is not a directory")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+    }
+
+    /// Данные попадают в дерево при закрытии
+    struct MemWriter {
+        tree: Arc<Mutex<Tree>>,
+        path: PathBuf,
+        data: Vec<u8>,
+    }
+
+    impl Write for MemWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.tree.lock().unwrap().insert(self.path.clone(), Some(self.data.clone()));
+            Ok(())
+        }
+    }
+
+    impl Drop for MemWriter {
+        fn drop(&mut self) {
+            let _ = self.flush();
+        }
+    }
+
+    impl FileSystem for MemFs {
+        fn id(&self) -> String {
+            format!("mem:{:p}", Arc::as_ptr(&self.tree))
+        }
+
+        fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+            if self.node(dir)?.is_some() {
+                return Err(io::Error::other("not a directory"));
+            }
+            let tree = self.tree.lock().unwrap();
+            Ok(tree.keys().filter(|p| p.parent() == Some(dir)).cloned().collect())
+        }
+
+        fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+            let node = self.node(path)?;
+            Ok(Metadata { is_dir: node.is_none(), size: node.map(|d| d.len() as u64), ..Default::default() })
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+            match self.node(path)? {
+                Some(data) => Ok(Box::new(io::Cursor::new(data))),
+                None => Err(io::Error::other("is a directory")),
+            }
+        }
+
+        fn write(&self, path: &Path, mode: WriteMode) -> io::Result<Box<dyn Write>> {
+            self.parent_is_dir(path)?;
+            let data = match (self.node(path), mode) {
+                (Ok(None), _) => return Err(io::Error::other("is a directory")),
+                (Ok(Some(_)), WriteMode::CreateNew) => return Err(io::Error::from(io::ErrorKind::AlreadyExists)),
+                (Ok(Some(data)), WriteMode::Append) => data,
+                _ => Vec::new(),
+            };
+            let writer = MemWriter { tree: self.tree.clone(), path: path.to_path_buf(), data };
+            writer.tree.lock().unwrap().insert(writer.path.clone(), Some(writer.data.clone()));
+            Ok(Box::new(writer))
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            self.node(from)?;
+            self.parent_is_dir(to)?;
+            let mut tree = self.tree.lock().unwrap();
+            let moved: Vec<PathBuf> = tree.keys().filter(|p| p.starts_with(from)).cloned().collect();
+            for old in moved {
+                let node = tree.remove(&old).unwrap_or_default();
+                let new = match old.strip_prefix(from) {
+                    Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+                    _ => to.to_path_buf(),
+                };
+                tree.insert(new, node);
+            }
+            Ok(())
+        }
+
+        fn remove(&self, path: &Path) -> io::Result<()> {
+            self.node(path)?;
+            self.tree.lock().unwrap().retain(|p, _| !p.starts_with(path));
+            Ok(())
+        }
+
+        fn mkdir(&self, path: &Path, parents: bool) -> io::Result<()> {
+            match self.node(path) {
+                Ok(None) if parents => return Ok(()),
+                Ok(_) => return Err(io::Error::from(io::ErrorKind::AlreadyExists)),
+                Err(_) => {}
+            }
+            match path.parent() {
+                Some(parent) if parents && !self.exists(parent) => self.mkdir(parent, true)?,
+                Some(_) => self.parent_is_dir(path)?,
+                None => {}
+            }
+            self.tree.lock().unwrap().insert(path.to_path_buf(), None);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::memory::MemFs;
+    use super::{FileSystem, LocalFs};
+    use crate::app::App;
+    use crate::config::Config;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::path::{Path, PathBuf};
+
+    fn select(app: &mut App, name: &str) {
+        let i = app.filtered_items.iter().position(|p| p.file_name().is_some_and(|n| n == name)).unwrap();
+        app.state.select(Some(i));
+        app.update_preview();
+    }
+
+    #[test]
+    fn app_works_through_provider() {
+        let mem = MemFs::new();
+        mem.add_file("/work/a.txt", "alpha");
+        mem.add_file("/work/docs/readme.md", "hi");
+        mem.add_file("/other/c.txt", "gamma");
+        let mut app = App::with_config(Config::default(), PathBuf::from("/work"));
+        app.fs = Box::new(mem.clone());
+        app.refresh_items();
+        assert_eq!(app.items, vec![PathBuf::from("/work/docs"), PathBuf::from("/work/a.txt")]);
+        assert!(app.meta_cache[Path::new("/work/docs")].is_dir);
+
+        select(&mut app, "a.txt");
+        assert_eq!(app.preview_content, "alpha");
+        select(&mut app, "docs");
+        assert!(app.preview_content.ends_with("- readme.md"));
+
+        app.create_input.set("new/{x,y}.txt");
+        app.create_item();
+        assert_eq!(mem.contents("/work/new/y.txt").as_deref(), Some(""));
+
+        select(&mut app, "a.txt");
+        app.rename_input.set("b.txt");
+        app.rename_item();
+        assert_eq!(mem.contents("/work/b.txt").as_deref(), Some("alpha"));
+        assert!(mem.contents("/work/a.txt").is_none());
+
+        select(&mut app, "docs");
+        app.copy_item();
+        app.current_dir = PathBuf::from("/other");
+        app.refresh_items();
+        app.paste_item();
+        assert_eq!(mem.contents("/other/docs/readme.md").as_deref(), Some("hi"));
+
+        select(&mut app, "c.txt");
+        app.delete_prompt();
+        app.handle_key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
+        assert!(mem.contents("/other/c.txt").is_none());
+
+        // Один провайдер — переименование, а не копия с удалением
+        assert_eq!(mem.id(), mem.clone().id());
+        assert_ne!(mem.id(), MemFs::new().id());
+        assert_eq!(LocalFs.id(), LocalFs.id());
+        select(&mut app, "docs");
+        app.cut_item();
+        app.current_dir = PathBuf::from("/work/new");
+        app.refresh_items();
+        app.paste_item();
+        assert_eq!(mem.contents("/work/new/docs/readme.md").as_deref(), Some("hi"));
+        assert!(mem.contents("/other/docs/readme.md").is_none());
+    }
+}
@@ -0,0 +1,467 @@
+use crate::app::App;
+use crate::line_edit::LineEditor;
+use crate::properties::name_for_id;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+const ACL_ACCESS: &str = "system.posix_acl_access";
+const ACL_DEFAULT: &str = "system.posix_acl_default";
+
+// --- Системные вызовы: у Linux и macOS разные сигнатуры ---
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(io::Error::other)
+}
+
+/// Повторяет вызов, пока буфер не окажется достаточным (размер мог вырасти между вызовами)
+fn read_sized(mut call: impl FnMut(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut buf = vec![0u8; size as usize];
+        let n = call(buf.as_mut_ptr().cast(), buf.len());
+        if n >= 0 {
+            buf.truncate(n as usize);
+            return Ok(buf);
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn list(path: &Path) -> io::Result<Vec<String>> {
+    let p = c_path(path)?;
+    let raw = read_sized(|buf, size| unsafe { libc::listxattr(p.as_ptr(), buf.cast(), size) })?;
+    Ok(split_names(&raw))
+}
+
+#[cfg(target_os = "macos")]
+pub fn list(path: &Path) -> io::Result<Vec<String>> {
+    let p = c_path(path)?;
+    let raw = read_sized(|buf, size| unsafe { libc::listxattr(p.as_ptr(), buf.cast(), size, 0) })?;
+    Ok(split_names(&raw))
+}
+
+#[cfg(target_os = "linux")]
+pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    read_sized(|buf, size| unsafe { libc::getxattr(p.as_ptr(), n.as_ptr(), buf, size) })
+}
+
+#[cfg(target_os = "macos")]
+pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    read_sized(|buf, size| unsafe { libc::getxattr(p.as_ptr(), n.as_ptr(), buf, size, 0, 0) })
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    let res = unsafe { libc::setxattr(p.as_ptr(), n.as_ptr(), value.as_ptr().cast(), value.len(), 0) };
+    if res < 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+}
+
+#[cfg(target_os = "macos")]
+pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    let res = unsafe { libc::setxattr(p.as_ptr(), n.as_ptr(), value.as_ptr().cast(), value.len(), 0, 0) };
+    if res < 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+}
+
+#[cfg(target_os = "linux")]
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    let res = unsafe { libc::removexattr(p.as_ptr(), n.as_ptr()) };
+    if res < 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+}
+
+#[cfg(target_os = "macos")]
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    let (p, n) = (c_path(path)?, c_name(name)?);
+    let res = unsafe { libc::removexattr(p.as_ptr(), n.as_ptr(), 0) };
+    if res < 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+}
+
+// Прочие Unix (BSD и т.п.) — без поддержки
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn list(_path: &Path) -> io::Result<Vec<String>> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn remove(_path: &Path, _name: &str) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+fn split_names(raw: &[u8]) -> Vec<String> {
+    raw.split(|b| *b == 0)
+        .filter(|n| !n.is_empty())
+        .map(|n| String::from_utf8_lossy(n).to_string())
+        .collect()
+}
+
+fn is_unsupported(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::Unsupported || err.raw_os_error() == Some(libc::ENOTSUP)
+}
+
+/// Переносит атрибуты при копировании. `security.*` (метки SELinux и т.п.)
+/// назначает сама система; ФС без поддержки xattr ошибкой не считается.
+pub fn copy_all(src: &Path, dest: &Path) -> io::Result<()> {
+    let names = match list(src) {
+        Ok(names) => names,
+        Err(e) if is_unsupported(&e) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for name in names.iter().filter(|n| !n.starts_with("security.")) {
+        let value = get(src, name)?;
+        match set(dest, name, &value) {
+            Err(e) if is_unsupported(&e) => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+/// Короткая сводка для превью: имена атрибутов и `+acl`, как у `ls -l`
+pub fn summary(path: &Path) -> Option<String> {
+    let names = list(path).ok()?;
+    let acl = names.iter().any(|n| n == ACL_ACCESS || n == ACL_DEFAULT);
+    let plain: Vec<&str> = names.iter().map(|n| n.as_str()).filter(|n| *n != ACL_ACCESS && *n != ACL_DEFAULT).collect();
+    match (plain.is_empty(), acl) {
+        (true, false) => None,
+        (true, true) => Some("+acl".to_string()),
+        (false, acl) => Some(format!("xattrs: {}{}", plain.join(", "), if acl { "  +acl" } else { "" })),
+    }
+}
+
+// --- Значения ---
+
+/// Текст, если значение — печатный UTF-8, иначе `0x…`
+pub fn display_value(value: &[u8], hex: bool) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) if !hex && !text.chars().any(|c| c.is_control() && c != '\n' && c != '\t') => text.to_string(),
+        // Завершающий NUL (частый у C-утилит) не показываем
+        Ok(text) if !hex && text.ends_with('\0') && !text[..text.len() - 1].chars().any(|c| c.is_control()) => {
+            text[..text.len() - 1].to_string()
+        }
+        _ => format!("0x{}", value.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+    }
+}
+
+/// Обратное к `display_value`: `0x…` с чётным числом hex-цифр — байты, иначе текст
+pub fn parse_value(text: &str) -> Vec<u8> {
+    if let Some(hex) = text.strip_prefix("0x")
+        && hex.len() % 2 == 0
+        && hex.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return (0..hex.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect();
+    }
+    text.as_bytes().to_vec()
+}
+
+// --- POSIX ACL (формат ядра Linux: версия 2, записи по 8 байт little-endian) ---
+
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+#[derive(Debug, PartialEq)]
+pub struct AclEntry {
+    tag: u16,
+    perm: u16,
+    id: u32,
+}
+
+pub fn decode_acl(raw: &[u8]) -> Option<Vec<AclEntry>> {
+    let version = u32::from_le_bytes(raw.get(..4)?.try_into().ok()?);
+    if version != 2 || !(raw.len() - 4).is_multiple_of(8) {
+        return None;
+    }
+    Some(
+        raw[4..]
+            .chunks_exact(8)
+            .map(|c| AclEntry {
+                tag: u16::from_le_bytes([c[0], c[1]]),
+                perm: u16::from_le_bytes([c[2], c[3]]),
+                id: u32::from_le_bytes([c[4], c[5], c[6], c[7]]),
+            })
+            .collect(),
+    )
+}
+
+fn rwx(perm: u16) -> String {
+    let bit = |mask: u16, c: char| if perm & mask != 0 { c } else { '-' };
+    [bit(4, 'r'), bit(2, 'w'), bit(1, 'x')].iter().collect()
+}
+
+/// Строки как у `getfacl`; маска ограничивает именованные записи и группу владельца
+pub fn format_acl(entries: &[AclEntry], prefix: &str) -> Vec<String> {
+    let mask = entries.iter().find(|e| e.tag == ACL_MASK).map(|e| e.perm);
+    entries
+        .iter()
+        .map(|e| {
+            let qualifier = match e.tag {
+                ACL_USER => name_for_id("/etc/passwd", e.id).unwrap_or_else(|| e.id.to_string()),
+                ACL_GROUP => name_for_id("/etc/group", e.id).unwrap_or_else(|| e.id.to_string()),
+                _ => String::new(),
+            };
+            let kind = match e.tag {
+                ACL_USER_OBJ | ACL_USER => "user",
+                ACL_GROUP_OBJ | ACL_GROUP => "group",
+                ACL_MASK => "mask",
+                ACL_OTHER => "other",
+                _ => "?",
+            };
+            let mut line = format!("{}{}:{}:{}", prefix, kind, qualifier, rwx(e.perm));
+            if let Some(mask) = mask
+                && matches!(e.tag, ACL_USER | ACL_GROUP | ACL_GROUP_OBJ)
+                && e.perm & !mask != 0
+            {
+                line.push_str(&format!("   #effective:{}", rwx(e.perm & mask)));
+            }
+            line
+        })
+        .collect()
+}
+
+/// ACL файла; без расширенного ACL — три записи из битов прав
+fn acl_lines(path: &Path) -> Vec<String> {
+    let mut lines = match get(path, ACL_ACCESS).ok().and_then(|raw| decode_acl(&raw)) {
+        Some(entries) => format_acl(&entries, ""),
+        None => {
+            let mode = std::fs::metadata(path).map(|m| m.mode()).unwrap_or(0);
+            vec![
+                format!("user::{}", rwx((mode >> 6) as u16 & 7)),
+                format!("group::{}", rwx((mode >> 3) as u16 & 7)),
+                format!("other::{}", rwx(mode as u16 & 7)),
+            ]
+        }
+    };
+    if let Some(entries) = get(path, ACL_DEFAULT).ok().and_then(|raw| decode_acl(&raw)) {
+        lines.extend(format_acl(&entries, "default:"));
+    }
+    lines
+}
+
+// --- Панель ---
+
+/// Редактирование атрибута: новый (`original == None`) или существующий
+pub struct XattrEdit {
+    pub name: LineEditor,
+    pub value: LineEditor,
+    pub on_value: bool,
+    pub original: Option<String>,
+}
+
+pub struct XattrPanel {
+    pub path: PathBuf,
+    pub entries: Vec<(String, Vec<u8>)>,
+    pub acl: Vec<String>,
+    pub selected: usize,
+    pub hex: bool,
+    pub edit: Option<XattrEdit>,
+    pub confirm_remove: bool,
+}
+
+impl XattrPanel {
+    fn load(path: PathBuf) -> io::Result<Self> {
+        let mut names = list(&path)?;
+        names.retain(|n| n != ACL_ACCESS && n != ACL_DEFAULT);
+        names.sort();
+        // Атрибут мог исчезнуть между list и get — такие пропускаем
+        let entries = names.into_iter().filter_map(|n| get(&path, &n).ok().map(|v| (n, v))).collect();
+        let acl = acl_lines(&path);
+        Ok(XattrPanel { path, entries, acl, selected: 0, hex: false, edit: None, confirm_remove: false })
+    }
+}
+
+impl App {
+    pub fn open_xattrs(&mut self) {
+        let Some(path) = self.state.selected().and_then(|i| self.filtered_items.get(i)).cloned() else {
+            return;
+        };
+        match XattrPanel::load(path) {
+            Ok(panel) => self.xattrs = Some(panel),
+            Err(e) if is_unsupported(&e) => self.message = "Extended attributes are not supported here".to_string(),
+            Err(e) => self.message = format!("Xattrs: {}", e),
+        }
+    }
+
+    /// Перечитывает панель, оставляя курсор на атрибуте `keep`
+    fn reload_xattrs(&mut self, keep: Option<&str>) {
+        let Some(old) = self.xattrs.take() else {
+            return;
+        };
+        match XattrPanel::load(old.path.clone()) {
+            Ok(mut panel) => {
+                panel.hex = old.hex;
+                panel.selected = keep
+                    .and_then(|k| panel.entries.iter().position(|(n, _)| n == k))
+                    .unwrap_or(old.selected.min(panel.entries.len().saturating_sub(1)));
+                self.xattrs = Some(panel);
+            }
+            Err(e) => self.message = format!("Xattrs: {}", e),
+        }
+        self.update_preview();
+    }
+
+    fn save_xattr(&mut self) {
+        let Some(panel) = self.xattrs.as_mut() else {
+            return;
+        };
+        let Some(edit) = panel.edit.as_ref() else {
+            return;
+        };
+        let name = edit.name.text().trim().to_string();
+        if name.is_empty() || !name.contains('.') {
+            self.message = "Attribute name needs a namespace, e.g. user.tag".to_string();
+            return;
+        }
+        let value = parse_value(edit.value.text());
+        let original = edit.original.clone();
+        let path = panel.path.clone();
+        let res = set(&path, &name, &value).and_then(|_| match &original {
+            // Переименование: новое имя уже записано, старое убираем
+            Some(old) if *old != name => remove(&path, old),
+            _ => Ok(()),
+        });
+        match res {
+            Ok(_) => {
+                self.message = format!("Set {}", name);
+                self.reload_xattrs(Some(&name));
+            }
+            Err(e) => self.message = format!("Xattr {}: {}", name, e),
+        }
+    }
+
+    fn remove_selected_xattr(&mut self) {
+        let Some(panel) = self.xattrs.as_mut() else {
+            return;
+        };
+        panel.confirm_remove = false;
+        let Some((name, _)) = panel.entries.get(panel.selected).cloned() else {
+            return;
+        };
+        match remove(&panel.path, &name) {
+            Ok(_) => {
+                self.message = format!("Removed {}", name);
+                self.reload_xattrs(None);
+            }
+            Err(e) => self.message = format!("Xattr {}: {}", name, e),
+        }
+    }
+
+    pub fn handle_xattr_input(&mut self, key: KeyEvent) {
+        let Some(panel) = self.xattrs.as_mut() else {
+            return;
+        };
+
+        if let Some(edit) = panel.edit.as_mut() {
+            match key.code {
+                KeyCode::Esc => panel.edit = None,
+                KeyCode::Enter => self.save_xattr(),
+                KeyCode::Tab | KeyCode::BackTab => edit.on_value = !edit.on_value,
+                _ => {
+                    let input = if edit.on_value { &mut edit.value } else { &mut edit.name };
+                    input.handle_key(key);
+                }
+            }
+            return;
+        }
+
+        if panel.confirm_remove {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.remove_selected_xattr(),
+                _ => panel.confirm_remove = false,
+            }
+            return;
+        }
+
+        let editor = |text: &str| {
+            let mut ed = LineEditor::default();
+            ed.set(text);
+            ed
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.xattrs = None,
+            KeyCode::Char('j') | KeyCode::Down if panel.selected + 1 < panel.entries.len() => panel.selected += 1,
+            KeyCode::Char('k') | KeyCode::Up => panel.selected = panel.selected.saturating_sub(1),
+            KeyCode::Char('x') => panel.hex = !panel.hex,
+            KeyCode::Char('a') => {
+                panel.edit = Some(XattrEdit { name: editor("user."), value: editor(""), on_value: false, original: None });
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some((name, value)) = panel.entries.get(panel.selected) {
+                    panel.edit = Some(XattrEdit {
+                        name: editor(name),
+                        value: editor(&display_value(value, panel.hex)),
+                        on_value: true,
+                        original: Some(name.clone()),
+                    });
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete if !panel.entries.is_empty() => panel.confirm_remove = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip() {
+        assert_eq!(display_value(b"blue", false), "blue");
+        assert_eq!(display_value(b"blue", true), "0x626c7565");
+        assert_eq!(display_value(&[0, 255], false), "0x00ff");
+        assert_eq!(parse_value("0x00ff"), vec![0, 255]);
+        assert_eq!(parse_value("0xabc"), b"0xabc".to_vec());
+        assert_eq!(parse_value("plain"), b"plain".to_vec());
+    }
+
+    #[test]
+    fn decodes_acl_with_mask() {
+        let mut raw = 2u32.to_le_bytes().to_vec();
+        for (tag, perm, id) in [(ACL_USER_OBJ, 6, u32::MAX), (ACL_USER, 7, 4242), (ACL_GROUP_OBJ, 4, u32::MAX), (ACL_MASK, 5, u32::MAX), (ACL_OTHER, 0, u32::MAX)] {
+            raw.extend(tag.to_le_bytes());
+            raw.extend((perm as u16).to_le_bytes());
+            raw.extend(id.to_le_bytes());
+        }
+        let lines = format_acl(&decode_acl(&raw).unwrap(), "");
+        assert_eq!(lines[0], "user::rw-");
+        assert!(lines[1].ends_with(":rwx   #effective:r-x"));
+        assert_eq!(lines[2], "group::r--");
+        assert_eq!(lines[3], "mask::r-x");
+        assert!(decode_acl(&[1, 0, 0, 0]).is_none());
+    }
+}